@@ -0,0 +1,551 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use wasmparser::{CompositeInnerType, Encoding, Export, ExternalKind, FuncType, MemoryType, Parser, Payload, TypeRef, ValType};
+use wasmtime::{Instance, Store, AsContextMut};
+
+use crate::{
+    codec::CODEC_EXPORT,
+    memory::{EXCHANGE_BUFFER_EXPORT, RESULT_BUFFER_EXPORT},
+    state::ModuleState,
+    error::{ModuleError, ModuleResult},
+};
+
+
+/// Optional guest export declaring the version of the binmod ABI implemented by the guest.
+///
+/// The export has the signature `() -> u32` and returns the version number.
+pub const ABI_VERSION_EXPORT: &str = "binmod_abi_version";
+
+/// Version of the binmod calling convention implemented by a guest.
+///
+/// Both versions exchange inputs and results through `guest_alloc` and a packed
+/// `(ptr << 32) | len` pointer, and differ in who frees the input buffer of a call.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum AbiVersion {
+    /// The callee takes ownership of the input buffer and frees it, and the
+    /// caller frees the result buffer. Assumed for guests not declaring a version.
+    #[default]
+    V1,
+    /// The caller keeps ownership of the input buffer and frees it once the
+    /// call returns, and frees the result buffer.
+    V2,
+}
+
+impl AbiVersion {
+    /// Every ABI version supported by the runtime.
+    pub const SUPPORTED: [AbiVersion; 2] = [AbiVersion::V1, AbiVersion::V2];
+
+    /// Get the version number declared by guests through `binmod_abi_version`.
+    pub fn number(&self) -> u32 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+        }
+    }
+
+    /// Get the ABI version for a version number.
+    ///
+    /// # Arguments
+    /// * `number` - The version number
+    ///
+    /// # Returns
+    /// The ABI version, or `None` if the version is not supported
+    pub fn from_number(number: u32) -> Option<Self> {
+        Self::SUPPORTED
+            .into_iter()
+            .find(|version| version.number() == number)
+    }
+
+    /// Check if the caller frees the input buffer of a call once it returns.
+    pub(crate) fn caller_frees_input(&self) -> bool {
+        matches!(self, Self::V2)
+    }
+}
+
+impl fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+
+/// Status of an export required or recognized by the binmod ABI.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ExportStatus {
+    /// The export is not present in the binary.
+    Missing,
+    /// The export is present and has the expected type.
+    Valid,
+    /// The export is present but has an unexpected type.
+    Invalid(String),
+}
+
+impl ExportStatus {
+    /// Check if the export is present and has the expected type.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid)
+    }
+}
+
+/// Report describing how a WebAssembly binary conforms to the binmod ABI.
+///
+/// A conforming binary exports a linear `memory`, `guest_alloc(u32) -> u32`,
+/// `guest_dealloc(u32, u32)`, and optionally `initialize`, `finalize` or `shutdown`, `binmod_abi_version() -> u32`,
+/// `binmod_codec(u32) -> u32`, `binmod_exchange_buffer(u32) -> u32` and
+/// `binmod_result_buffer(u32) -> u32`. Callable functions use the signature `(u32, u32) -> u64`.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::abi;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let report = abi::check(&std::fs::read("my_module.wasm")?);
+///
+/// if !report.is_conforming() {
+///     for issue in &report.issues {
+///         eprintln!("{}", issue);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AbiReport {
+    /// Status of the exported linear memory.
+    pub memory: ExportStatus,
+    /// Status of the `guest_alloc` export.
+    pub guest_alloc: ExportStatus,
+    /// Status of the `guest_dealloc` export.
+    pub guest_dealloc: ExportStatus,
+    /// Status of the optional `initialize` export.
+    ///
+    /// An `initialize` export with another signature than `(u32, u32) -> u64` is not
+    /// a binmod initializer. It is never called and is not a conformance issue.
+    pub initialize: ExportStatus,
+    /// Status of the optional `finalize` export invoked when the module shuts down.
+    ///
+    /// Like `initialize`, it is only a finalizer with the signature `(u32, u32) -> u64`.
+    pub finalize: ExportStatus,
+    /// Status of the optional `shutdown` export, invoked when the module shuts down
+    /// if there is no valid `finalize` export.
+    ///
+    /// Like `initialize`, it is only a finalizer with the signature `(u32, u32) -> u64`.
+    pub shutdown: ExportStatus,
+    /// Status of the optional `binmod_abi_version` export declaring the ABI version.
+    pub abi_version: ExportStatus,
+    /// Status of the optional `binmod_codec` export negotiating the wire format.
+    pub codec: ExportStatus,
+    /// Status of the optional `binmod_exchange_buffer` export providing a reusable buffer for call inputs.
+    pub exchange_buffer: ExportStatus,
+    /// Status of the optional `binmod_result_buffer` export providing a reusable buffer for host function results.
+    pub result_buffer: ExportStatus,
+    /// Names of the exported functions callable through the binmod ABI.
+    pub functions: Vec<String>,
+    /// Human readable descriptions of every conformance violation.
+    pub issues: Vec<String>,
+}
+
+impl AbiReport {
+    fn new() -> Self {
+        Self {
+            memory: ExportStatus::Missing,
+            guest_alloc: ExportStatus::Missing,
+            guest_dealloc: ExportStatus::Missing,
+            initialize: ExportStatus::Missing,
+            finalize: ExportStatus::Missing,
+            shutdown: ExportStatus::Missing,
+            abi_version: ExportStatus::Missing,
+            codec: ExportStatus::Missing,
+            exchange_buffer: ExportStatus::Missing,
+            result_buffer: ExportStatus::Missing,
+            functions: vec![],
+            issues: vec![],
+        }
+    }
+
+    /// Check if the binary conforms to the binmod ABI.
+    pub fn is_conforming(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Get the name of the guest finalizer invoked when the module shuts down, if any.
+    ///
+    /// A valid `finalize` export takes precedence over a valid `shutdown` export.
+    pub fn finalizer(&self) -> Option<&'static str> {
+        [("finalize", &self.finalize), ("shutdown", &self.shutdown)]
+            .into_iter()
+            .find(|(_, status)| status.is_valid())
+            .map(|(name, _)| name)
+    }
+
+    /// Check that the binary exports every given function through the binmod ABI.
+    /// 
+    /// # Arguments
+    /// * `names` - The names of the functions to check
+    /// 
+    /// # Returns
+    /// A result indicating success or an
+    /// [`AbiError`](crate::error::ModuleError::AbiError) listing every missing function
+    pub fn expect_functions(&self, names: &[&str]) -> ModuleResult<()> {
+        let missing = names
+            .iter()
+            .filter(|name| !self.functions.iter().any(|function| function == *name))
+            .map(|name| format!("missing function export `{}`", name))
+            .collect::<Vec<_>>();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ModuleError::AbiError(missing.join("; ")))
+        }
+    }
+
+    /// Convert the report into a result.
+    ///
+    /// # Returns
+    /// A result containing the report or an
+    /// [`AbiError`](crate::error::ModuleError::AbiError) listing every issue
+    /// if the binary does not conform to the binmod ABI
+    pub fn into_result(self) -> ModuleResult<Self> {
+        if self.is_conforming() {
+            Ok(self)
+        } else {
+            Err(ModuleError::AbiError(self.issues.join("; ")))
+        }
+    }
+}
+
+/// Check whether a WebAssembly binary conforms to the binmod ABI.
+///
+/// # Arguments
+/// * `binary` - The WebAssembly binary to check
+///
+/// # Returns
+/// An [`AbiReport`](crate::abi::AbiReport) describing the binary
+pub fn check(binary: &[u8]) -> AbiReport {
+    let mut report = AbiReport::new();
+
+    let parsed = match ParsedModule::parse(binary) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            report.issues.push(format!("failed to parse module: {}", e));
+            return report;
+        }
+    };
+
+    for export in &parsed.exports {
+        match (export.name, export.kind) {
+            ("memory", ExternalKind::Memory) => {
+                report.memory = match parsed.memories.get(export.index as usize) {
+                    Some(memory) if memory.memory64 => ExportStatus::Invalid("expected a 32-bit memory".to_string()),
+                    _ => ExportStatus::Valid,
+                };
+            },
+            ("memory", _) => {
+                report.memory = ExportStatus::Invalid("expected a memory export".to_string());
+            },
+            (name, ExternalKind::Func) => {
+                let ty = parsed.func_type(export.index);
+                let status = |params: &[ValType], results: &[ValType]| match ty {
+                    Some(ty) if ty.params() == params && ty.results() == results => ExportStatus::Valid,
+                    Some(ty) => ExportStatus::Invalid(format!(
+                        "expected {}, found {}",
+                        signature(params, results),
+                        signature(ty.params(), ty.results()),
+                    )),
+                    None => ExportStatus::Invalid("unknown function type".to_string()),
+                };
+
+                match name {
+                    "guest_alloc" => report.guest_alloc = status(&[ValType::I32], &[ValType::I32]),
+                    "guest_dealloc" => report.guest_dealloc = status(&[ValType::I32, ValType::I32], &[]),
+                    "initialize" => report.initialize = status(&[ValType::I32, ValType::I32], &[ValType::I64]),
+                    "finalize" => report.finalize = status(&[ValType::I32, ValType::I32], &[ValType::I64]),
+                    "shutdown" => report.shutdown = status(&[ValType::I32, ValType::I32], &[ValType::I64]),
+                    ABI_VERSION_EXPORT => report.abi_version = status(&[], &[ValType::I32]),
+                    CODEC_EXPORT => report.codec = status(&[ValType::I32], &[ValType::I32]),
+                    EXCHANGE_BUFFER_EXPORT => report.exchange_buffer = status(&[ValType::I32], &[ValType::I32]),
+                    RESULT_BUFFER_EXPORT => report.result_buffer = status(&[ValType::I32], &[ValType::I32]),
+                    _ => if status(&[ValType::I32, ValType::I32], &[ValType::I64]).is_valid() {
+                        report.functions.push(name.to_string());
+                    },
+                }
+            },
+            (name @ ("guest_alloc" | "guest_dealloc" | "initialize" | "finalize" | "shutdown" | ABI_VERSION_EXPORT | CODEC_EXPORT | EXCHANGE_BUFFER_EXPORT | RESULT_BUFFER_EXPORT), _) => {
+                let status = ExportStatus::Invalid("expected a function export".to_string());
+
+                match name {
+                    "guest_alloc" => report.guest_alloc = status,
+                    "guest_dealloc" => report.guest_dealloc = status,
+                    "initialize" => report.initialize = status,
+                    "finalize" => report.finalize = status,
+                    "shutdown" => report.shutdown = status,
+                    ABI_VERSION_EXPORT => report.abi_version = status,
+                    CODEC_EXPORT => report.codec = status,
+                    EXCHANGE_BUFFER_EXPORT => report.exchange_buffer = status,
+                    _ => report.result_buffer = status,
+                }
+            },
+            _ => {},
+        }
+    }
+
+    for (name, status, required) in [
+        ("memory", &report.memory, true),
+        ("guest_alloc", &report.guest_alloc, true),
+        ("guest_dealloc", &report.guest_dealloc, true),
+        (ABI_VERSION_EXPORT, &report.abi_version, false),
+        (CODEC_EXPORT, &report.codec, false),
+        (EXCHANGE_BUFFER_EXPORT, &report.exchange_buffer, false),
+        (RESULT_BUFFER_EXPORT, &report.result_buffer, false),
+    ] {
+        match status {
+            ExportStatus::Missing if required => report.issues.push(format!("missing export `{}`", name)),
+            ExportStatus::Invalid(reason) => report.issues.push(format!("invalid export `{}`: {}", name, reason)),
+            _ => {},
+        }
+    }
+
+    report
+}
+
+/// Resolve the ABI version of an instance through its optional `binmod_abi_version` export
+pub(crate) fn resolve_version(instance: &Instance, store: &mut Store<ModuleState>, configured: Option<AbiVersion>) -> ModuleResult<AbiVersion> {
+    let declared = match instance.get_func(store.as_context_mut(), ABI_VERSION_EXPORT) {
+        Some(func) => Some(
+            func
+                .typed::<(), u32>(store.as_context_mut())?
+                .call(store.as_context_mut(), ())
+                .map_err(|e| ModuleError::InstantiationError(format!("failed to read ABI version: {}", e)))?
+        ),
+        None => None,
+    };
+
+    select_version(declared, configured)
+}
+
+/// Resolve the ABI version of an asynchronous instance through its optional `binmod_abi_version` export
+pub(crate) async fn resolve_version_async(instance: &Instance, store: &mut Store<ModuleState>, configured: Option<AbiVersion>) -> ModuleResult<AbiVersion> {
+    let declared = match instance.get_func(store.as_context_mut(), ABI_VERSION_EXPORT) {
+        Some(func) => Some(
+            func
+                .typed::<(), u32>(store.as_context_mut())?
+                .call_async(store.as_context_mut(), ())
+                .await
+                .map_err(|e| ModuleError::InstantiationError(format!("failed to read ABI version: {}", e)))?
+        ),
+        None => None,
+    };
+
+    select_version(declared, configured)
+}
+
+/// Select the ABI version declared by the guest, falling back to the configured version
+fn select_version(declared: Option<u32>, configured: Option<AbiVersion>) -> ModuleResult<AbiVersion> {
+    let Some(number) = declared else {
+        return Ok(configured.unwrap_or_default());
+    };
+
+    let version = AbiVersion::from_number(number).ok_or_else(|| ModuleError::AbiError(format!(
+        "unsupported binmod ABI version {}, supported versions are {}",
+        number,
+        AbiVersion::SUPPORTED.map(|version| version.to_string()).join(", "),
+    )))?;
+
+    match configured {
+        Some(configured) if configured != version => Err(ModuleError::AbiError(format!(
+            "guest implements binmod ABI version {}, but version {} is configured",
+            version,
+            configured,
+        ))),
+        _ => Ok(version),
+    }
+}
+
+/// Format a function signature using the binmod ABI's unsigned integer types
+fn signature(params: &[ValType], results: &[ValType]) -> String {
+    let format = |types: &[ValType]| types
+        .iter()
+        .map(|ty| match ty {
+            ValType::I32 => "u32".to_string(),
+            ValType::I64 => "u64".to_string(),
+            ty => ty.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match results {
+        [] => format!("({})", format(params)),
+        [result] => format!("({}) -> {}", format(params), format(&[*result])),
+        _ => format!("({}) -> ({})", format(params), format(results)),
+    }
+}
+
+/// The subset of a parsed module needed to check ABI conformance
+struct ParsedModule<'a> {
+    types: Vec<Option<FuncType>>,
+    funcs: Vec<u32>,
+    memories: Vec<MemoryType>,
+    exports: Vec<Export<'a>>,
+}
+
+impl<'a> ParsedModule<'a> {
+    fn parse(binary: &'a [u8]) -> Result<Self, String> {
+        let mut parsed = Self {
+            types: vec![],
+            funcs: vec![],
+            memories: vec![],
+            exports: vec![],
+        };
+
+        for payload in Parser::new(0).parse_all(binary) {
+            match payload.map_err(|e| e.to_string())? {
+                Payload::Version { encoding: Encoding::Component, .. } => {
+                    return Err("binary is a component, not a core module".to_string());
+                },
+                Payload::TypeSection(reader) => {
+                    for group in reader {
+                        for ty in group.map_err(|e| e.to_string())?.into_types() {
+                            parsed.types.push(match ty.composite_type.inner {
+                                CompositeInnerType::Func(func) => Some(func),
+                                _ => None,
+                            });
+                        }
+                    }
+                },
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        match import.map_err(|e| e.to_string())?.ty {
+                            TypeRef::Func(index) => parsed.funcs.push(index),
+                            TypeRef::Memory(memory) => parsed.memories.push(memory),
+                            _ => {},
+                        }
+                    }
+                },
+                Payload::FunctionSection(reader) => {
+                    for index in reader {
+                        parsed.funcs.push(index.map_err(|e| e.to_string())?);
+                    }
+                },
+                Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        parsed.memories.push(memory.map_err(|e| e.to_string())?);
+                    }
+                },
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        parsed.exports.push(export.map_err(|e| e.to_string())?);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn func_type(&self, func_index: u32) -> Option<&FuncType> {
+        self.funcs
+            .get(func_index as usize)
+            .and_then(|type_index| self.types.get(*type_index as usize))
+            .and_then(|ty| ty.as_ref())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOC: &str = r#"
+        (memory (export "memory") 1)
+        (func (export "guest_alloc") (param i32) (result i32) (i32.const 0))
+        (func (export "guest_dealloc") (param i32 i32))
+    "#;
+
+    fn check_wat(exports: &str) -> AbiReport {
+        check(&wat::parse_str(format!("(module {} {})", ALLOC, exports)).unwrap())
+    }
+
+    #[test]
+    fn reports_conforming_module() {
+        let report = check_wat(r#"
+            (func (export "initialize") (param i32 i32) (result i64) (i64.const 0))
+            (func (export "add") (param i32 i32) (result i64) (i64.const 0))
+            (func (export "helper") (param i32))
+        "#);
+
+        assert!(report.is_conforming(), "{:?}", report.issues);
+        assert_eq!(report.initialize, ExportStatus::Valid);
+        assert_eq!(report.abi_version, ExportStatus::Missing);
+        assert_eq!(report.functions, ["add"]);
+        assert!(report.expect_functions(&["add"]).is_ok());
+        assert!(report.expect_functions(&["add", "sub"]).is_err());
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_exports() {
+        let report = check(&wat::parse_str(r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "guest_alloc") (param i64) (result i32) (i32.const 0))
+                (global (export "binmod_codec") i32 (i32.const 0)))
+        "#).unwrap());
+
+        assert_eq!(report.guest_dealloc, ExportStatus::Missing);
+        assert_eq!(report.guest_alloc, ExportStatus::Invalid("expected (u32) -> u32, found (u64) -> u32".to_string()));
+        assert_eq!(report.codec, ExportStatus::Invalid("expected a function export".to_string()));
+        assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+        assert!(report.into_result().is_err());
+    }
+
+    #[test]
+    fn ignores_initialize_with_other_signature() {
+        let report = check_wat(r#"(func (export "initialize"))"#);
+
+        assert!(report.is_conforming(), "{:?}", report.issues);
+        assert_eq!(report.initialize, ExportStatus::Invalid("expected (u32, u32) -> u64, found ()".to_string()));
+    }
+
+    #[test]
+    fn selects_finalizer_with_binmod_signature() {
+        let plain = check_wat(r#"(func (export "shutdown"))"#);
+        let both = check_wat(r#"
+            (func (export "finalize") (param i32 i32) (result i64) (i64.const 0))
+            (func (export "shutdown") (param i32 i32) (result i64) (i64.const 0))
+        "#);
+        let fallback = check_wat(r#"
+            (func (export "finalize"))
+            (func (export "shutdown") (param i32 i32) (result i64) (i64.const 0))
+        "#);
+
+        assert!(plain.is_conforming(), "{:?}", plain.issues);
+        assert_eq!(plain.finalizer(), None);
+        assert_eq!(both.finalizer(), Some("finalize"));
+        assert!(both.functions.is_empty(), "{:?}", both.functions);
+        assert_eq!(fallback.finalizer(), Some("shutdown"));
+    }
+
+    #[test]
+    fn reports_components_and_malformed_binaries() {
+        let component = check(&wat::parse_str("(component)").unwrap());
+        let malformed = check(b"not wasm");
+
+        assert!(component.issues[0].contains("component"), "{:?}", component.issues);
+        assert!(!malformed.is_conforming());
+    }
+
+    #[test]
+    fn selects_declared_version() {
+        assert_eq!(select_version(None, None).unwrap(), AbiVersion::V1);
+        assert_eq!(select_version(None, Some(AbiVersion::V2)).unwrap(), AbiVersion::V2);
+        assert_eq!(select_version(Some(2), None).unwrap(), AbiVersion::V2);
+        assert_eq!(select_version(Some(2), Some(AbiVersion::V2)).unwrap(), AbiVersion::V2);
+    }
+
+    #[test]
+    fn rejects_unsupported_or_mismatched_version() {
+        assert!(matches!(select_version(Some(3), None), Err(ModuleError::AbiError(_))));
+        assert!(matches!(select_version(Some(1), Some(AbiVersion::V2)), Err(ModuleError::AbiError(_))));
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::{HashMap, VecDeque}, hash::{DefaultHasher, Hash, Hasher}, sync::{Arc, LazyLock, Mutex}};
+use wasmtime::{Engine, Module as WasmModule, component::Component};
+
+
+/// Maximum number of compiled artifacts kept in memory.
+const MAX_ARTIFACTS: usize = 32;
+
+/// Maximum total size in bytes of the compiled artifacts kept in memory.
+const MAX_ARTIFACT_BYTES: usize = 256 << 20;
+
+/// Content hash of a binary and compatibility hash of the engine it was compiled for
+type ArtifactKey = (String, u64);
+
+/// Compiled artifacts shared by every module of the process, keyed by content hash
+struct ArtifactCache {
+    order: VecDeque<ArtifactKey>,
+    artifacts: HashMap<ArtifactKey, Arc<[u8]>>,
+    bytes: usize,
+    max_artifacts: usize,
+    max_bytes: usize,
+}
+
+impl ArtifactCache {
+    fn new(max_artifacts: usize, max_bytes: usize) -> Self {
+        Self {
+            order: VecDeque::new(),
+            artifacts: HashMap::new(),
+            bytes: 0,
+            max_artifacts,
+            max_bytes,
+        }
+    }
+
+    fn get(&self, key: &ArtifactKey) -> Option<Arc<[u8]>> {
+        self.artifacts.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: ArtifactKey, artifact: Vec<u8>) {
+        // An artifact exceeding the budget on its own would evict every other one
+        if artifact.len() > self.max_bytes || self.artifacts.contains_key(&key) {
+            return;
+        }
+
+        self.bytes += artifact.len();
+        self.artifacts.insert(key.clone(), artifact.into());
+        self.order.push_back(key);
+
+        // Evict the oldest artifacts first
+        while self.order.len() > self.max_artifacts || self.bytes > self.max_bytes {
+            if let Some(key) = self.order.pop_front()
+                && let Some(artifact) = self.artifacts.remove(&key)
+            {
+                self.bytes -= artifact.len();
+            }
+        }
+    }
+}
+
+static CACHE: LazyLock<Mutex<ArtifactCache>> = LazyLock::new(|| Mutex::new(ArtifactCache::new(MAX_ARTIFACTS, MAX_ARTIFACT_BYTES)));
+
+/// Get the cache key of a binary compiled for an engine
+fn key(engine: &Engine, content_hash: &str) -> ArtifactKey {
+    let mut hasher = DefaultHasher::new();
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+
+    (content_hash.to_string(), hasher.finish())
+}
+
+/// Compile a module, reusing the artifact compiled for a binary with the
+/// same content hash and an engine with compatible settings if there is one
+///
+/// # Arguments
+/// * `engine` - The engine to compile the module for
+/// * `binary` - The WebAssembly binary of the module
+/// * `content_hash` - The content hash of the binary
+///
+/// # Returns
+/// The compiled module or an error if compilation fails
+pub(crate) fn compile_module(engine: &Engine, binary: &[u8], content_hash: &str) -> wasmtime::Result<WasmModule> {
+    let key = key(engine, content_hash);
+    let cached = CACHE.lock().unwrap().get(&key);
+
+    if let Some(artifact) = cached {
+        // SAFETY: artifacts are only ever produced by `WasmModule::serialize` in this
+        // process, for an engine with the same compatibility hash.
+        if let Ok(module) = unsafe { WasmModule::deserialize(engine, &artifact) } {
+            log::debug!("reusing compiled artifact for module {}", content_hash);
+            return Ok(module);
+        }
+    }
+
+    let module = WasmModule::from_binary(engine, binary)?;
+
+    if let Ok(artifact) = module.serialize() {
+        CACHE.lock().unwrap().insert(key, artifact);
+    }
+
+    Ok(module)
+}
+
+/// Compile a component, reusing the artifact compiled for a binary with the
+/// same content hash and an engine with compatible settings if there is one
+///
+/// # Arguments
+/// * `engine` - The engine to compile the component for
+/// * `binary` - The WebAssembly binary of the component
+/// * `content_hash` - The content hash of the binary
+///
+/// # Returns
+/// The compiled component or an error if compilation fails
+pub(crate) fn compile_component(engine: &Engine, binary: &[u8], content_hash: &str) -> wasmtime::Result<Component> {
+    let key = key(engine, content_hash);
+    let cached = CACHE.lock().unwrap().get(&key);
+
+    if let Some(artifact) = cached {
+        // SAFETY: artifacts are only ever produced by `Component::serialize` in this
+        // process, for an engine with the same compatibility hash.
+        if let Ok(component) = unsafe { Component::deserialize(engine, &artifact) } {
+            log::debug!("reusing compiled artifact for component {}", content_hash);
+            return Ok(component);
+        }
+    }
+
+    let component = Component::from_binary(engine, binary)?;
+
+    if let Ok(artifact) = component.serialize() {
+        CACHE.lock().unwrap().insert(key, artifact);
+    }
+
+    Ok(component)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(index: usize) -> ArtifactKey {
+        (index.to_string(), 0)
+    }
+
+    #[test]
+    fn evicts_oldest_artifacts() {
+        let mut cache = ArtifactCache::new(2, 1024);
+
+        for index in 0..3 {
+            cache.insert(key(index), vec![0; 16]);
+        }
+
+        assert!(cache.get(&key(0)).is_none());
+        assert!(cache.get(&key(1)).is_some() && cache.get(&key(2)).is_some());
+        assert_eq!(cache.bytes, 32);
+    }
+
+    #[test]
+    fn evicts_oldest_artifacts_beyond_byte_budget() {
+        let mut cache = ArtifactCache::new(32, 100);
+
+        for index in 0..5 {
+            cache.insert(key(index), vec![0; 25]);
+        }
+
+        assert!(cache.get(&key(0)).is_none());
+        assert!((1..5).all(|index| cache.get(&key(index)).is_some()));
+        assert_eq!(cache.bytes, 100);
+    }
+
+    #[test]
+    fn skips_artifacts_exceeding_byte_budget() {
+        let mut cache = ArtifactCache::new(32, 100);
+        cache.insert(key(0), vec![0; 16]);
+        cache.insert(key(1), vec![0; 101]);
+
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(1)).is_none());
+        assert_eq!(cache.bytes, 16);
+    }
+}
@@ -0,0 +1,278 @@
+use serde::de::DeserializeOwned;
+use wasmtime::{AsContextMut, Caller, TypedFunc};
+
+use crate::{
+    state::ModuleState,
+    input::{FnInput, IntoFnInput},
+    result::FnResult,
+    memory::{unpack_ptr, MemoryOps, AsyncMemoryOps},
+    error::{ModuleError, ModuleResult},
+};
+
+
+/// Re-entrant handle passed to host functions created with
+/// [`HostFn::with_guest`](crate::host_fns::HostFn::with_guest), calling
+/// guest exports by name while the host function is running.
+/// 
+/// Callback inputs are allocated with `guest_alloc` rather than written to the
+/// exchange buffer, so they never clobber data of the calls they are nested in,
+/// and follow the ownership rules of the module's ABI version like any other call.
+/// Host functions of an [`AsyncModule`](crate::module::AsyncModule) use
+/// [`AsyncGuestCaller`](crate::callback::AsyncGuestCaller) instead. Nested callbacks fail with [`CallbackDepthExceeded`](crate::error::ModuleError::CallbackDepthExceeded)
+/// beyond the configured [`max_callback_depth`](crate::config::ModuleConfig::max_callback_depth).
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let module = Module::builder()
+///     .from_file("my_module.wasm")?
+///     .with_name("my_module")
+///     .host_fn_with_guest("for_each_row", |guest: &mut GuestCaller, input: &FnInput| {
+///         let callback: String = match input.get_arg(0) {
+///             Ok(callback) => callback,
+///             Err(e) => return FnResult::err(&e),
+///         };
+/// 
+///         for row in ["a", "b", "c"] {
+///             if let Err(e) = guest.typed_call::<()>(&callback, (row,)) {
+///                 return FnResult::err(&e);
+///             }
+///         }
+/// 
+///         FnResult::none()
+///     })
+///     .build()?
+///     .instantiate()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct GuestCaller<'a, 'b> {
+    caller: &'a mut Caller<'b, ModuleState>,
+}
+
+impl<'a, 'b> GuestCaller<'a, 'b> {
+    pub(crate) fn new(caller: &'a mut Caller<'b, ModuleState>) -> Self {
+        Self { caller }
+    }
+
+    /// Get the number of guest callbacks currently in progress.
+    pub fn depth(&self) -> u32 {
+        self.caller.data().callback_depth
+    }
+
+    /// Call a guest export with typed arguments and return value.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the export to call
+    /// * `args` - The arguments to pass to the export
+    /// 
+    /// # Returns
+    /// A result containing the return value of the export or an error
+    /// if the call fails or the callback depth limit is exceeded
+    pub fn typed_call<R>(&mut self, name: impl AsRef<str>, args: impl IntoFnInput) -> ModuleResult<R>
+    where
+        R: DeserializeOwned,
+    {
+        Ok(
+            self
+                .call(name, args.into_fn_input()?)?
+                .into_result::<R>()?
+        )
+    }
+
+    /// Call a guest export.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the export to call
+    /// * `input` - The input to pass to the export
+    /// 
+    /// # Returns
+    /// A result containing the [`FnResult`](crate::result::FnResult) of the export or an error
+    /// if the call fails or the callback depth limit is exceeded
+    pub fn call(&mut self, name: impl AsRef<str>, input: FnInput) -> ModuleResult<FnResult> {
+        enter_callback(self.caller)?;
+        let result = self.call_inner(name.as_ref(), input);
+        self.caller.data_mut().callback_depth -= 1;
+
+        result
+    }
+
+    fn call_inner(&mut self, name: &str, input: FnInput) -> ModuleResult<FnResult> {
+        let ModuleState { codec, abi_version, .. } = *self.caller.data();
+        let memory = MemoryOps::from_caller(self.caller)?;
+        let func = export_func(self.caller, name)?;
+
+        let (input_ptr, input_len) = memory.write(
+            self.caller.as_context_mut(),
+            &input.to_bytes_with(codec)?,
+        )?;
+        let packed = func.call(self.caller.as_context_mut(), (input_ptr, input_len as u32));
+
+        // Under V1 the guest takes ownership of the input and frees it itself. Otherwise the
+        // guest only borrows it, so it is freed here even if the call fails.
+        let freed = match abi_version.caller_frees_input() {
+            true => memory.dealloc(self.caller.as_context_mut(), input_ptr, input_len),
+            false => Ok(()),
+        };
+        let (result_ptr, result_len) = unpack_ptr(packed?);
+        freed?;
+
+        let output = memory.receive(
+            self.caller.as_context_mut(),
+            result_ptr,
+            result_len,
+        )?;
+
+        Ok(FnResult::from_bytes_with(&output, codec)?)
+    }
+}
+
+/// Re-entrant handle passed to host functions created with
+/// [`HostFn::with_guest_async`](crate::host_fns::HostFn::with_guest_async), calling
+/// guest exports of an [`AsyncModule`](crate::module::AsyncModule) by name while
+/// the host function is running.
+/// 
+/// Callbacks are awaited on the executor driving the call, and follow the same
+/// allocation and depth rules as [`GuestCaller`](crate::callback::GuestCaller).
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let module = AsyncModule::builder()
+///     .from_file("my_module.wasm")?
+///     .with_name("my_module")
+///     .host_fn_with_guest_async("for_each_row", |guest, input| Box::pin(async move {
+///         let callback: String = match input.get_arg(0) {
+///             Ok(callback) => callback,
+///             Err(e) => return FnResult::err(&e),
+///         };
+/// 
+///         for row in ["a", "b", "c"] {
+///             if let Err(e) = guest.typed_call::<()>(&callback, (row,)).await {
+///                 return FnResult::err(&e);
+///             }
+///         }
+/// 
+///         FnResult::none()
+///     }))
+///     .build_async()?
+///     .instantiate()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncGuestCaller<'a, 'b> {
+    caller: &'a mut Caller<'b, ModuleState>,
+}
+
+impl<'a, 'b> AsyncGuestCaller<'a, 'b> {
+    pub(crate) fn new(caller: &'a mut Caller<'b, ModuleState>) -> Self {
+        Self { caller }
+    }
+
+    /// Get the number of guest callbacks currently in progress.
+    pub fn depth(&self) -> u32 {
+        self.caller.data().callback_depth
+    }
+
+    /// Call a guest export with typed arguments and return value.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the export to call
+    /// * `args` - The arguments to pass to the export
+    /// 
+    /// # Returns
+    /// A result containing the return value of the export or an error
+    /// if the call fails or the callback depth limit is exceeded
+    pub async fn typed_call<R>(&mut self, name: impl AsRef<str>, args: impl IntoFnInput) -> ModuleResult<R>
+    where
+        R: DeserializeOwned,
+    {
+        Ok(
+            self
+                .call(name, args.into_fn_input()?)
+                .await?
+                .into_result::<R>()?
+        )
+    }
+
+    /// Call a guest export.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the export to call
+    /// * `input` - The input to pass to the export
+    /// 
+    /// # Returns
+    /// A result containing the [`FnResult`](crate::result::FnResult) of the export or an error
+    /// if the call fails or the callback depth limit is exceeded
+    pub async fn call(&mut self, name: impl AsRef<str>, input: FnInput) -> ModuleResult<FnResult> {
+        enter_callback(self.caller)?;
+        let result = self.call_inner(name.as_ref(), input).await;
+        self.caller.data_mut().callback_depth -= 1;
+
+        result
+    }
+
+    async fn call_inner(&mut self, name: &str, input: FnInput) -> ModuleResult<FnResult> {
+        let ModuleState { codec, abi_version, .. } = *self.caller.data();
+        let memory = AsyncMemoryOps::from_caller(self.caller)?;
+        let func = export_func(self.caller, name)?;
+
+        let (input_ptr, input_len) = memory
+            .write(
+                self.caller.as_context_mut(),
+                &input.to_bytes_with(codec)?,
+            )
+            .await?;
+        let packed = func
+            .call_async(self.caller.as_context_mut(), (input_ptr, input_len as u32))
+            .await;
+
+        // Under V1 the guest takes ownership of the input and frees it itself. Otherwise the
+        // guest only borrows it, so it is freed here even if the call fails.
+        let freed = match abi_version.caller_frees_input() {
+            true => memory.dealloc(self.caller.as_context_mut(), input_ptr, input_len).await,
+            false => Ok(()),
+        };
+        let (result_ptr, result_len) = unpack_ptr(packed?);
+        freed?;
+
+        let output = memory
+            .receive(
+                self.caller.as_context_mut(),
+                result_ptr,
+                result_len,
+            )
+            .await?;
+
+        Ok(FnResult::from_bytes_with(&output, codec)?)
+    }
+}
+
+/// Enter a nested callback, failing if the depth limit is reached
+fn enter_callback(caller: &mut Caller<'_, ModuleState>) -> ModuleResult<()> {
+    let ModuleState { callback_depth, max_callback_depth, .. } = *caller.data();
+
+    if callback_depth >= max_callback_depth {
+        return Err(ModuleError::CallbackDepthExceeded(max_callback_depth));
+    }
+
+    caller.data_mut().callback_depth += 1;
+
+    Ok(())
+}
+
+/// Look up a guest export taking and returning a packed pointer
+fn export_func(caller: &mut Caller<'_, ModuleState>, name: &str) -> ModuleResult<TypedFunc<(u32, u32), u64>> {
+    Ok(
+        caller
+            .get_export(name)
+            .and_then(|export| export.into_func())
+            .ok_or_else(|| ModuleError::FunctionNotFound(format!("failed to get function '{}'", name)))?
+            .typed::<(u32, u32), u64>(caller.as_context_mut())?
+    )
+}
@@ -0,0 +1,117 @@
+use std::{
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use mea::latch::Latch;
+
+
+/// A token used to cancel in-flight [`AsyncModule`](crate::module::AsyncModule) calls.
+///
+/// Cloned tokens share the same cancellation state, so a clone can be handed
+/// to another task and cancelled from there.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{cancel::CancellationToken, error::ModuleError};
+/// # async fn example(mut module: binmod::module::AsyncModule) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let token = CancellationToken::new();
+///
+/// // Cancel the call from another task
+/// let handle = token.clone();
+/// std::thread::spawn(move || handle.cancel());
+///
+/// match module.typed_call_cancellable::<f64>("circle_area", (5.0,), &token).await {
+///     Err(ModuleError::Cancelled) => println!("Call was cancelled"),
+///     result => println!("Result: {:?}", result),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    latch: Arc<Latch>,
+}
+
+impl CancellationToken {
+    /// Create a new, uncancelled token.
+    pub fn new() -> Self {
+        Self {
+            latch: Arc::new(Latch::new(1)),
+        }
+    }
+
+    /// Cancel every call using this token.
+    ///
+    /// Cancelling an already cancelled token has no effect.
+    pub fn cancel(&self) {
+        self.latch.count_down();
+    }
+
+    /// Check if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.latch.try_wait().is_ok()
+    }
+
+    /// Wait until the token is cancelled.
+    pub async fn cancelled(&self) {
+        self.latch.wait().await;
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Background thread that cancels a token once a timeout elapses.
+///
+/// The thread is stopped without cancelling the token when the timer is dropped first.
+pub(crate) struct CancelTimer {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CancelTimer {
+    /// Start a timer cancelling the given token after `timeout`.
+    pub fn start(token: CancellationToken, timeout: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let deadline = Instant::now() + timeout;
+
+                // Parking instead of sleeping lets `stop` wake the thread
+                // early, so dropping the timer never waits for the timeout.
+                while !stop.load(Ordering::Relaxed) {
+                    let now = Instant::now();
+
+                    if now >= deadline {
+                        token.cancel();
+                        break;
+                    }
+
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for CancelTimer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use wasmtime::{Instance, Store, AsContextMut};
+
+use crate::{
+    state::ModuleState,
+    error::{FnError, ModuleError, ModuleResult},
+};
+
+
+/// Optional guest export negotiating the wire format of a module.
+///
+/// The export has the signature `(u32) -> u32`. It receives the id of the
+/// codec preferred by the host and returns the id of the codec the guest
+/// will use. Guests without the export are assumed to use JSON.
+pub const CODEC_EXPORT: &str = "binmod_codec";
+
+/// Wire format used to exchange function inputs and results with a guest.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let mut module = Module::builder()
+///     .from_file("my_module.wasm")?
+///     .with_name("my_module")
+///     .with_config(ModuleConfig::default().with_codec(Codec::MessagePack))
+///     .build()?
+///     .instantiate()?;
+///
+/// println!("Negotiated codec: {:?}", module.codec());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Codec {
+    /// JSON, understood by every binmod MDK.
+    #[default]
+    Json,
+    /// MessagePack, with structs encoded as maps.
+    MessagePack,
+    /// CBOR.
+    Cbor,
+}
+
+impl Codec {
+    /// Get the id identifying the codec in the binmod ABI.
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::Json => 0,
+            Self::MessagePack => 1,
+            Self::Cbor => 2,
+        }
+    }
+
+    /// Get the codec identified by an id in the binmod ABI.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codec
+    ///
+    /// # Returns
+    /// The codec, or `None` if the id is unknown
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Self::Json),
+            1 => Some(Self::MessagePack),
+            2 => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Serialize a value to bytes.
+    ///
+    /// # Arguments
+    /// * `value` - The value to serialize
+    ///
+    /// # Returns
+    /// A Result containing the serialized bytes or an [`FnError`](crate::error::FnError)
+    /// if serialization fails
+    pub fn encode<T>(&self, value: &T) -> Result<Vec<u8>, FnError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self {
+            Self::Json => serde_json::to_vec(value)
+                .map_err(|e| e.to_string()),
+            Self::MessagePack => rmp_serde::to_vec_named(value)
+                .map_err(|e| e.to_string()),
+            Self::Cbor => {
+                let mut bytes = vec![];
+                ciborium::into_writer(value, &mut bytes)
+                    .map(|_| bytes)
+                    .map_err(|e| e.to_string())
+            },
+        }
+        .map_err(|e| FnError::new("SerializationError", e))
+    }
+
+    /// Deserialize a value from bytes.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to deserialize from
+    ///
+    /// # Returns
+    /// A Result containing the deserialized value or an [`FnError`](crate::error::FnError)
+    /// if deserialization fails
+    pub fn decode<T>(&self, bytes: &[u8]) -> Result<T, FnError>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            Self::Json => serde_json::from_slice(bytes)
+                .map_err(|e| e.to_string()),
+            Self::MessagePack => rmp_serde::from_slice(bytes)
+                .map_err(|e| e.to_string()),
+            Self::Cbor => ciborium::from_reader(bytes)
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|e| FnError::new("DeserializationError", e))
+    }
+}
+
+/// Negotiate the codec of an instance through its optional `binmod_codec` export
+pub(crate) fn negotiate(instance: &Instance, store: &mut Store<ModuleState>, preferred: Codec) -> ModuleResult<Codec> {
+    let Some(func) = instance.get_func(store.as_context_mut(), CODEC_EXPORT) else {
+        return Ok(Codec::Json);
+    };
+    let id = func
+        .typed::<u32, u32>(store.as_context_mut())?
+        .call(store.as_context_mut(), preferred.id())
+        .map_err(|e| ModuleError::InstantiationError(format!("failed to negotiate codec: {}", e)))?;
+
+    resolve(id)
+}
+
+/// Negotiate the codec of an asynchronous instance through its optional `binmod_codec` export
+pub(crate) async fn negotiate_async(instance: &Instance, store: &mut Store<ModuleState>, preferred: Codec) -> ModuleResult<Codec> {
+    let Some(func) = instance.get_func(store.as_context_mut(), CODEC_EXPORT) else {
+        return Ok(Codec::Json);
+    };
+    let id = func
+        .typed::<u32, u32>(store.as_context_mut())?
+        .call_async(store.as_context_mut(), preferred.id())
+        .await
+        .map_err(|e| ModuleError::InstantiationError(format!("failed to negotiate codec: {}", e)))?;
+
+    resolve(id)
+}
+
+fn resolve(id: u32) -> ModuleResult<Codec> {
+    Codec::from_id(id)
+        .ok_or_else(|| ModuleError::AbiError(format!("guest selected unknown codec id {}", id)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::FnInput;
+
+    const CODECS: [Codec; 3] = [Codec::Json, Codec::MessagePack, Codec::Cbor];
+
+    #[test]
+    fn id_round_trips() {
+        for codec in CODECS {
+            assert_eq!(Codec::from_id(codec.id()), Some(codec));
+        }
+
+        assert_eq!(Codec::from_id(3), None);
+    }
+
+    #[test]
+    fn values_round_trip() {
+        let input = FnInput::new()
+            .with_args([1.5, 2.5])
+            .unwrap()
+            .with_kwarg("name", "binmod")
+            .unwrap();
+
+        for codec in CODECS {
+            let bytes = codec.encode(&input).unwrap();
+            let decoded: FnInput = codec.decode(&bytes).unwrap();
+
+            assert_eq!(decoded.get_arg::<f64>(1).unwrap(), 2.5);
+            assert_eq!(decoded.get_kwarg::<String>("name").unwrap(), "binmod");
+        }
+    }
+
+    #[test]
+    fn reports_decoding_errors() {
+        for codec in CODECS {
+            let error = codec.decode::<FnInput>(&[0xc1]).unwrap_err();
+
+            assert_eq!(error.error_type, "DeserializationError");
+        }
+    }
+}
@@ -0,0 +1,704 @@
+use std::collections::HashMap;
+use wasmtime::{Engine, Store, AsContextMut};
+use wasmtime::component::{
+    ComponentExportIndex, ComponentNamedList, Instance, InstancePre, Lift, Linker, Lower, Val,
+};
+
+use crate::{
+    digest,
+    setup,
+    state::ComponentState,
+    host_fns::{HostFn, HostFnSet},
+    config::{ModuleEnv, ModuleConfig, ModuleLimits},
+    module::ModuleBuilder,
+    error::{ModuleResult, ModuleError},
+};
+
+
+/// Represents a WebAssembly Component with host functions, linked against
+/// WASI Preview 2, and provides methods to instantiate and call the
+/// functions exported through its WIT interfaces.
+///
+/// Host functions are imported by the component from the interface named after
+/// the module's namespace as `func(input: string) -> string`, using the same JSON
+/// encoding as host functions of a [`Module`](crate::module::Module).
+///
+/// Functions exported from an interface are addressed as `interface#function`,
+/// e.g. `example:calculator/ops#add`.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{component::ComponentModule, config::ModuleEnv};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let mut module = ComponentModule::builder()
+///     .from_file("path/to/component.wasm")?
+///     .with_name("example_component")
+///     .with_namespace("example:host/env")
+///     .with_environment(
+///         ModuleEnv::default()
+///             .inherit_env()
+///             .inherit_network()
+///     )
+///     .host_fn("host_function", |arg1: i32, arg2: String| -> Result<String, String> {
+///         Ok(format!("Received: {} and {}", arg1, arg2))
+///     })
+///     .build_component()?
+///     .instantiate()?;
+///
+/// let (sum,): (u32,) = module.typed_call("example:calculator/ops#add", (10u32, 20u32))?;
+/// println!("Sum: {}", sum);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ComponentModule {
+    name: String,
+    namespace: String,
+    binary: Vec<u8>,
+    content_hash: String,
+    environment: ModuleEnv,
+    config: ModuleConfig,
+    limits: ModuleLimits,
+    host_fns: HashMap<String, HostFn>,
+    host_fn_sets: Vec<HostFnSet>,
+    engine: Option<Engine>,
+    store: Option<Store<ComponentState>>,
+    linker: Option<Linker<ComponentState>>,
+    instance_pre: Option<InstancePre<ComponentState>>,
+    instance: Option<Instance>,
+}
+
+impl ComponentModule {
+    /// Create a new Binmod Component Module.
+    ///
+    /// # Arguments
+    /// * `binary` - The WebAssembly component binary
+    /// * `name` - The name of the module
+    /// * `namespace` - The interface the component imports host functions from
+    /// * `environment` - The environment configuration for the module
+    /// * `config` - The configuration for the module
+    /// * `limits` - The resource limits for the module
+    /// * `host_fns` - A map of host function names to HostFn instances
+    ///
+    /// # Returns
+    /// A new [`ComponentModule`](crate::component::ComponentModule) instance
+    pub fn new(
+        binary: Vec<u8>,
+        name: impl Into<String>,
+        namespace: impl Into<String>,
+        environment: ModuleEnv,
+        config: ModuleConfig,
+        limits: ModuleLimits,
+        host_fns: HashMap<String, HostFn>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            namespace: namespace.into(),
+            content_hash: digest::content_hash(&binary),
+            binary,
+            environment,
+            config,
+            limits,
+            host_fns,
+            host_fn_sets: Vec::new(),
+            engine: None,
+            store: None,
+            linker: None,
+            instance_pre: None,
+            instance: None,
+        }
+    }
+
+    /// Create a new [`ModuleBuilder`](crate::module::ModuleBuilder)
+    /// for constructing a [`ComponentModule`](crate::component::ComponentModule).
+    pub fn builder() -> ModuleBuilder {
+        ModuleBuilder::new()
+    }
+
+    /// Get the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the namespace of the module.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Get the binary code of the module.
+    pub fn binary(&self) -> &[u8] {
+        &self.binary
+    }
+
+    /// Get the content hash of the module binary.
+    /// 
+    /// # Returns
+    /// The lowercase hex encoded SHA-256 digest of the binary
+    pub fn content_hash(&self) -> &str {
+        &self.content_hash
+    }
+
+    /// Get the environment configuration of the module.
+    pub fn environment(&self) -> &ModuleEnv {
+        &self.environment
+    }
+
+    /// Set the host function sets registered alongside the module's host functions.
+    /// 
+    /// Only takes effect the next time the module is linked.
+    pub(crate) fn set_host_fn_sets(&mut self, host_fn_sets: Vec<HostFnSet>) {
+        self.host_fn_sets = host_fn_sets;
+    }
+
+    /// Check if the module has been instantiated.
+    pub fn is_instantiated(&self) -> bool {
+        self.instance.is_some()
+    }
+
+    /// Set the fuel for the module's store.
+    ///
+    /// # Arguments
+    /// * `fuel` - The amount of fuel to set
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    /// if fuel is not enabled or the module is not instantiated
+    pub fn set_fuel(&mut self, fuel: u64) -> ModuleResult<()> {
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .set_fuel(fuel)
+            .map_err(|_| ModuleError::FuelNotEnabled)?;
+
+        Ok(())
+    }
+
+    /// Get the remaining fuel for the module's store.
+    ///
+    /// # Returns
+    /// A result containing the remaining fuel or an error
+    /// if fuel is not enabled or the module is not instantiated
+    pub fn get_fuel(&mut self) -> ModuleResult<u64> {
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .get_fuel()
+            .map_err(|_| ModuleError::FuelNotEnabled)
+    }
+
+    /// Set the epoch deadline for the module's store.
+    ///
+    /// # Arguments
+    /// * `deadline` - The epoch deadline to set
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    /// if the module is not instantiated
+    pub fn set_epoch_deadline(&mut self, deadline: u64) -> ModuleResult<()> {
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .set_epoch_deadline(deadline);
+
+        Ok(())
+    }
+
+    /// Increment the epoch for the module's store.
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    /// if the module is not instantiated
+    pub fn increment_epoch(&mut self) -> ModuleResult<()> {
+        self.engine
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .increment_epoch();
+
+        Ok(())
+    }
+
+    /// Instantiate the module.
+    ///
+    /// # Returns
+    /// A result containing the instantiated module or an error
+    /// if instantiation fails or the module is already instantiated
+    pub fn instantiate(mut self) -> ModuleResult<Self> {
+        if self.is_instantiated() {
+            return Err(ModuleError::AlreadyInstantiated);
+        }
+
+        if self.engine.is_none() {
+            let engine = setup::component_engine(&self.config, false)?;
+            let linker = setup::component_linker(
+                &engine,
+                &self.namespace,
+                &self.host_fns,
+                &self.host_fn_sets,
+                self.config.validate_schemas,
+                false,
+            )?;
+
+            self.engine = Some(engine);
+            self.linker = Some(linker);
+        }
+
+        let engine = self.engine.as_ref().expect("engine should be intialized");
+
+        if self.instance_pre.is_none() {
+            self.instance_pre = Some(setup::component_instance_pre(
+                engine,
+                self.linker.as_ref().expect("linker should be initialized"),
+                &self.binary,
+                &self.content_hash,
+            )?);
+        }
+
+        let mut store = setup::component_store(engine, &self.environment, &self.limits);
+
+        self.instance = Some(
+            self.instance_pre
+                .as_ref()
+                .expect("instance_pre should be initialized")
+                .instantiate(&mut store)
+                .map_err(|e| ModuleError::InstantiationError(format!("failed to instantiate component: {}", e)))?
+        );
+
+        self.store = Some(store);
+        log::debug!("instantiated component '{}' ({})", self.name, self.content_hash);
+        digest::record_instantiated(&self.name, &self.content_hash);
+
+        Ok(self)
+    }
+
+    /// Call a function exported by the component with typed parameters and results.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to call, as `function` or `interface#function`
+    /// * `params` - The parameters to pass to the function
+    ///
+    /// # Returns
+    /// A result containing the results of the function or an error
+    /// if the call fails or the module is not instantiated
+    pub fn typed_call<P, R>(&mut self, name: impl AsRef<str>, params: P) -> ModuleResult<R>
+    where
+        P: ComponentNamedList + Lower + Sync,
+        R: ComponentNamedList + Lift + Send + Sync,
+    {
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+
+        let index = export_index(instance, store.as_context_mut(), name.as_ref())?;
+        let func = instance
+            .get_typed_func::<P, R>(store.as_context_mut(), &index)
+            .map_err(|e| ModuleError::FunctionNotFound(format!("failed to get function '{}': {}", name.as_ref(), e)))?;
+
+        let results = func.call(store.as_context_mut(), params)?;
+        func.post_return(store.as_context_mut())?;
+
+        Ok(results)
+    }
+
+    /// Call a function exported by the component with dynamically typed values.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to call, as `function` or `interface#function`
+    /// * `params` - The parameters to pass to the function
+    ///
+    /// # Returns
+    /// A result containing the results of the function or an error
+    /// if the call fails or the module is not instantiated
+    pub fn call(&mut self, name: impl AsRef<str>, params: &[Val]) -> ModuleResult<Vec<Val>> {
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+
+        let index = export_index(instance, store.as_context_mut(), name.as_ref())?;
+        let func = instance
+            .get_func(store.as_context_mut(), index)
+            .ok_or_else(|| ModuleError::FunctionNotFound(format!("failed to get function '{}'", name.as_ref())))?;
+
+        let mut results = vec![Val::Bool(false); func.ty(&*store).results().len()];
+        func.call(store.as_context_mut(), params, &mut results)?;
+        func.post_return(store.as_context_mut())?;
+
+        Ok(results)
+    }
+}
+
+impl Clone for ComponentModule {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            namespace: self.namespace.clone(),
+            binary: self.binary.clone(),
+            content_hash: self.content_hash.clone(),
+            environment: self.environment.clone(),
+            config: self.config.clone(),
+            limits: self.limits.clone(),
+            host_fns: self.host_fns.clone(),
+            host_fn_sets: self.host_fn_sets.clone(),
+            engine: self.engine.clone(),
+            store: None,
+            linker: self.linker.clone(),
+            instance_pre: self.instance_pre.clone(),
+            instance: None,
+        }
+    }
+}
+
+/// Represents an asynchronous WebAssembly Component with host functions,
+/// linked against WASI Preview 2, and provides methods to instantiate and
+/// call the functions exported through its WIT interfaces.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{component::AsyncComponentModule, config::ModuleEnv};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let mut module = AsyncComponentModule::builder()
+///     .from_file("path/to/component.wasm")?
+///     .with_name("example_component")
+///     .with_namespace("example:host/env")
+///     .build_component_async()?
+///     .instantiate()
+///     .await?;
+///
+/// let (sum,): (u32,) = module
+///     .typed_call("example:calculator/ops#add", (10u32, 20u32))
+///     .await?;
+/// println!("Sum: {}", sum);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Note
+///
+/// The async module API is experimental and may have performance implications and limited support.
+/// Do not use in production environments without thorough testing.
+pub struct AsyncComponentModule {
+    name: String,
+    namespace: String,
+    binary: Vec<u8>,
+    content_hash: String,
+    environment: ModuleEnv,
+    config: ModuleConfig,
+    limits: ModuleLimits,
+    fuel_yield_interval: Option<u64>,
+    host_fns: HashMap<String, HostFn>,
+    host_fn_sets: Vec<HostFnSet>,
+    engine: Option<Engine>,
+    store: Option<Store<ComponentState>>,
+    linker: Option<Linker<ComponentState>>,
+    instance_pre: Option<InstancePre<ComponentState>>,
+    instance: Option<Instance>,
+}
+
+impl AsyncComponentModule {
+    /// Create a new Binmod Async Component Module.
+    ///
+    /// # Arguments
+    /// * `binary` - The WebAssembly component binary
+    /// * `name` - The name of the module
+    /// * `namespace` - The interface the component imports host functions from
+    /// * `environment` - The environment configuration for the module
+    /// * `config` - The configuration for the module
+    /// * `limits` - The resource limits for the module
+    /// * `fuel_yield_interval` - The fuel interval at which execution yields
+    /// * `host_fns` - A map of host function names to HostFn instances
+    ///
+    /// # Returns
+    /// A new [`AsyncComponentModule`](crate::component::AsyncComponentModule) instance
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        binary: Vec<u8>,
+        name: impl Into<String>,
+        namespace: impl Into<String>,
+        environment: ModuleEnv,
+        config: ModuleConfig,
+        limits: ModuleLimits,
+        fuel_yield_interval: Option<u64>,
+        host_fns: HashMap<String, HostFn>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            namespace: namespace.into(),
+            content_hash: digest::content_hash(&binary),
+            binary,
+            environment,
+            config,
+            limits,
+            fuel_yield_interval,
+            host_fns,
+            host_fn_sets: Vec::new(),
+            engine: None,
+            store: None,
+            linker: None,
+            instance_pre: None,
+            instance: None,
+        }
+    }
+
+    /// Create a new [`ModuleBuilder`](crate::module::ModuleBuilder)
+    /// for constructing an [`AsyncComponentModule`](crate::component::AsyncComponentModule).
+    pub fn builder() -> ModuleBuilder {
+        ModuleBuilder::new()
+    }
+
+    /// Get the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the namespace of the module.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Get the binary code of the module.
+    pub fn binary(&self) -> &[u8] {
+        &self.binary
+    }
+
+    /// Get the content hash of the module binary.
+    /// 
+    /// # Returns
+    /// The lowercase hex encoded SHA-256 digest of the binary
+    pub fn content_hash(&self) -> &str {
+        &self.content_hash
+    }
+
+    /// Get the environment configuration of the module.
+    pub fn environment(&self) -> &ModuleEnv {
+        &self.environment
+    }
+
+    /// Set the host function sets registered alongside the module's host functions.
+    /// 
+    /// Only takes effect the next time the module is linked.
+    pub(crate) fn set_host_fn_sets(&mut self, host_fn_sets: Vec<HostFnSet>) {
+        self.host_fn_sets = host_fn_sets;
+    }
+
+    /// Check if the module has been instantiated.
+    pub fn is_instantiated(&self) -> bool {
+        self.instance.is_some()
+    }
+
+    /// Set the fuel for the module's store.
+    ///
+    /// # Arguments
+    /// * `fuel` - The amount of fuel to set
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    /// if fuel is not enabled or the module is not instantiated
+    pub fn set_fuel(&mut self, fuel: u64) -> ModuleResult<()> {
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .set_fuel(fuel)
+            .map_err(|_| ModuleError::FuelNotEnabled)?;
+
+        Ok(())
+    }
+
+    /// Get the remaining fuel for the module's store.
+    ///
+    /// # Returns
+    /// A result containing the remaining fuel or an error
+    /// if fuel is not enabled or the module is not instantiated
+    pub fn get_fuel(&mut self) -> ModuleResult<u64> {
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .get_fuel()
+            .map_err(|_| ModuleError::FuelNotEnabled)
+    }
+
+    /// Set the epoch deadline for the module's store.
+    ///
+    /// # Arguments
+    /// * `deadline` - The epoch deadline to set
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    /// if the module is not instantiated
+    pub fn set_epoch_deadline(&mut self, deadline: u64) -> ModuleResult<()> {
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .set_epoch_deadline(deadline);
+
+        Ok(())
+    }
+
+    /// Increment the epoch for the module's store.
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    /// if the module is not instantiated
+    pub fn increment_epoch(&mut self) -> ModuleResult<()> {
+        self.engine
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .increment_epoch();
+
+        Ok(())
+    }
+
+    /// Instantiate the module.
+    ///
+    /// # Returns
+    /// A result containing the instantiated module or an error
+    /// if instantiation fails or the module is already instantiated
+    pub async fn instantiate(mut self) -> ModuleResult<Self> {
+        if self.is_instantiated() {
+            return Err(ModuleError::AlreadyInstantiated);
+        }
+
+        if self.engine.is_none() {
+            let engine = setup::component_engine(&self.config, true)?;
+            let linker = setup::component_linker(
+                &engine,
+                &self.namespace,
+                &self.host_fns,
+                &self.host_fn_sets,
+                self.config.validate_schemas,
+                true,
+            )?;
+
+            self.engine = Some(engine);
+            self.linker = Some(linker);
+        }
+
+        let engine = self.engine.as_ref().expect("engine should be intialized");
+
+        if self.instance_pre.is_none() {
+            self.instance_pre = Some(setup::component_instance_pre(
+                engine,
+                self.linker.as_ref().expect("linker should be initialized"),
+                &self.binary,
+                &self.content_hash,
+            )?);
+        }
+
+        let mut store = setup::component_store(engine, &self.environment, &self.limits);
+
+        // We start with unlimited fuel for async modules
+        // and ensure execution is paused for an async yield
+        // everytime it consumes `n` units of fuel.
+        store.set_fuel(u64::MAX)
+            .map_err(|_| ModuleError::FuelNotEnabled)?;
+        store.fuel_async_yield_interval(Some(self.fuel_yield_interval.unwrap_or(10000)))?;
+
+        self.instance = Some(
+            self.instance_pre
+                .as_ref()
+                .expect("instance_pre should be initialized")
+                .instantiate_async(&mut store)
+                .await
+                .map_err(|e| ModuleError::InstantiationError(format!("failed to instantiate component: {}", e)))?
+        );
+
+        self.store = Some(store);
+        log::debug!("instantiated component '{}' ({})", self.name, self.content_hash);
+        digest::record_instantiated(&self.name, &self.content_hash);
+
+        Ok(self)
+    }
+
+    /// Call a function exported by the component with typed parameters and results.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to call, as `function` or `interface#function`
+    /// * `params` - The parameters to pass to the function
+    ///
+    /// # Returns
+    /// A result containing the results of the function or an error
+    /// if the call fails or the module is not instantiated
+    pub async fn typed_call<P, R>(&mut self, name: impl AsRef<str>, params: P) -> ModuleResult<R>
+    where
+        P: ComponentNamedList + Lower + Send + Sync,
+        R: ComponentNamedList + Lift + Send + Sync + 'static,
+    {
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+
+        let index = export_index(instance, store.as_context_mut(), name.as_ref())?;
+        let func = instance
+            .get_typed_func::<P, R>(store.as_context_mut(), &index)
+            .map_err(|e| ModuleError::FunctionNotFound(format!("failed to get function '{}': {}", name.as_ref(), e)))?;
+
+        let results = func.call_async(store.as_context_mut(), params).await?;
+        func.post_return_async(store.as_context_mut()).await?;
+
+        Ok(results)
+    }
+
+    /// Call a function exported by the component with dynamically typed values.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to call, as `function` or `interface#function`
+    /// * `params` - The parameters to pass to the function
+    ///
+    /// # Returns
+    /// A result containing the results of the function or an error
+    /// if the call fails or the module is not instantiated
+    pub async fn call(&mut self, name: impl AsRef<str>, params: &[Val]) -> ModuleResult<Vec<Val>> {
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+
+        let index = export_index(instance, store.as_context_mut(), name.as_ref())?;
+        let func = instance
+            .get_func(store.as_context_mut(), index)
+            .ok_or_else(|| ModuleError::FunctionNotFound(format!("failed to get function '{}'", name.as_ref())))?;
+
+        let mut results = vec![Val::Bool(false); func.ty(&*store).results().len()];
+        func.call_async(store.as_context_mut(), params, &mut results).await?;
+        func.post_return_async(store.as_context_mut()).await?;
+
+        Ok(results)
+    }
+}
+
+impl Clone for AsyncComponentModule {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            namespace: self.namespace.clone(),
+            binary: self.binary.clone(),
+            content_hash: self.content_hash.clone(),
+            environment: self.environment.clone(),
+            config: self.config.clone(),
+            limits: self.limits.clone(),
+            fuel_yield_interval: self.fuel_yield_interval,
+            host_fns: self.host_fns.clone(),
+            host_fn_sets: self.host_fn_sets.clone(),
+            engine: self.engine.clone(),
+            store: None,
+            linker: self.linker.clone(),
+            instance_pre: self.instance_pre.clone(),
+            instance: None,
+        }
+    }
+}
+
+/// Resolve an exported function by name, looking it up inside an exported
+/// interface if the name has the form `interface#function`.
+fn export_index(instance: &Instance, mut store: impl AsContextMut, name: &str) -> ModuleResult<ComponentExportIndex> {
+    let not_found = || ModuleError::FunctionNotFound(format!("failed to get function '{}'", name));
+
+    match name.rsplit_once('#') {
+        Some((interface, func)) => {
+            let interface = instance
+                .get_export_index(&mut store, None, interface)
+                .ok_or_else(not_found)?;
+
+            instance
+                .get_export_index(&mut store, Some(&interface), func)
+                .ok_or_else(not_found)
+        },
+        None => instance
+            .get_export_index(&mut store, None, name)
+            .ok_or_else(not_found),
+    }
+}
@@ -1,7 +1,9 @@
 use std::{env, sync::Arc, collections::HashMap, path::PathBuf, pin::Pin, net::SocketAddr, future::Future};
 use serde::{Serialize, Deserialize};
-use wasmtime::{Config, Strategy, Cache, CacheConfig, OptLevel, StoreLimits, StoreLimitsBuilder};
-use wasmtime_wasi::{WasiCtx, p1::WasiP1Ctx, DirPerms, FilePerms, sockets::SocketAddrUse};
+use wasmtime::{Config, Strategy, Cache, CacheConfig, OptLevel, ProfilingStrategy, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, p1::WasiP1Ctx, DirPerms, FilePerms, sockets::SocketAddrUse};
+
+use crate::{abi::AbiVersion, codec::Codec, package::MountedAssets};
 
 
 /// Enum for selecting the module compiler strategy.
@@ -22,8 +24,30 @@ impl From<ModuleCompiler> for Strategy {
     }
 }
 
+/// Enum for selecting the native profiling strategy.
+/// 
+/// These strategies emit metadata about the JIT-compiled code so that
+/// native profilers such as `perf` on Linux can symbolize guest frames.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ModuleProfiler {
+    None,
+    PerfMap,
+    JitDump,
+}
+
+impl From<ModuleProfiler> for ProfilingStrategy {
+    fn from(profiler: ModuleProfiler) -> Self {
+        match profiler {
+            ModuleProfiler::None => ProfilingStrategy::None,
+            ModuleProfiler::PerfMap => ProfilingStrategy::PerfMap,
+            ModuleProfiler::JitDump => ProfilingStrategy::JitDump,
+        }
+    }
+}
+
 /// Struct for configuring a module.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct ModuleConfig {
     /// The compiler strategy to use.
     /// 
@@ -96,6 +120,48 @@ pub struct ModuleConfig {
     /// 
     /// Default is `false`.
     pub memory64: bool,
+    /// The native profiling strategy to use.
+    /// 
+    /// `None` disables native profiling.
+    /// `PerfMap` writes a `/tmp/perf-<pid>.map` file that `perf` uses to symbolize JIT code.
+    /// `JitDump` writes a `jit-<pid>.dump` file for use with `perf inject --jit`.
+    /// 
+    /// This is independent of guest profiling through
+    /// [`Module::start_profiling`](crate::module::Module::start_profiling).
+    /// 
+    /// Default is `None`.
+    pub profiler: ModuleProfiler,
+    /// Whether to validate calls against the schemas of host functions and guest exports.
+    /// 
+    /// Inputs and results of host functions with a [`FnSchema`](crate::schema::FnSchema),
+    /// and of guest exports described by the module's
+    /// [`InterfaceSchema`](crate::schema::InterfaceSchema), are checked on every call.
+    /// 
+    /// Default is `false`.
+    pub validate_schemas: bool,
+    /// The wire format preferred for function inputs and results.
+    /// 
+    /// The codec is negotiated with guests exporting `binmod_codec` when the module
+    /// is instantiated. Guests without the export, and component modules, use JSON.
+    /// 
+    /// Default is `Json`.
+    pub codec: Codec,
+    /// The binmod ABI version implemented by the guest.
+    /// 
+    /// Guests exporting `binmod_abi_version` declare their version themselves, and
+    /// instantiation fails if it differs from the configured one. Guests without
+    /// the export are assumed to implement the configured version.
+    /// 
+    /// Default is `None`, which assumes `V1` for guests without the export.
+    pub abi_version: Option<AbiVersion>,
+    /// The maximum number of nested guest callbacks.
+    /// 
+    /// Host functions created with [`HostFn::with_guest`](crate::host_fns::HostFn::with_guest) or
+    /// [`HostFn::with_guest_async`](crate::host_fns::HostFn::with_guest_async) can call back into the guest, which can in turn call host functions again.
+    /// Callbacks nested deeper than this limit fail instead of exhausting the stack.
+    /// 
+    /// Default is `16`.
+    pub max_callback_depth: u32,
 }
 
 impl ModuleConfig {
@@ -112,6 +178,11 @@ impl ModuleConfig {
             relaxed_simd: false,
             relaxed_simd_deterministic: false,
             memory64: false,
+            profiler: ModuleProfiler::None,
+            validate_schemas: false,
+            codec: Codec::Json,
+            abi_version: None,
+            max_callback_depth: 16,
         }
     }
 
@@ -234,6 +305,66 @@ impl ModuleConfig {
         self.memory64 = enabled;
         self
     }
+
+    /// Set the native profiling strategy.
+    /// 
+    /// # Arguments
+    /// * `profiler` - The profiling strategy to set
+    /// 
+    /// # Returns
+    /// The updated ModuleFeatureFlags instance
+    pub fn with_profiler(mut self, profiler: ModuleProfiler) -> Self {
+        self.profiler = profiler;
+        self
+    }
+
+    /// Enable or disable validation of calls against their schemas.
+    /// 
+    /// # Arguments
+    /// * `enabled` - Whether to enable schema validation
+    /// 
+    /// # Returns
+    /// The updated ModuleFeatureFlags instance
+    pub fn with_validate_schemas(mut self, enabled: bool) -> Self {
+        self.validate_schemas = enabled;
+        self
+    }
+
+    /// Set the preferred wire format for function inputs and results.
+    /// 
+    /// # Arguments
+    /// * `codec` - The preferred codec
+    /// 
+    /// # Returns
+    /// The updated ModuleFeatureFlags instance
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Set the binmod ABI version implemented by the guest.
+    /// 
+    /// # Arguments
+    /// * `version` - The ABI version
+    /// 
+    /// # Returns
+    /// The updated ModuleFeatureFlags instance
+    pub fn with_abi_version(mut self, version: AbiVersion) -> Self {
+        self.abi_version = Some(version);
+        self
+    }
+
+    /// Set the maximum number of nested guest callbacks.
+    /// 
+    /// # Arguments
+    /// * `depth` - The maximum callback depth
+    /// 
+    /// # Returns
+    /// The updated ModuleFeatureFlags instance
+    pub fn with_max_callback_depth(mut self, depth: u32) -> Self {
+        self.max_callback_depth = depth;
+        self
+    }
 }
 
 impl Default for ModuleConfig {
@@ -264,6 +395,7 @@ impl From<ModuleConfig> for Config {
             .wasm_relaxed_simd(features.relaxed_simd)
             .relaxed_simd_deterministic(features.relaxed_simd_deterministic)
             .wasm_memory64(features.memory64)
+            .profiler(features.profiler.into())
             .wasm_multi_value(true)
             .parallel_compilation(true);
 
@@ -273,6 +405,7 @@ impl From<ModuleConfig> for Config {
 
 /// Struct for configuring module limits.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct ModuleLimits {
     /// The maximum number of bytes a linear memory can grow to.
     pub memory_size: i32,
@@ -344,6 +477,12 @@ impl From<SocketAddrUse> for ModuleSocketAddrAction {
     }
 }
 
+/// Function used to check whether a socket address and action is permitted.
+pub type SocketCheckFn = dyn Fn(SocketAddr, ModuleSocketAddrAction) -> Pin<Box<dyn Future<Output = bool> + Send + Sync>>
+    + Send
+    + Sync
+    + 'static;
+
 /// Struct representing network configuration for a module environment.
 #[derive(Clone)]
 pub struct ModuleNetwork {
@@ -354,12 +493,7 @@ pub struct ModuleNetwork {
     /// Allow DNS resolution.
     pub allow_dns: bool,
     /// A function to check whether a socket address and action is permitted.
-    pub socket_check: Arc<
-        dyn Fn(SocketAddr, ModuleSocketAddrAction) -> Pin<Box<dyn Future<Output = bool> + Send + Sync>>
-            + Send
-            + Sync
-            + 'static
-    >,
+    pub socket_check: Arc<SocketCheckFn>,
 }
 
 impl ModuleNetwork {
@@ -399,8 +533,13 @@ pub struct ModuleEnv {
     pub env: Option<HashMap<String, String>>,
     /// Host paths to mount into the module's filesystem.
     pub mount: Option<HashMap<String, PathBuf>>,
+    /// Host paths to mount read-only into the module's filesystem.
+    pub readonly_mount: Option<HashMap<String, PathBuf>>,
     /// Network configuration for the module.
     pub network: ModuleNetwork,
+    /// Package assets mounted into the module's filesystem, which are
+    /// removed once every environment referencing them is dropped.
+    mounted_assets: Vec<Arc<MountedAssets>>,
 }
 
 impl ModuleEnv {
@@ -410,7 +549,9 @@ impl ModuleEnv {
             args: None,
             env: None,
             mount: None,
+            readonly_mount: None,
             network: ModuleNetwork::default(),
+            mounted_assets: Vec::new(),
         }
     }
 
@@ -560,6 +701,43 @@ impl ModuleEnv {
         self
     }
 
+    /// Mount a host path read-only into the module's filesystem.
+    /// 
+    /// # Arguments
+    /// * `host_path` - The host path to mount
+    /// * `guest_path` - The guest path inside the module
+    /// 
+    /// # Returns
+    /// The updated ModuleEnv instance
+    pub fn mount_path_readonly(mut self, host_path: impl Into<PathBuf>, guest_path: impl Into<String>) -> Self {
+        match &mut self.readonly_mount {
+            Some(mounts) => { mounts.insert(guest_path.into(), host_path.into()); },
+            None => self.readonly_mount = Some(HashMap::from([(guest_path.into(), host_path.into())])),
+        }
+        self
+    }
+
+    /// Mount the extracted assets of a package read-only into the module's filesystem.
+    /// 
+    /// The assets stay on disk as long as the environment or a module using it is alive.
+    /// 
+    /// # Arguments
+    /// * `assets` - The assets extracted with [`Package::mount_assets`](crate::package::Package::mount_assets)
+    /// * `guest_path` - The guest path inside the module
+    /// 
+    /// # Returns
+    /// The updated ModuleEnv instance
+    pub fn mount_assets(self, assets: MountedAssets, guest_path: impl Into<String>) -> Self {
+        self.mount_shared_assets(Arc::new(assets), guest_path)
+    }
+
+    /// Mount assets shared with other environments read-only into the module's filesystem
+    pub(crate) fn mount_shared_assets(mut self, assets: Arc<MountedAssets>, guest_path: impl Into<String>) -> Self {
+        self = self.mount_path_readonly(assets.path(), guest_path);
+        self.mounted_assets.push(assets);
+        self
+    }
+
     /// Allow or disallow TCP connections.
     /// 
     /// # Arguments
@@ -621,41 +799,66 @@ impl Default for ModuleEnv {
     }
 }
 
-impl From<ModuleEnv> for WasiP1Ctx {
-    fn from(env: ModuleEnv) -> Self {
+impl ModuleEnv {
+    /// Create a WASI context builder configured from the environment
+    fn into_wasi_builder(self) -> WasiCtxBuilder {
         let mut builder = WasiCtx::builder();
 
-        if let Some(args) = env.args {
+        if let Some(args) = self.args {
             builder.args(&args);
         }
 
-        if let Some(env_vars) = env.env {
+        if let Some(env_vars) = self.env {
             for (key, value) in env_vars {
                 builder.env(&key, &value);
             }
         }
 
-        if let Some(mounts) = env.mount {
+        if let Some(mounts) = self.mount {
             for (guest_path, host_path) in mounts {
                 builder
                     .preopened_dir(
                         host_path,
                         &guest_path,
-                        // TODO: Support read only mounts
                         DirPerms::all(),
                         FilePerms::all(),
                     )
-                    .expect(&format!("failed to preopen dir {}", guest_path));
+                    .unwrap_or_else(|_| panic!("failed to preopen dir {}", guest_path));
+            }
+        }
+
+        if let Some(mounts) = self.readonly_mount {
+            for (guest_path, host_path) in mounts {
+                builder
+                    .preopened_dir(
+                        host_path,
+                        &guest_path,
+                        DirPerms::READ,
+                        FilePerms::READ,
+                    )
+                    .unwrap_or_else(|_| panic!("failed to preopen dir {}", guest_path));
             }
         }
 
-        builder.allow_tcp(env.network.allow_tcp);
-        builder.allow_udp(env.network.allow_udp);
-        builder.allow_ip_name_lookup(env.network.allow_dns);
+        builder.allow_tcp(self.network.allow_tcp);
+        builder.allow_udp(self.network.allow_udp);
+        builder.allow_ip_name_lookup(self.network.allow_dns);
         builder.socket_addr_check(move |addr, action| {
-            (env.network.socket_check)(addr, action.into())
+            (self.network.socket_check)(addr, action.into())
         });
 
-        builder.build_p1()
+        builder
+    }
+}
+
+impl From<ModuleEnv> for WasiP1Ctx {
+    fn from(env: ModuleEnv) -> Self {
+        env.into_wasi_builder().build_p1()
+    }
+}
+
+impl From<ModuleEnv> for WasiCtx {
+    fn from(env: ModuleEnv) -> Self {
+        env.into_wasi_builder().build()
     }
 }
\ No newline at end of file
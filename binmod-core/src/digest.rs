@@ -0,0 +1,33 @@
+use sha2::{Digest, Sha256};
+
+
+/// Counter incremented through the [`metrics`] facade every time a module or
+/// component is built, labelled with its `module` name and `content_hash`.
+pub const MODULES_LOADED_METRIC: &str = "binmod_modules_loaded_total";
+
+/// Counter incremented through the [`metrics`] facade every time a module or
+/// component is instantiated, labelled with its `module` name and `content_hash`.
+pub const INSTANCES_CREATED_METRIC: &str = "binmod_instances_created_total";
+
+/// Compute the content hash of a module binary.
+///
+/// # Arguments
+/// * `binary` - The WebAssembly binary to hash
+///
+/// # Returns
+/// The lowercase hex encoded SHA-256 digest of the binary
+pub fn content_hash(binary: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(binary))
+}
+
+/// Record a built module in the [`MODULES_LOADED_METRIC`](crate::digest::MODULES_LOADED_METRIC) counter
+pub(crate) fn record_loaded(name: &str, content_hash: &str) {
+    metrics::counter!(MODULES_LOADED_METRIC, "module" => name.to_string(), "content_hash" => content_hash.to_string())
+        .increment(1);
+}
+
+/// Record a new instance in the [`INSTANCES_CREATED_METRIC`](crate::digest::INSTANCES_CREATED_METRIC) counter
+pub(crate) fn record_instantiated(name: &str, content_hash: &str) {
+    metrics::counter!(INSTANCES_CREATED_METRIC, "module" => name.to_string(), "content_hash" => content_hash.to_string())
+        .increment(1);
+}
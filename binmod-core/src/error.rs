@@ -43,7 +43,7 @@ pub enum ModuleError {
 
     /// Wasmtime-specific errors
     #[error("Wasmtime error: {0}")]
-    WasmtimeError(#[from] wasmtime::Error),
+    WasmtimeError(wasmtime::Error),
 
     /// I/O related errors
     #[error("I/O error: {0}")]
@@ -57,6 +57,10 @@ pub enum ModuleError {
     #[error("Instantiation failed: {0}")]
     InstantiationError(String),
     
+    /// Error when the guest `initialize` export reports a failure
+    #[error("Initialization failed: {0}")]
+    InitializationFailed(FnError),
+
     /// Error when a module is not found during linking
     #[error("Module not found during linking: {0}")]
     ModuleNotFound(String),
@@ -64,6 +68,55 @@ pub enum ModuleError {
     /// Error for invalid module configuration
     #[error("Invalid module configuration: {0}")]
     InvalidModuleConfig(String),
+
+    /// Error when a module does not conform to the binmod ABI
+    #[error("Module does not conform to the binmod ABI: {0}")]
+    AbiError(String),
+
+    /// Error when a module binary differs from its pinned content hash
+    #[error("Module content hash mismatch: expected {expected}, found {actual}")]
+    ContentHashMismatch {
+        expected: String,
+        actual: String,
+    },
+
+    /// Error when a module binary is not signed by a trusted key
+    #[error("Invalid module signature: {0}")]
+    SignatureInvalid(String),
+
+    /// Errors related to reading or writing module packages
+    #[error("Invalid module package: {0}")]
+    PackageError(String),
+
+    /// Error when a call is cancelled through a
+    /// [`CancellationToken`](crate::cancel::CancellationToken)
+    #[error("Call cancelled")]
+    Cancelled,
+
+    /// Errors related to guest profiling
+    #[error("Profiling error: {0}")]
+    ProfilingError(String),
+
+    /// Errors related to streams exchanged with the guest
+    #[error("Stream error: {0}")]
+    StreamError(String),
+
+    /// Error when guest callbacks are nested deeper than the
+    /// configured maximum callback depth
+    #[error("Guest callback depth limit of {0} exceeded")]
+    CallbackDepthExceeded(u32),
+}
+
+impl From<wasmtime::Error> for ModuleError {
+    fn from(error: wasmtime::Error) -> Self {
+        // Host functions report failures as `ModuleError`s wrapped in an
+        // `anyhow::Error` to trap the guest, so we unwrap them here to
+        // surface the original error to the caller.
+        match error.downcast::<ModuleError>() {
+            Ok(error) => error,
+            Err(error) => ModuleError::WasmtimeError(error),
+        }
+    }
 }
 
 pub type ModuleResult<T> = Result<T, ModuleError>;
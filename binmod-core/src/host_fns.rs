@@ -1,13 +1,18 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::{any, collections::{BTreeMap, HashMap}, future::Future, marker::PhantomData, sync::Arc};
 use anyhow::Result;
+use futures::future::BoxFuture;
 use serde::de::DeserializeOwned;
-use wasmtime::{AsContextMut, Caller};
+use wasmtime::{AsContext, AsContextMut, Caller, StoreContextMut};
 
 use crate::{
-    state::ModuleState,
+    callback::{GuestCaller, AsyncGuestCaller},
+    codec::Codec,
+    error::{FnError, ModuleError},
+    state::{ModuleState, ComponentState},
     memory::{unpack_ptr, pack_ptr, MemoryOps, AsyncMemoryOps},
     input::{FromFnInput, FnInput},
-    result::{FnResult, IntoFnResult}
+    result::{FnResult, IntoFnResult},
+    schema::{DescribeHostFn, FnSchema},
 };
 
 
@@ -72,10 +77,71 @@ impl_host_fn_callable!(A1, A2, A3, A4, A5, A6);
 impl_host_fn_callable!(A1, A2, A3, A4, A5, A6, A7);
 impl_host_fn_callable!(A1, A2, A3, A4, A5, A6, A7, A8);
 
+/// Wrapper for host functions taking the function input directly
+struct InputHostFn<F>(F);
+
+impl<F> HostFnCallable for InputHostFn<F>
+where
+    F: Fn(&FnInput) -> FnResult + Send + Sync + 'static,
+{
+    fn call(&self, input: &FnInput) -> FnResult {
+        (self.0)(input)
+    }
+}
+
+/// Wrapper for host functions validating each call against their schema
+struct ValidatedHostFn {
+    func: Arc<dyn HostFnCallable>,
+    schema: Arc<FnSchema>,
+}
+
+impl HostFnCallable for ValidatedHostFn {
+    fn call(&self, input: &FnInput) -> FnResult {
+        validate_call(&self.schema, input, || self.func.call(input))
+    }
+}
+
+/// Validate the input and result of a call against a schema
+fn validate_call(schema: &FnSchema, input: &FnInput, call: impl FnOnce() -> FnResult) -> FnResult {
+    if let Err(e) = schema.validate_input(input) {
+        return FnResult::Error { error: e };
+    }
+
+    let result = call();
+
+    match schema.validate_result(&result) {
+        Ok(()) => result,
+        Err(e) => FnResult::Error { error: e },
+    }
+}
+
+/// Host function exchanging opaque bytes with the guest
+type RawFn = dyn Fn(&[u8]) -> Result<Vec<u8>, FnError> + Send + Sync;
+
+/// Host function calling back into the guest
+type CallbackFn = dyn Fn(&mut GuestCaller<'_, '_>, &FnInput) -> FnResult + Send + Sync;
+
+/// Host function calling back into the guest of an asynchronous module
+type AsyncCallbackFn = dyn for<'a, 'b, 'c> Fn(&'a mut AsyncGuestCaller<'b, 'c>, FnInput) -> BoxFuture<'a, FnResult> + Send + Sync;
+
+/// How a Host Function exchanges data with the guest
+#[derive(Clone)]
+enum HostFnKind {
+    /// Inputs and results encoded with the module's codec
+    Encoded(Arc<dyn HostFnCallable>),
+    /// Opaque bytes passed through unchanged
+    Raw(Arc<RawFn>),
+    /// Inputs and results encoded with the module's codec, with access to the guest
+    Callback(Arc<CallbackFn>),
+    /// Inputs and results encoded with the module's codec, with asynchronous access to the guest
+    AsyncCallback(Arc<AsyncCallbackFn>),
+}
+
 /// Represents a Host Function that can be called from a Wasmtime module
 #[derive(Clone)]
 pub struct HostFn {
-    func: Arc<dyn HostFnCallable>,
+    kind: HostFnKind,
+    schema: Option<Arc<FnSchema>>,
 }
 
 impl HostFn {
@@ -91,7 +157,225 @@ impl HostFn {
         HostFnWrapper<F, Args>: HostFnCallable + 'static,
     {
         Self {
-            func: Arc::new(HostFnWrapper::new(func)),
+            kind: HostFnKind::Encoded(Arc::new(HostFnWrapper::new(func))),
+            schema: None,
+        }
+    }
+
+    /// Create a new Host Function from a closure taking the raw function input.
+    /// 
+    /// # Arguments
+    /// * `func` - The closure handling the [`FnInput`](crate::input::FnInput) of each call
+    ///
+    /// # Returns
+    /// A new HostFn instance
+    pub fn from_fn<F>(func: F) -> Self
+    where
+        F: Fn(&FnInput) -> FnResult + Send + Sync + 'static,
+    {
+        Self {
+            kind: HostFnKind::Encoded(Arc::new(InputHostFn(func))),
+            schema: None,
+        }
+    }
+
+    /// Create a new Host Function exchanging opaque bytes with the guest.
+    /// 
+    /// The bytes written by the guest are passed to the closure as is, and the returned
+    /// bytes are written back without any encoding. An error traps the guest, since
+    /// there is no envelope to report it in.
+    /// 
+    /// # Arguments
+    /// * `func` - The closure handling the input bytes of each call
+    ///
+    /// # Returns
+    /// A new HostFn instance
+    pub fn raw<F, E>(func: F) -> Self
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, E> + Send + Sync + 'static,
+        E: ToString,
+    {
+        Self {
+            kind: HostFnKind::Raw(Arc::new(move |input: &[u8]| {
+                func(input).map_err(|e| FnError::new(any::type_name::<E>(), e.to_string()))
+            })),
+            schema: None,
+        }
+    }
+
+    /// Create a new Host Function that can call back into the guest.
+    /// 
+    /// The closure receives a [`GuestCaller`](crate::callback::GuestCaller) calling guest
+    /// exports by name while the Host Function is running. Asynchronous and component
+    /// modules do not support such Host Functions and fail calls to them, see
+    /// [`with_guest_async`](Self::with_guest_async) for asynchronous modules.
+    /// 
+    /// # Arguments
+    /// * `func` - The closure handling the [`FnInput`](crate::input::FnInput) of each call
+    ///
+    /// # Returns
+    /// A new HostFn instance
+    pub fn with_guest<F>(func: F) -> Self
+    where
+        F: Fn(&mut GuestCaller<'_, '_>, &FnInput) -> FnResult + Send + Sync + 'static,
+    {
+        Self {
+            kind: HostFnKind::Callback(Arc::new(func)),
+            schema: None,
+        }
+    }
+
+    /// Create a new Host Function that can call back into the guest of an
+    /// [`AsyncModule`](crate::module::AsyncModule).
+    /// 
+    /// The closure receives an [`AsyncGuestCaller`](crate::callback::AsyncGuestCaller)
+    /// whose calls are awaited on the executor driving the module. Synchronous and
+    /// component modules do not support such Host Functions and fail calls to them.
+    /// 
+    /// # Arguments
+    /// * `func` - The closure returning the future handling the [`FnInput`](crate::input::FnInput) of each call
+    ///
+    /// # Returns
+    /// A new HostFn instance
+    pub fn with_guest_async<F>(func: F) -> Self
+    where
+        F: for<'a, 'b, 'c> Fn(&'a mut AsyncGuestCaller<'b, 'c>, FnInput) -> BoxFuture<'a, FnResult> + Send + Sync + 'static,
+    {
+        Self {
+            kind: HostFnKind::AsyncCallback(Arc::new(func)),
+            schema: None,
+        }
+    }
+
+    /// Check if the Host Function exchanges opaque bytes with the guest.
+    pub fn is_raw(&self) -> bool {
+        matches!(self.kind, HostFnKind::Raw(_))
+    }
+
+    /// Attach a schema describing the arguments and return value of the Host Function.
+    /// 
+    /// # Arguments
+    /// * `schema` - The [`FnSchema`](crate::schema::FnSchema) of the function
+    ///
+    /// # Returns
+    /// The updated HostFn instance
+    pub fn with_schema(mut self, schema: FnSchema) -> Self {
+        self.schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Get the schema of the Host Function, if any.
+    pub fn schema(&self) -> Option<&FnSchema> {
+        self.schema.as_deref()
+    }
+
+    /// Validate each call of the Host Function against its schema, returning a
+    /// `ValidationError` to the guest for invalid inputs or results.
+    /// 
+    /// Host Functions without a schema, and raw Host Functions, are returned unchanged.
+    /// 
+    /// # Returns
+    /// The validating HostFn instance
+    pub fn validated(self) -> Self {
+        match (&self.kind, &self.schema) {
+            (HostFnKind::Encoded(func), Some(schema)) => Self {
+                kind: HostFnKind::Encoded(Arc::new(ValidatedHostFn {
+                    func: func.clone(),
+                    schema: schema.clone(),
+                })),
+                schema: self.schema.clone(),
+            },
+            (HostFnKind::Callback(func), Some(schema)) => {
+                let (func, schema) = (func.clone(), schema.clone());
+
+                Self {
+                    kind: HostFnKind::Callback(Arc::new(move |guest: &mut GuestCaller<'_, '_>, input: &FnInput| {
+                        validate_call(&schema, input, || func(guest, input))
+                    })),
+                    schema: self.schema.clone(),
+                }
+            },
+            (HostFnKind::AsyncCallback(func), Some(schema)) => {
+                let (func, schema) = (func.clone(), schema.clone());
+
+                Self {
+                    kind: HostFnKind::AsyncCallback(Arc::new(move |guest: &mut AsyncGuestCaller<'_, '_>, input: FnInput| {
+                        let (func, schema) = (func.clone(), schema.clone());
+
+                        Box::pin(async move {
+                            if let Err(e) = schema.validate_input(&input) {
+                                return FnResult::Error { error: e };
+                            }
+
+                            let result = func(guest, input).await;
+
+                            match schema.validate_result(&result) {
+                                Ok(()) => result,
+                                Err(e) => FnResult::Error { error: e },
+                            }
+                        })
+                    })),
+                    schema: self.schema.clone(),
+                }
+            },
+            _ => self,
+        }
+    }
+
+    /// Clone the Host Function for linking into a module, validating it if enabled
+    pub(crate) fn for_linking(&self, validate_schemas: bool) -> Self {
+        match validate_schemas {
+            true => self.clone().validated(),
+            false => self.clone(),
+        }
+    }
+
+    /// Call the Host Function with the bytes written by the guest
+    fn call_bytes(&self, input: &[u8], codec: Codec) -> Result<Vec<u8>, FnError> {
+        match &self.kind {
+            HostFnKind::Encoded(func) => func
+                .call(&FnInput::from_bytes_with(input, codec)?)
+                .to_bytes_with(codec),
+            HostFnKind::Raw(func) => func(input),
+            HostFnKind::Callback(_) | HostFnKind::AsyncCallback(_) => Err(FnError::new(
+                "UnsupportedError",
+                "Host functions calling back into the guest are not supported by component modules",
+            )),
+        }
+    }
+
+    /// Call the Host Function with the bytes written by the guest, giving
+    /// functions calling back into the guest access to the caller
+    fn call_guest_bytes(&self, caller: &mut Caller<ModuleState>, input: &[u8], codec: Codec) -> Result<Vec<u8>, FnError> {
+        match &self.kind {
+            HostFnKind::Callback(func) => func(
+                &mut GuestCaller::new(caller),
+                &FnInput::from_bytes_with(input, codec)?,
+            )
+            .to_bytes_with(codec),
+            HostFnKind::AsyncCallback(_) => Err(FnError::new(
+                "UnsupportedError",
+                "Host functions calling back into the guest asynchronously are not supported by synchronous modules",
+            )),
+            _ => self.call_bytes(input, codec),
+        }
+    }
+
+    /// Call the Host Function with the bytes written by the guest, giving functions
+    /// calling back into the guest asynchronous access to the caller
+    async fn call_guest_bytes_async(&self, caller: &mut Caller<'_, ModuleState>, input: &[u8], codec: Codec) -> Result<Vec<u8>, FnError> {
+        match &self.kind {
+            HostFnKind::AsyncCallback(func) => func(
+                &mut AsyncGuestCaller::new(caller),
+                FnInput::from_bytes_with(input, codec)?,
+            )
+            .await
+            .to_bytes_with(codec),
+            HostFnKind::Callback(_) => Err(FnError::new(
+                "UnsupportedError",
+                "Host functions calling back into the guest synchronously are not supported by async modules",
+            )),
+            _ => self.call_bytes(input, codec),
         }
     }
 
@@ -101,57 +385,307 @@ impl HostFn {
     /// A closure that can be used as a Wasmtime host function
     pub fn into_func(self) -> impl Fn(Caller<ModuleState>, u64) -> Result<u64> {
         move |mut caller: Caller<ModuleState>, ptr: u64| -> Result<u64> {
+            let ModuleState { codec, abi_version, .. } = *caller.data();
             let memory = MemoryOps::from_caller(&mut caller)?;
             let (input_ptr, input_len) = unpack_ptr(ptr);
-            let input = FnInput::from_bytes(
-                &memory.read(
+            // The guest keeps ownership of the input from ABI version 2 onwards.
+            let input = match (&self.kind, input_len) {
+                (HostFnKind::Raw(_), 0) => vec![],
+                _ if abi_version.caller_frees_input() => memory.peek(
+                    caller.as_context(),
+                    input_ptr,
+                    input_len,
+                )?,
+                _ => memory.receive(
                     caller.as_context_mut(),
                     input_ptr,
                     input_len,
-                )?
-            )?;
-            let (result_ptr, result_len) = memory.write(
+                )?,
+            };
+            let result = self
+                .call_guest_bytes(&mut caller, &input, codec)
+                .map_err(ModuleError::FunctionError)?;
+            let (result_ptr, result_len) = memory.send_result(
                 caller.as_context_mut(),
-                &self.func
-                    .call(&input)
-                    .to_bytes()?,
+                &result,
             )?;
 
-            Ok(pack_ptr(result_ptr, result_len))
+            Ok(pack_ptr(result_ptr, result_len)?)
         }
     }
 
-    /// Convert the Host Function into a Wasmtime function, handling
-    /// asynchronous memory operations for input and output when using
-    /// an [`AsyncModule`](crate::module::AsyncModule).
+    /// Convert the Host Function into an asynchronous Wasmtime function for use with an
+    /// [`AsyncModule`](crate::module::AsyncModule), awaiting memory operations and
+    /// guest callbacks on the executor driving the call.
     /// 
     /// # Returns
-    /// A closure that can be used as a Wasmtime host function
-    pub fn into_func_async(self) -> impl Fn(Caller<ModuleState>, u64) -> Result<u64> {
-        move |mut caller: Caller<ModuleState>, ptr: u64| -> Result<u64> {
-            futures::executor::block_on(async {
-                let memory = AsyncMemoryOps::from_caller(&mut caller)?;
-                let (input_ptr, input_len) = unpack_ptr(ptr);
-                let input = FnInput::from_bytes(
-                    &memory
-                        .read(
-                            caller.as_context_mut(),
-                            input_ptr,
-                            input_len,
-                        )
-                        .await?
-                )?;
-                let (result_ptr, result_len) = memory
-                    .write(
-                        caller.as_context_mut(),
-                        &self.func
-                            .call(&input)
-                            .to_bytes()?,
-                    )
-                    .await?;
-
-                Ok(pack_ptr(result_ptr, result_len))
-            })
-        }
-    }
-}
\ No newline at end of file
+    /// A closure that can be used as a Wasmtime async host function
+    pub fn into_func_async(self) -> impl for<'a> Fn(Caller<'a, ModuleState>, (u64,)) -> Box<dyn Future<Output = Result<u64>> + Send + 'a> + Send + Sync + 'static {
+        move |caller: Caller<'_, ModuleState>, (ptr,): (u64,)| -> Box<dyn Future<Output = Result<u64>> + Send + '_> {
+            Box::new(self.clone().call_async(caller, ptr))
+        }
+    }
+
+    /// Handle a call from the guest of an asynchronous module
+    async fn call_async(self, mut caller: Caller<'_, ModuleState>, ptr: u64) -> Result<u64> {
+        let ModuleState { codec, abi_version, .. } = *caller.data();
+        let memory = AsyncMemoryOps::from_caller(&mut caller)?;
+        let (input_ptr, input_len) = unpack_ptr(ptr);
+        // The guest keeps ownership of the input from ABI version 2 onwards.
+        let input = match (&self.kind, input_len) {
+            (HostFnKind::Raw(_), 0) => vec![],
+            _ if abi_version.caller_frees_input() => memory.peek(
+                caller.as_context(),
+                input_ptr,
+                input_len,
+            )?,
+            _ => memory
+                .receive(
+                    caller.as_context_mut(),
+                    input_ptr,
+                    input_len,
+                )
+                .await?,
+        };
+        let result = self
+            .call_guest_bytes_async(&mut caller, &input, codec)
+            .await
+            .map_err(ModuleError::FunctionError)?;
+        let (result_ptr, result_len) = memory
+            .send_result(
+                caller.as_context_mut(),
+                &result,
+            )
+            .await?;
+
+        Ok(pack_ptr(result_ptr, result_len)?)
+    }
+
+    /// Convert the Host Function into a component host function for use with a
+    /// [`ComponentModule`](crate::component::ComponentModule) or
+    /// [`AsyncComponentModule`](crate::component::AsyncComponentModule).
+    /// 
+    /// The function is imported as `func(input: string) -> string`, where the input
+    /// and result are the JSON encoded [`FnInput`](crate::input::FnInput) and
+    /// [`FnResult`](crate::result::FnResult).
+    /// 
+    /// # Returns
+    /// A closure that can be used as a Wasmtime component host function
+    pub fn into_component_func(self) -> impl Fn(StoreContextMut<ComponentState>, (String,)) -> Result<(String,)> {
+        move |_store: StoreContextMut<ComponentState>, (input,): (String,)| -> Result<(String,)> {
+            let result = self.call_bytes(input.as_bytes(), Codec::Json)?;
+
+            Ok((String::from_utf8(result)?,))
+        }
+    }
+
+    /// Convert the Host Function into a component host function exchanging bytes.
+    /// 
+    /// The function is imported as `func(input: list<u8>) -> list<u8>`, which is how
+    /// raw Host Functions are linked into component modules.
+    /// 
+    /// # Returns
+    /// A closure that can be used as a Wasmtime component host function
+    pub fn into_component_raw_func(self) -> impl Fn(StoreContextMut<ComponentState>, (Vec<u8>,)) -> Result<(Vec<u8>,)> {
+        move |_store: StoreContextMut<ComponentState>, (input,): (Vec<u8>,)| -> Result<(Vec<u8>,)> {
+            Ok((self.call_bytes(&input, Codec::Json)?,))
+        }
+    }
+}
+
+/// A set of host functions registered together, optionally
+/// under a namespace other than the module's.
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let clock = HostFnSet::new()
+///     .with_namespace("clock")
+///     .host_fn("now", || -> Result<u64, String> { Ok(42) });
+/// 
+/// let module = Module::builder()
+///     .from_file("my_module.wasm")?
+///     .with_name("my_module")
+///     .with_host_fns(clock)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct HostFnSet {
+    namespace: Option<String>,
+    host_fns: HashMap<String, HostFn>,
+}
+
+impl HostFnSet {
+    /// Create a new, empty set of host functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the namespace the host functions are imported from.
+    /// 
+    /// Without a namespace the functions are registered under the module's namespace.
+    /// 
+    /// # Arguments
+    /// * `namespace` - The namespace to set
+    /// 
+    /// # Returns
+    /// The updated HostFnSet instance
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Add a host function to the set.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The Rust function or closure to be wrapped as a Host Function
+    /// 
+    /// # Returns
+    /// The updated HostFnSet instance
+    pub fn host_fn<F, Args>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        HostFnWrapper<F, Args>: HostFnCallable + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::new(func));
+        self
+    }
+
+    /// Add a host function to the set, describing it with a schema derived
+    /// from its argument and return types.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The Rust function or closure to be wrapped as a Host Function
+    /// 
+    /// # Returns
+    /// The updated HostFnSet instance
+    pub fn host_fn_with_schema<F, Args>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: DescribeHostFn<Args>,
+        HostFnWrapper<F, Args>: HostFnCallable + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::new(func).with_schema(F::describe()));
+        self
+    }
+
+    /// Add a host function exchanging opaque bytes with the guest to the set.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The closure handling the input bytes of each call
+    /// 
+    /// # Returns
+    /// The updated HostFnSet instance
+    pub fn host_fn_raw<F, E>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, E> + Send + Sync + 'static,
+        E: ToString,
+    {
+        self.host_fns.insert(name.into(), HostFn::raw(func));
+        self
+    }
+
+    /// Add a host function calling back into the guest to the set.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The closure handling each call, given a [`GuestCaller`](crate::callback::GuestCaller)
+    /// 
+    /// # Returns
+    /// The updated HostFnSet instance
+    pub fn host_fn_with_guest<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&mut GuestCaller<'_, '_>, &FnInput) -> FnResult + Send + Sync + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::with_guest(func));
+        self
+    }
+
+    /// Add a host function calling back into the guest of an asynchronous module to the set.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The closure returning the future handling each call, given an
+    ///   [`AsyncGuestCaller`](crate::callback::AsyncGuestCaller)
+    /// 
+    /// # Returns
+    /// The updated HostFnSet instance
+    pub fn host_fn_with_guest_async<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: for<'a, 'b, 'c> Fn(&'a mut AsyncGuestCaller<'b, 'c>, FnInput) -> BoxFuture<'a, FnResult> + Send + Sync + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::with_guest_async(func));
+        self
+    }
+
+    /// Insert a host function into the set, replacing any function with the same name.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `host_fn` - The host function
+    pub fn insert(&mut self, name: impl Into<String>, host_fn: HostFn) {
+        self.host_fns.insert(name.into(), host_fn);
+    }
+
+    /// Get the namespace of the set, if any.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Get the names of the host functions in the set.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.host_fns.keys().map(String::as_str)
+    }
+
+    /// Get the number of host functions in the set.
+    pub fn len(&self) -> usize {
+        self.host_fns.len()
+    }
+
+    /// Check if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.host_fns.is_empty()
+    }
+}
+
+/// Trait for types exposing their methods as a set of host functions
+/// bound to a shared instance, usually implemented with the
+/// `#[binmod::host_functions]` attribute.
+pub trait HostFunctions: Send + Sync + 'static {
+    /// Create the set of host functions bound to the instance.
+    fn host_fn_set(self: Arc<Self>) -> HostFnSet;
+}
+
+impl<T: HostFunctions> From<Arc<T>> for HostFnSet {
+    fn from(instance: Arc<T>) -> Self {
+        instance.host_fn_set()
+    }
+}
+
+/// Group a module's host functions and host function sets by the namespace they are imported from
+pub(crate) fn group_host_fns<'a>(
+    namespace: &'a str,
+    host_fns: &'a HashMap<String, HostFn>,
+    host_fn_sets: &'a [HostFnSet],
+) -> BTreeMap<&'a str, Vec<(&'a str, &'a HostFn)>> {
+    let mut groups = BTreeMap::<&str, Vec<_>>::new();
+
+    for (name, host_fn) in host_fns {
+        groups.entry(namespace).or_default().push((name.as_str(), host_fn));
+    }
+
+    for set in host_fn_sets {
+        let group = groups.entry(set.namespace().unwrap_or(namespace)).or_default();
+
+        for (name, host_fn) in &set.host_fns {
+            group.push((name.as_str(), host_fn));
+        }
+    }
+
+    groups
+}
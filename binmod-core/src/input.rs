@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize, de::DeserializeOwned};
-use serde_json::{to_value, to_vec, from_value, from_slice, Value, Error as JsonError};
+use serde_json::{to_value, from_value, Value, Error as JsonError};
 
-use crate::error::{ModuleResult, FnError};
+use crate::{codec::Codec, error::{ModuleResult, FnError}};
 
 
 /// Represents the input arguments for a function call
@@ -28,6 +28,29 @@ impl FnInput {
         }
     }
 
+    /// Create a Function input from a JSON value.
+    /// 
+    /// An array is used as the positional arguments and an object as the keyword
+    /// arguments, while `null` creates an empty input.
+    /// 
+    /// # Arguments
+    /// * `value` - The JSON value to create the input from
+    /// 
+    /// # Returns
+    /// A Result containing the [`FnInput`](crate::input::FnInput) instance or an
+    /// [`FnError`](crate::error::FnError) if the value is not an array, object or `null`
+    pub fn from_json(value: Value) -> Result<Self, FnError> {
+        match value {
+            Value::Array(args) => Ok(Self { args: Some(args), kwargs: None }),
+            Value::Object(kwargs) => Ok(Self { args: None, kwargs: Some(kwargs.into_iter().collect()) }),
+            Value::Null => Ok(Self::new()),
+            other => Err(FnError::new(
+                "InvalidInput",
+                format!("Expected an array of arguments or an object of keyword arguments, found {}", other),
+            )),
+        }
+    }
+
     /// Add a positional argument to the function input.
     /// 
     /// # Arguments
@@ -142,6 +165,26 @@ impl FnInput {
         Ok(self)
     }
 
+    /// Check if a positional argument is present.
+    /// 
+    /// # Arguments
+    /// * `index` - The index of the argument
+    pub fn has_arg(&self, index: usize) -> bool {
+        self.args
+            .as_ref()
+            .is_some_and(|args| index < args.len())
+    }
+
+    /// Check if a keyword argument is present.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the keyword argument
+    pub fn has_kwarg(&self, name: &str) -> bool {
+        self.kwargs
+            .as_ref()
+            .is_some_and(|kwargs| kwargs.contains_key(name))
+    }
+
     /// Get a positional argument by index.
     /// 
     /// # Arguments
@@ -154,11 +197,11 @@ impl FnInput {
     where
         T: DeserializeOwned,
     {
-        if let Some(args) = &self.args {
-            if index < args.len() {
-                return from_value(args[index].clone())
-                    .map_err(|e| FnError::new("DeserializationError", format!("Failed to parse argument {}: {}", index, e)));
-            }
+        if let Some(args) = &self.args
+            && index < args.len()
+        {
+            return from_value(args[index].clone())
+                .map_err(|e| FnError::new("DeserializationError", format!("Failed to parse argument {}: {}", index, e)));
         }
 
         Err(FnError::new("MissingArg", format!("Missing arg in position {}", index)))
@@ -176,11 +219,11 @@ impl FnInput {
     where 
         T: DeserializeOwned,
     {
-        if let Some(kwargs) = &self.kwargs {
-            if let Some(value) = kwargs.get(name) {
-                return from_value(value.clone())
-                    .map_err(|e| FnError::new("DeserializationError", format!("Failed to parse kwarg '{}': {}", name, e)));
-            }
+        if let Some(kwargs) = &self.kwargs
+            && let Some(value) = kwargs.get(name)
+        {
+            return from_value(value.clone())
+                .map_err(|e| FnError::new("DeserializationError", format!("Failed to parse kwarg '{}': {}", name, e)));
         }
 
         Err(FnError::new("MissingKwarg", format!("Missing kwarg: {}", name)))
@@ -195,12 +238,10 @@ impl FnInput {
     where
         T: DeserializeOwned,
     {
-        Ok(
-            from_value(Value::Array(
-                self.args.unwrap_or_default()
-            ))
-            .map_err(|e| FnError::new("DeserializationError", e.to_string()))?
-        )
+        from_value(Value::Array(
+            self.args.unwrap_or_default()
+        ))
+        .map_err(|e| FnError::new("DeserializationError", e.to_string()))
     }
 
     /// Convert the keyword arguments into a Rust type.
@@ -212,30 +253,37 @@ impl FnInput {
     where
         T: DeserializeOwned,
     {
-        Ok(
-            from_value(Value::Object(
-                self.kwargs
-                    .unwrap_or_default()
-                    .into_iter()
-                    .collect()
-            ))
-            .map_err(|e| FnError::new("DeserializationError", e.to_string()))?
-        )
+        from_value(Value::Object(
+            self.kwargs
+                .unwrap_or_default()
+                .into_iter()
+                .collect()
+        ))
+        .map_err(|e| FnError::new("DeserializationError", e.to_string()))
     }
 
-    /// Serialize the Function input to bytes.
+    /// Serialize the Function input to JSON bytes.
     /// 
     /// # Returns
     /// A Result containing the serialized bytes or an [`FnError`](crate::error::FnError)
     /// if serialization fails
     pub fn to_bytes(&self) -> Result<Vec<u8>, FnError> {
-        Ok(
-            to_vec(self)
-                .map_err(|e| FnError::new("SerializationError", e.to_string()))?
-        )
+        self.to_bytes_with(Codec::Json)
     }
 
-    /// Deserialize Function input from bytes.
+    /// Serialize the Function input to bytes using the given codec.
+    /// 
+    /// # Arguments
+    /// * `codec` - The codec to serialize with
+    /// 
+    /// # Returns
+    /// A Result containing the serialized bytes or an [`FnError`](crate::error::FnError)
+    /// if serialization fails
+    pub fn to_bytes_with(&self, codec: Codec) -> Result<Vec<u8>, FnError> {
+        codec.encode(self)
+    }
+
+    /// Deserialize Function input from JSON bytes.
     /// 
     /// # Arguments
     /// * `bytes` - The bytes to deserialize from
@@ -244,10 +292,20 @@ impl FnInput {
     /// A Result containing the deserialized [`FnInput`](crate::input::FnInput) instance
     /// or an [`FnError`](crate::error::FnError) if deserialization fails
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, FnError> {
-        Ok(
-            from_slice(bytes)
-                .map_err(|e| FnError::new("DeserializationError", e.to_string()))?
-        )
+        Self::from_bytes_with(bytes, Codec::Json)
+    }
+
+    /// Deserialize Function input from bytes using the given codec.
+    /// 
+    /// # Arguments
+    /// * `bytes` - The bytes to deserialize from
+    /// * `codec` - The codec to deserialize with
+    /// 
+    /// # Returns
+    /// A Result containing the deserialized [`FnInput`](crate::input::FnInput) instance
+    /// or an [`FnError`](crate::error::FnError) if deserialization fails
+    pub fn from_bytes_with(bytes: &[u8], codec: Codec) -> Result<Self, FnError> {
+        codec.decode(bytes)
     }
 }
 
@@ -0,0 +1,154 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    input::FnInput,
+    module::{Module, AsyncModule},
+    error::{FnError, ModuleResult},
+};
+
+
+/// Builder for a call mixing positional and keyword arguments, created
+/// with [`Module::invoke`](crate::module::Module::invoke).
+/// 
+/// Arguments failing to serialize are reported when the call is made.
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # fn example(mut module: Module) -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let greeting: String = module
+///     .invoke("greet")
+///     .arg("Alice")
+///     .kwarg("punctuation", "!")
+///     .call()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Invocation<'a> {
+    module: &'a mut Module,
+    name: String,
+    input: Result<FnInput, FnError>,
+}
+
+impl<'a> Invocation<'a> {
+    pub(crate) fn new(module: &'a mut Module, name: impl Into<String>) -> Self {
+        Self {
+            module,
+            name: name.into(),
+            input: Ok(FnInput::default()),
+        }
+    }
+
+    /// Add a positional argument to the call.
+    /// 
+    /// # Arguments
+    /// * `arg` - The argument to add
+    /// 
+    /// # Returns
+    /// The updated Invocation instance
+    pub fn arg<T: Serialize>(mut self, arg: T) -> Self {
+        self.input = self.input.and_then(|input| input.with_arg(arg));
+        self
+    }
+
+    /// Add a keyword argument to the call.
+    /// 
+    /// # Arguments
+    /// * `key` - The name of the keyword argument
+    /// * `value` - The value of the keyword argument
+    /// 
+    /// # Returns
+    /// The updated Invocation instance
+    pub fn kwarg<T: Serialize>(mut self, key: impl Into<String>, value: T) -> Self {
+        self.input = self.input.and_then(|input| input.with_kwarg(key, value));
+        self
+    }
+
+    /// Call the function with the collected arguments.
+    /// 
+    /// # Returns
+    /// A result containing the return value of the function or an error
+    /// if an argument failed to serialize, the call fails or the module is not instantiated
+    pub fn call<R: DeserializeOwned>(self) -> ModuleResult<R> {
+        Ok(
+            self.module
+                .call(&self.name, self.input?)?
+                .into_result::<R>()?
+        )
+    }
+}
+
+/// Builder for a call mixing positional and keyword arguments, created
+/// with [`AsyncModule::invoke`](crate::module::AsyncModule::invoke).
+/// 
+/// Arguments failing to serialize are reported when the call is made.
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # async fn example(mut module: AsyncModule) -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let greeting: String = module
+///     .invoke("greet")
+///     .arg("Alice")
+///     .kwarg("punctuation", "!")
+///     .call()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncInvocation<'a> {
+    module: &'a mut AsyncModule,
+    name: String,
+    input: Result<FnInput, FnError>,
+}
+
+impl<'a> AsyncInvocation<'a> {
+    pub(crate) fn new(module: &'a mut AsyncModule, name: impl Into<String>) -> Self {
+        Self {
+            module,
+            name: name.into(),
+            input: Ok(FnInput::default()),
+        }
+    }
+
+    /// Add a positional argument to the call.
+    /// 
+    /// # Arguments
+    /// * `arg` - The argument to add
+    /// 
+    /// # Returns
+    /// The updated AsyncInvocation instance
+    pub fn arg<T: Serialize>(mut self, arg: T) -> Self {
+        self.input = self.input.and_then(|input| input.with_arg(arg));
+        self
+    }
+
+    /// Add a keyword argument to the call.
+    /// 
+    /// # Arguments
+    /// * `key` - The name of the keyword argument
+    /// * `value` - The value of the keyword argument
+    /// 
+    /// # Returns
+    /// The updated AsyncInvocation instance
+    pub fn kwarg<T: Serialize>(mut self, key: impl Into<String>, value: T) -> Self {
+        self.input = self.input.and_then(|input| input.with_kwarg(key, value));
+        self
+    }
+
+    /// Call the function with the collected arguments.
+    /// 
+    /// # Returns
+    /// A result containing the return value of the function or an error
+    /// if an argument failed to serialize, the call fails or the module is not instantiated
+    pub async fn call<R: DeserializeOwned>(self) -> ModuleResult<R> {
+        Ok(
+            self.module
+                .call(&self.name, self.input?)
+                .await?
+                .into_result::<R>()?
+        )
+    }
+}
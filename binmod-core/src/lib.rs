@@ -1,12 +1,30 @@
 #[allow(unused_extern_crates)]
 extern crate self as binmod_core;
 
+#[doc(hidden)]
+pub use serde;
+
+pub mod abi;
+pub(crate) mod artifacts;
+pub mod callback;
+pub mod cancel;
+pub mod codec;
+pub mod component;
 pub mod config;
+pub mod digest;
 pub mod error;
 pub mod memory;
 pub mod module;
 pub mod host_fns;
 pub mod input;
+pub mod invoke;
+pub mod package;
 pub mod result;
+pub mod schema;
+pub(crate) mod setup;
+pub mod signature;
+pub mod stream;
 pub mod pool;
+pub(crate) mod profiling;
+pub mod reload;
 pub mod state;
\ No newline at end of file
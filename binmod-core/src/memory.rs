@@ -3,6 +3,24 @@ use wasmtime::{AsContextMut, AsContext, Caller, Instance, Memory, Store, TypedFu
 use crate::{state::ModuleState, error::{ModuleError, ModuleResult}};
 
 
+/// Optional guest export providing a reusable buffer for call inputs.
+/// 
+/// The export has the signature `(u32) -> u32`. It receives the minimum size of the
+/// buffer and returns a pointer to a buffer of at least that size, which must stay
+/// valid until the export is called again. Data placed in the buffer is never freed
+/// through `guest_dealloc`, and a call's input written to it stays valid for the
+/// whole call since host function results are never written to it.
+pub const EXCHANGE_BUFFER_EXPORT: &str = "binmod_exchange_buffer";
+
+/// Optional guest export providing a reusable buffer for host function results.
+/// 
+/// The export has the same signature and rules as
+/// [`EXCHANGE_BUFFER_EXPORT`](crate::memory::EXCHANGE_BUFFER_EXPORT), but must return a
+/// buffer distinct from it. A host function result written to it is only valid until
+/// the guest calls another host function. Without the export, host function results
+/// are allocated through `guest_alloc`.
+pub const RESULT_BUFFER_EXPORT: &str = "binmod_result_buffer";
+
 /// Pack a pointer and length into a single u64 value
 /// 
 /// The higher 32 bits contain the pointer, and the lower 32 bits contain the length.
@@ -13,9 +31,10 @@ use crate::{state::ModuleState, error::{ModuleError, ModuleResult}};
 /// * `len` - The length value (u32)
 /// 
 /// # Returns
-/// A u64 value combining the pointer and length
-pub fn pack_ptr(ptr: u32, len: usize) -> u64 {
-    ((ptr as u64) << 32) | (len as u64)
+/// A result containing the u64 value combining the pointer and length,
+/// or an error if the length does not fit in 32 bits
+pub fn pack_ptr(ptr: u32, len: usize) -> ModuleResult<u64> {
+    Ok(((ptr as u64) << 32) | (guest_len(len)? as u64))
 }
 
 
@@ -30,6 +49,140 @@ pub fn unpack_ptr(packed: u64) -> (u32, usize) {
     ((packed >> 32) as u32, (packed & 0xFFFFFFFF) as usize)
 }
 
+/// Convert a length to the 32 bit length passed to the guest
+/// 
+/// # Arguments
+/// * `len` - The length to convert
+/// 
+/// # Returns
+/// The length as u32 or an error if it exceeds the guest's address space
+pub fn guest_len(len: usize) -> ModuleResult<u32> {
+    u32::try_from(len)
+        .map_err(|_| ModuleError::MemoryError(format!("length {} exceeds the guest address space", len)))
+}
+
+/// Look up the guest's `guest_alloc` export from within a host function
+/// 
+/// # Arguments
+/// * `caller` - The caller context of the host function
+/// 
+/// # Returns
+/// The typed `guest_alloc` function or an error if it is missing or mistyped
+pub fn guest_alloc_from_caller(caller: &mut Caller<'_, ModuleState>) -> ModuleResult<TypedFunc<u32, u32>> {
+    caller
+        .get_export("guest_alloc")
+        .and_then(|e| e.into_func())
+        .ok_or_else(|| ModuleError::MemoryError("failed to find guest_alloc".to_string()))?
+        .typed::<u32, u32>(&caller)
+        .map_err(|e| ModuleError::MemoryError(format!("failed to type guest_alloc: {}", e)))
+}
+
+/// Look up the guest's `guest_dealloc` export from within a host function
+/// 
+/// # Arguments
+/// * `caller` - The caller context of the host function
+/// 
+/// # Returns
+/// The typed `guest_dealloc` function or an error if it is missing or mistyped
+pub fn guest_dealloc_from_caller(caller: &mut Caller<'_, ModuleState>) -> ModuleResult<TypedFunc<(u32, u32), ()>> {
+    caller
+        .get_export("guest_dealloc")
+        .and_then(|e| e.into_func())
+        .ok_or_else(|| ModuleError::MemoryError("failed to find guest_dealloc".to_string()))?
+        .typed::<(u32, u32), ()>(&caller)
+        .map_err(|e| ModuleError::MemoryError(format!("failed to type guest_dealloc: {}", e)))
+}
+
+/// Reusable buffer exported by the guest through `binmod_exchange_buffer`
+/// or `binmod_result_buffer`.
+/// 
+/// Call inputs, or host function results, are written to the buffer instead of
+/// being allocated per call, and the buffer is only grown when data exceeds it.
+#[derive(Clone)]
+pub struct ExchangeBuffer {
+    export: &'static str,
+    grow_fn: TypedFunc<u32, u32>,
+    ptr: u32,
+    capacity: u32,
+}
+
+impl ExchangeBuffer {
+    /// Look up an exchange buffer export of an instance
+    /// 
+    /// # Arguments
+    /// * `instance` - The instance to look up the export in
+    /// * `store` - The store of the instance
+    /// * `export` - The name of the export, either
+    ///   [`EXCHANGE_BUFFER_EXPORT`](crate::memory::EXCHANGE_BUFFER_EXPORT) or
+    ///   [`RESULT_BUFFER_EXPORT`](crate::memory::RESULT_BUFFER_EXPORT)
+    /// 
+    /// # Returns
+    /// The exchange buffer, or `None` if the guest does not export one
+    pub fn from_instance(instance: &Instance, store: &mut Store<ModuleState>, export: &'static str) -> ModuleResult<Option<Self>> {
+        let Some(func) = instance.get_func(store.as_context_mut(), export) else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            export,
+            grow_fn: func
+                .typed::<u32, u32>(store.as_context())
+                .map_err(|e| ModuleError::MemoryError(format!("failed to type {}: {}", export, e)))?,
+            ptr: 0,
+            capacity: 0,
+        }))
+    }
+
+    /// Get the current capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.capacity as usize
+    }
+
+    /// Check if a region of guest memory lies within the buffer
+    fn contains(&self, ptr: u32, len: usize) -> bool {
+        self.capacity > 0
+            && ptr >= self.ptr
+            && ptr as u64 + len as u64 <= self.ptr as u64 + self.capacity as u64
+    }
+
+    /// Get the size to grow the buffer to for the given data, if it does not fit
+    fn grow_size(&self, len: usize) -> ModuleResult<Option<u32>> {
+        if len <= self.capacity as usize {
+            return Ok(None);
+        }
+
+        Ok(Some(guest_len(len)?.max(self.capacity.saturating_mul(2))))
+    }
+}
+
+/// Which exchange buffer of an instance data is sent through
+#[derive(Clone, Copy)]
+enum Exchange {
+    /// The buffer holding call inputs
+    Input,
+    /// The buffer holding host function results
+    Result,
+}
+
+impl Exchange {
+    fn buffer(self, state: &mut ModuleState) -> &mut Option<ExchangeBuffer> {
+        match self {
+            Self::Input => &mut state.exchange,
+            Self::Result => &mut state.result_exchange,
+        }
+    }
+}
+
+/// Check if a region of guest memory lies within an exchange buffer of an instance
+fn in_exchange(ctx: impl AsContext<Data = ModuleState>, ptr: u32, len: usize) -> bool {
+    let state = ctx.as_context().data();
+
+    [&state.exchange, &state.result_exchange]
+        .into_iter()
+        .flatten()
+        .any(|exchange| exchange.contains(ptr, len))
+}
+
 #[derive(Clone)]
 pub struct MemoryOps {
     memory: Memory,
@@ -74,18 +227,8 @@ impl MemoryOps {
                 .get_export("memory")
                 .and_then(|e| e.into_memory())
                 .ok_or_else(|| ModuleError::MemoryError("failed to find memory export".to_string()))?,
-            alloc_fn: caller
-                .get_export("guest_alloc")
-                .and_then(|e| e.into_func())
-                .ok_or_else(|| ModuleError::MemoryError("failed to find guest_alloc".to_string()))?
-                .typed::<u32, u32>(&caller)
-                .map_err(|e| ModuleError::MemoryError(format!("failed to type guest_alloc: {}", e)))?,
-            dealloc_fn: caller
-                .get_export("guest_dealloc")
-                .and_then(|e| e.into_func())
-                .ok_or_else(|| ModuleError::MemoryError("failed to find guest_dealloc".to_string()))?
-                .typed::<(u32, u32), ()>(&caller)
-                .map_err(|e| ModuleError::MemoryError(format!("failed to type guest_dealloc: {}", e)))?,
+            alloc_fn: guest_alloc_from_caller(caller)?,
+            dealloc_fn: guest_dealloc_from_caller(caller)?,
         })
     }
 
@@ -100,11 +243,9 @@ impl MemoryOps {
     /// 
     /// A pointer to the allocated memory in the guest module
     pub fn alloc(&self, mut ctx: impl AsContextMut, size: usize) -> ModuleResult<u32> {
-        Ok(
-            self.alloc_fn
-                .call(ctx.as_context_mut(), size as u32)
-                .map_err(|e| ModuleError::MemoryError(format!("Guest alloc failed: {}", e)))?
-        )
+        self.alloc_fn
+            .call(ctx.as_context_mut(), guest_len(size)?)
+            .map_err(|e| ModuleError::MemoryError(format!("Guest alloc failed: {}", e)))
     }
 
     /// Deallocate memory in the guest module
@@ -120,7 +261,7 @@ impl MemoryOps {
     /// A result indicating success or failure
     pub fn dealloc(&self, mut ctx: impl AsContextMut, ptr: u32, size: usize) -> ModuleResult<()> {
         self.dealloc_fn
-            .call(ctx.as_context_mut(), (ptr, size as u32))
+            .call(ctx.as_context_mut(), (ptr, guest_len(size)?))
             .map_err(|e| ModuleError::MemoryError(format!("Guest dealloc failed: {}", e)))?;
 
         Ok(())
@@ -147,7 +288,7 @@ impl MemoryOps {
         Ok((ptr, size))
     }
 
-    /// Read data from the guest module's memory
+    /// Read data from the guest module's memory without deallocating it
     /// 
     /// # Arguments
     /// 
@@ -158,7 +299,7 @@ impl MemoryOps {
     /// # Returns
     /// 
     /// A vector containing the read data
-    pub fn read(&self, mut ctx: impl AsContextMut, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
+    pub fn peek(&self, ctx: impl AsContext, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
         if ptr == 0 || len == 0 {
             return Err(ModuleError::MemoryError(
                 "Null pointer or zero length".to_string(),
@@ -167,15 +308,119 @@ impl MemoryOps {
 
         let mut buffer = vec![0u8; len];
         self.memory
-            .read(ctx.as_context_mut(), ptr as usize, &mut buffer)
+            .read(ctx, ptr as usize, &mut buffer)
             .map_err(|e| ModuleError::MemoryError(format!("Memory read failed: {}", e)))?;
 
+        Ok(buffer)
+    }
+
+    /// Read data from the guest module's memory
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The store context
+    /// * `ptr` - The pointer to the data to read
+    /// * `len` - The length of the data to read
+    /// 
+    /// # Returns
+    /// 
+    /// A vector containing the read data
+    pub fn read(&self, mut ctx: impl AsContextMut, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
+        let buffer = self.peek(ctx.as_context(), ptr, len)?;
+
         self.dealloc(ctx.as_context_mut(), ptr, len)?;
 
         Ok(buffer)
     }
+
+    /// Write a call input for the guest, into the exchange buffer if the guest exports one
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `data` - The data to write
+    /// 
+    /// # Returns
+    /// 
+    /// A tuple containing the pointer to the written data and its size
+    pub fn send(&self, ctx: impl AsContextMut<Data = ModuleState>, data: &[u8]) -> ModuleResult<(u32, usize)> {
+        self.send_through(ctx, data, Exchange::Input)
+    }
+
+    /// Write a host function result for the guest, into the result buffer if the guest exports one
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `data` - The data to write
+    /// 
+    /// # Returns
+    /// 
+    /// A tuple containing the pointer to the written data and its size
+    pub fn send_result(&self, ctx: impl AsContextMut<Data = ModuleState>, data: &[u8]) -> ModuleResult<(u32, usize)> {
+        self.send_through(ctx, data, Exchange::Result)
+    }
+
+    fn send_through(&self, mut ctx: impl AsContextMut<Data = ModuleState>, data: &[u8], through: Exchange) -> ModuleResult<(u32, usize)> {
+        let Some(mut exchange) = through.buffer(ctx.as_context_mut().data_mut()).clone() else {
+            return self.write(ctx, data);
+        };
+
+        if let Some(size) = exchange.grow_size(data.len())? {
+            exchange.ptr = exchange.grow_fn
+                .call(ctx.as_context_mut(), size)
+                .map_err(|e| ModuleError::MemoryError(format!("{} grow failed: {}", exchange.export, e)))?;
+            exchange.capacity = size;
+            *through.buffer(ctx.as_context_mut().data_mut()) = Some(exchange.clone());
+        }
+
+        self.memory
+            .write(ctx.as_context_mut(), exchange.ptr as usize, data)
+            .map_err(|e| ModuleError::MemoryError(format!("Memory write failed: {}", e)))?;
+
+        Ok((exchange.ptr, data.len()))
+    }
+
+    /// Read data written by the guest, freeing it unless it lies in an exchange buffer
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `ptr` - The pointer to the data to read
+    /// * `len` - The length of the data to read
+    /// 
+    /// # Returns
+    /// 
+    /// A vector containing the read data
+    pub fn receive(&self, mut ctx: impl AsContextMut<Data = ModuleState>, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
+        if in_exchange(ctx.as_context(), ptr, len) {
+            self.peek(ctx.as_context(), ptr, len)
+        } else {
+            self.read(ctx.as_context_mut(), ptr, len)
+        }
+    }
+
+    /// Free data sent to the guest, unless it lies in an exchange buffer
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `ptr` - The pointer to the data to free
+    /// * `len` - The length of the data to free
+    /// 
+    /// # Returns
+    /// 
+    /// A result indicating success or failure
+    pub fn release(&self, mut ctx: impl AsContextMut<Data = ModuleState>, ptr: u32, len: usize) -> ModuleResult<()> {
+        if in_exchange(ctx.as_context(), ptr, len) {
+            Ok(())
+        } else {
+            self.dealloc(ctx.as_context_mut(), ptr, len)
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct AsyncMemoryOps {
     memory: Memory,
     alloc_fn: TypedFunc<u32, u32>,
@@ -219,18 +464,8 @@ impl AsyncMemoryOps {
                 .get_export("memory")
                 .and_then(|e| e.into_memory())
                 .ok_or_else(|| ModuleError::MemoryError("failed to find memory export".to_string()))?,
-            alloc_fn: caller
-                .get_export("guest_alloc")
-                .and_then(|e| e.into_func())
-                .ok_or_else(|| ModuleError::MemoryError("failed to find guest_alloc".to_string()))?
-                .typed::<u32, u32>(&caller)
-                .map_err(|e| ModuleError::MemoryError(format!("failed to type guest_alloc: {}", e)))?,
-            dealloc_fn: caller
-                .get_export("guest_dealloc")
-                .and_then(|e| e.into_func())
-                .ok_or_else(|| ModuleError::MemoryError("failed to find guest_dealloc".to_string()))?
-                .typed::<(u32, u32), ()>(&caller)
-                .map_err(|e| ModuleError::MemoryError(format!("failed to type guest_dealloc: {}", e)))?,
+            alloc_fn: guest_alloc_from_caller(caller)?,
+            dealloc_fn: guest_dealloc_from_caller(caller)?,
         })
     }
 
@@ -248,12 +483,10 @@ impl AsyncMemoryOps {
     where
         T: Send + 'static,
     {
-        Ok(
-            self.alloc_fn
-                .call_async(ctx.as_context_mut(), size as u32)
-                .await
-                .map_err(|e| ModuleError::MemoryError(format!("Guest alloc failed: {}", e)))?
-        )
+        self.alloc_fn
+            .call_async(ctx.as_context_mut(), guest_len(size)?)
+            .await
+            .map_err(|e| ModuleError::MemoryError(format!("Guest alloc failed: {}", e)))
     }
 
     /// Deallocate memory in the guest module
@@ -272,7 +505,7 @@ impl AsyncMemoryOps {
         T: Send + 'static,
     {
         self.dealloc_fn
-            .call_async(ctx.as_context_mut(), (ptr, size as u32))
+            .call_async(ctx.as_context_mut(), (ptr, guest_len(size)?))
             .await
             .map_err(|e| ModuleError::MemoryError(format!("Guest dealloc failed: {}", e)))?;
 
@@ -303,7 +536,7 @@ impl AsyncMemoryOps {
         Ok((ptr, size))
     }
 
-    /// Read data from the guest module's memory
+    /// Read data from the guest module's memory without deallocating it
     /// 
     /// # Arguments
     /// 
@@ -314,7 +547,7 @@ impl AsyncMemoryOps {
     /// # Returns
     /// 
     /// A vector containing the read data
-    pub async fn read(&self, mut ctx: impl AsContextMut<Data: Send>, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
+    pub fn peek(&self, ctx: impl AsContext, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
         if ptr == 0 || len == 0 {
             return Err(ModuleError::MemoryError(
                 "Null pointer or zero length".to_string(),
@@ -323,11 +556,115 @@ impl AsyncMemoryOps {
 
         let mut buffer = vec![0u8; len];
         self.memory
-            .read(ctx.as_context_mut(), ptr as usize, &mut buffer)
+            .read(ctx, ptr as usize, &mut buffer)
             .map_err(|e| ModuleError::MemoryError(format!("Memory read failed: {}", e)))?;
 
+        Ok(buffer)
+    }
+
+    /// Read data from the guest module's memory
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The store context
+    /// * `ptr` - The pointer to the data to read
+    /// * `len` - The length of the data to read
+    /// 
+    /// # Returns
+    /// 
+    /// A vector containing the read data
+    pub async fn read(&self, mut ctx: impl AsContextMut<Data: Send>, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
+        let buffer = self.peek(ctx.as_context(), ptr, len)?;
+
         self.dealloc(ctx.as_context_mut(), ptr, len).await?;
         
         Ok(buffer)
     }
+
+    /// Write a call input for the guest, into the exchange buffer if the guest exports one
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `data` - The data to write
+    /// 
+    /// # Returns
+    /// 
+    /// A tuple containing the pointer to the written data and its size
+    pub async fn send(&self, ctx: impl AsContextMut<Data = ModuleState>, data: &[u8]) -> ModuleResult<(u32, usize)> {
+        self.send_through(ctx, data, Exchange::Input).await
+    }
+
+    /// Write a host function result for the guest, into the result buffer if the guest exports one
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `data` - The data to write
+    /// 
+    /// # Returns
+    /// 
+    /// A tuple containing the pointer to the written data and its size
+    pub async fn send_result(&self, ctx: impl AsContextMut<Data = ModuleState>, data: &[u8]) -> ModuleResult<(u32, usize)> {
+        self.send_through(ctx, data, Exchange::Result).await
+    }
+
+    async fn send_through(&self, mut ctx: impl AsContextMut<Data = ModuleState>, data: &[u8], through: Exchange) -> ModuleResult<(u32, usize)> {
+        let Some(mut exchange) = through.buffer(ctx.as_context_mut().data_mut()).clone() else {
+            return self.write(ctx, data).await;
+        };
+
+        if let Some(size) = exchange.grow_size(data.len())? {
+            exchange.ptr = exchange.grow_fn
+                .call_async(ctx.as_context_mut(), size)
+                .await
+                .map_err(|e| ModuleError::MemoryError(format!("{} grow failed: {}", exchange.export, e)))?;
+            exchange.capacity = size;
+            *through.buffer(ctx.as_context_mut().data_mut()) = Some(exchange.clone());
+        }
+
+        self.memory
+            .write(ctx.as_context_mut(), exchange.ptr as usize, data)
+            .map_err(|e| ModuleError::MemoryError(format!("Memory write failed: {}", e)))?;
+
+        Ok((exchange.ptr, data.len()))
+    }
+
+    /// Read data written by the guest, freeing it unless it lies in an exchange buffer
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `ptr` - The pointer to the data to read
+    /// * `len` - The length of the data to read
+    /// 
+    /// # Returns
+    /// 
+    /// A vector containing the read data
+    pub async fn receive(&self, mut ctx: impl AsContextMut<Data = ModuleState>, ptr: u32, len: usize) -> ModuleResult<Vec<u8>> {
+        if in_exchange(ctx.as_context(), ptr, len) {
+            self.peek(ctx.as_context(), ptr, len)
+        } else {
+            self.read(ctx.as_context_mut(), ptr, len).await
+        }
+    }
+
+    /// Free data sent to the guest, unless it lies in an exchange buffer
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The mutable store context
+    /// * `ptr` - The pointer to the data to free
+    /// * `len` - The length of the data to free
+    /// 
+    /// # Returns
+    /// 
+    /// A result indicating success or failure
+    pub async fn release(&self, mut ctx: impl AsContextMut<Data = ModuleState>, ptr: u32, len: usize) -> ModuleResult<()> {
+        if in_exchange(ctx.as_context(), ptr, len) {
+            Ok(())
+        } else {
+            self.dealloc(ctx.as_context_mut(), ptr, len).await
+        }
+    }
 }
\ No newline at end of file
@@ -1,25 +1,55 @@
-use std::{collections::HashMap, path::Path, fs};
-use wasmtime::{Engine, Instance, InstancePre, Store, Module as WasmModule, Caller, Linker, Config, AsContextMut, AsContext};
+use std::{collections::{BTreeMap, HashMap}, path::{Path, PathBuf}, fs, io::{Read, Write}, future::Future, marker::PhantomData, sync::{Arc, atomic::{AtomicU64, Ordering}}, time::Duration};
+use wasmtime::{Engine, Instance, InstancePre, Store, Caller, Linker, Config, GuestProfiler, TypedFunc, AsContextMut, AsContext};
 use wasmtime_wasi::p1;
+use futures::{future::{self, BoxFuture, Either}, io::{AsyncRead, AsyncWrite}, pin_mut};
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 
 use crate::{
+    abi::{self, AbiReport, AbiVersion},
+    artifacts,
+    callback::{GuestCaller, AsyncGuestCaller},
+    digest,
+    cancel::{CancellationToken, CancelTimer},
+    codec::{self, Codec},
+    component::{ComponentModule, AsyncComponentModule},
+    package::{MountedAssets, Package, PackageManifest},
+    signature::{self, VerifyingKey, SIGNATURE_EXTENSION},
     input::{FnInput, IntoFnInput},
+    invoke::{Invocation, AsyncInvocation},
     result::FnResult,
+    schema::{DescribeHostFn, InterfaceSchema},
     state::ModuleState,
-    host_fns::{HostFn, HostFnCallable, HostFnWrapper},
-    memory::{MemoryOps, AsyncMemoryOps, unpack_ptr},
+    stream::{self, StreamHandle, StreamTable},
+    host_fns::{self, HostFn, HostFnCallable, HostFnSet, HostFnWrapper},
+    memory::{MemoryOps, AsyncMemoryOps, ExchangeBuffer, EXCHANGE_BUFFER_EXPORT, RESULT_BUFFER_EXPORT, unpack_ptr, guest_alloc_from_caller, guest_dealloc_from_caller},
     config::{ModuleEnv, ModuleConfig, ModuleLimits},
+    profiling::{Sampler, EpochTicker},
     error::{ModuleResult, ModuleError},
 };
 
 
+/// Default deadline for the guest finalizer invoked when a module shuts down.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Source of the ids distinguishing instances, so function handles
+/// resolved on one instance are never used with the store of another.
+static NEXT_INSTANCE_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_instance_id() -> u64 {
+    NEXT_INSTANCE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Typed function export of a guest, as called through the binmod ABI
+type GuestFunc = TypedFunc<(u32, u32), u64>;
+
 /// Represents a Binmod Module with host functions, and provides methods
 /// to instantiate and call functions within the module.
 /// 
 /// # Examples
-/// ```rust
+/// ```rust,no_run
 /// use binmod::{module::Module, config::ModuleEnv};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// 
 /// let mut module = Module::builder()
 ///     .from_file("path/to/module.wasm")
@@ -41,20 +71,33 @@ use crate::{
 /// let result: String = module
 ///     .typed_call("guest_function", (42, "Hello".to_string()))?;
 /// println!("Result from guest function: {}", result);
+/// # Ok(())
+/// # }
 /// ```
 pub struct Module {
     name: String,
     namespace: String,
     binary: Vec<u8>,
+    content_hash: String,
     environment: ModuleEnv,
     config: ModuleConfig,
     limits: ModuleLimits,
     host_fns: HashMap<String, HostFn>,
+    host_fn_sets: Vec<HostFnSet>,
+    interface: Option<Arc<InterfaceSchema>>,
+    init_input: FnInput,
+    shutdown_timeout: Duration,
+    abi_report: Option<AbiReport>,
     engine: Option<Engine>,
     store: Option<Store<ModuleState>>,
     linker: Option<Linker<ModuleState>>,
     instance_pre: Option<InstancePre<ModuleState>>,
     instance: Option<Instance>,
+    instance_id: u64,
+    memory: Option<MemoryOps>,
+    epoch_deadline: Option<u64>,
+    profiling: Option<Sampler>,
+    epoch_ticker: Option<EpochTicker>,
 }
 
 impl Module {
@@ -83,16 +126,27 @@ impl Module {
         Self {
             name: name.into(),
             namespace: namespace.into(),
+            content_hash: digest::content_hash(&binary),
             binary,
             environment,
             config,
             limits,
             host_fns,
+            host_fn_sets: Vec::new(),
+            interface: None,
+            init_input: FnInput::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            abi_report: None,
             engine: None,
             store: None,
             linker: None,
             instance_pre: None,
             instance: None,
+            instance_id: 0,
+            memory: None,
+            epoch_deadline: None,
+            profiling: None,
+            epoch_ticker: None,
         }
     }
 
@@ -117,11 +171,167 @@ impl Module {
         &self.binary
     }
 
+    /// Get the content hash of the module binary.
+    /// 
+    /// # Returns
+    /// The lowercase hex encoded SHA-256 digest of the binary
+    pub fn content_hash(&self) -> &str {
+        &self.content_hash
+    }
+
     /// Get the environment configuration of the module.
     pub fn environment(&self) -> &ModuleEnv {
         &self.environment
     }
 
+    /// Get the input passed to the guest `initialize` export.
+    pub fn init_input(&self) -> &FnInput {
+        &self.init_input
+    }
+
+    /// Set the input passed to the guest `initialize` export.
+    /// 
+    /// Only takes effect the next time the module is instantiated.
+    /// 
+    /// # Arguments
+    /// * `input` - The input to pass to `initialize`
+    pub fn set_init_input(&mut self, input: FnInput) {
+        self.init_input = input;
+    }
+
+    /// Set the host function sets registered alongside the module's host functions.
+    /// 
+    /// Only takes effect the next time the module is linked.
+    pub(crate) fn set_host_fn_sets(&mut self, host_fn_sets: Vec<HostFnSet>) {
+        self.host_fn_sets = host_fn_sets;
+    }
+
+    /// Get the interface schema describing the module's exports, if any.
+    pub fn interface_schema(&self) -> Option<&InterfaceSchema> {
+        self.interface.as_deref()
+    }
+
+    /// Set the interface schema describing the module's exports.
+    /// 
+    /// Calls are validated against it when
+    /// [`validate_schemas`](crate::config::ModuleConfig::validate_schemas) is enabled.
+    /// 
+    /// # Arguments
+    /// * `interface` - The interface schema of the module
+    pub fn set_interface_schema(&mut self, interface: InterfaceSchema) {
+        self.interface = Some(Arc::new(interface));
+    }
+
+    /// Get the report of the ABI check run on the binary, if it has been checked.
+    /// 
+    /// Binaries are checked when the module is built, or on the first
+    /// instantiation for modules created with `new`.
+    pub fn abi_report(&self) -> Option<&AbiReport> {
+        self.abi_report.as_ref()
+    }
+
+    /// Set the report of the ABI check run on the binary, so it is not checked again.
+    pub(crate) fn set_abi_report(&mut self, report: AbiReport) {
+        self.abi_report = Some(report);
+    }
+
+    /// Get the binmod ABI version implemented by the guest, if the module is instantiated.
+    pub fn abi_version(&self) -> Option<AbiVersion> {
+        self.store
+            .as_ref()
+            .map(|store| store.data().abi_version)
+    }
+
+    /// Get the wire format negotiated with the guest, if the module is instantiated.
+    pub fn codec(&self) -> Option<Codec> {
+        self.store
+            .as_ref()
+            .map(|store| store.data().codec)
+    }
+
+    /// Open a stream the guest can read in chunks, see [`StreamHandle`](crate::stream::StreamHandle).
+    /// 
+    /// # Arguments
+    /// * `reader` - The reader providing the stream's data
+    /// 
+    /// # Returns
+    /// A result containing the handle to pass to the guest or an error if the module is not instantiated
+    pub fn open_reader(&mut self, reader: impl Read + Send + 'static) -> ModuleResult<StreamHandle> {
+        Ok(self.streams()?.open_reader(reader))
+    }
+
+    /// Open a stream the guest can write in chunks, see [`StreamHandle`](crate::stream::StreamHandle).
+    /// 
+    /// # Arguments
+    /// * `writer` - The writer receiving the stream's data
+    /// 
+    /// # Returns
+    /// A result containing the handle to pass to the guest or an error if the module is not instantiated
+    pub fn open_writer(&mut self, writer: impl Write + Send + 'static) -> ModuleResult<StreamHandle> {
+        Ok(self.streams()?.open_writer(writer))
+    }
+
+    /// Remove a stream opened with [`open_reader`](Self::open_reader) and take back its reader.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result containing the reader or an error if the stream is unknown or not a reader of type `R`
+    pub fn take_reader<R: Read + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<R> {
+        self.streams()?.take_reader(handle)
+    }
+
+    /// Remove a stream opened with [`open_writer`](Self::open_writer) and take back its flushed writer.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result containing the writer or an error if the stream is unknown or not a writer of type `W`
+    pub fn take_writer<W: Write + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<W> {
+        self.streams()?.take_writer(handle)
+    }
+
+    /// Remove a stream, dropping its reader or writer.
+    /// 
+    /// Streams are also dropped when the module is shut down.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result indicating whether the stream was open or an error if the module is not instantiated
+    pub fn close_stream(&mut self, handle: StreamHandle) -> ModuleResult<bool> {
+        Ok(self.streams()?.close(handle))
+    }
+
+    fn streams(&mut self) -> ModuleResult<&mut StreamTable> {
+        Ok(
+            &mut self.store
+                .as_mut()
+                .ok_or(ModuleError::NotInstantiated)?
+                .data_mut()
+                .streams
+        )
+    }
+
+    /// Get the deadline for the guest finalizer invoked on shutdown.
+    pub fn shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    /// Set the deadline for the guest finalizer invoked on shutdown.
+    /// 
+    /// The deadline is only enforced if epoch interruption is enabled in the
+    /// [`ModuleConfig`](crate::config::ModuleConfig), and must be greater than zero.
+    /// 
+    /// # Arguments
+    /// * `timeout` - The deadline to set
+    pub fn set_shutdown_timeout(&mut self, timeout: Duration) {
+        self.shutdown_timeout = timeout;
+    }
+
     /// Check if the module has been instantiated.
     pub fn is_instantiated(&self) -> bool {
         self.instance.is_some()
@@ -151,13 +361,11 @@ impl Module {
     /// A result containing the remaining fuel or an error
     /// if fuel is not enabled or the module is not instantiated
     pub fn get_fuel(&mut self) -> ModuleResult<u64> {
-        Ok(
-            self.store
-                .as_mut()
-                .ok_or(ModuleError::NotInstantiated)?
-                .get_fuel()
-                .map_err(|_| ModuleError::FuelNotEnabled)?
-        )
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .get_fuel()
+            .map_err(|_| ModuleError::FuelNotEnabled)
     }
 
     /// Set the epoch deadline for the module's store.
@@ -169,14 +377,30 @@ impl Module {
     /// A result indicating success or an error
     /// if the module is not instantiated
     pub fn set_epoch_deadline(&mut self, deadline: u64) -> ModuleResult<()> {
-        self.store
+        let store = self.store
             .as_mut()
-            .ok_or(ModuleError::NotInstantiated)?
-            .set_epoch_deadline(deadline);
+            .ok_or(ModuleError::NotInstantiated)?;
+
+        // The profiler owns the store's deadline while it runs,
+        // so it is reinstalled to enforce the new deadline.
+        if self.profiling.is_some() {
+            self.profiling = Some(Sampler::install(store, Some(deadline)));
+        } else {
+            store.set_epoch_deadline(deadline);
+        }
+        self.epoch_deadline = Some(deadline);
     
         Ok(())
     }
 
+    /// Get the engine the module is compiled with, once it has been instantiated.
+    /// 
+    /// The engine is shared with clones of the module. It can be cloned and moved
+    /// to another thread to increment the epoch while the module is running.
+    pub fn engine(&self) -> Option<&Engine> {
+        self.engine.as_ref()
+    }
+
     /// Increment the epoch for the module's store.
     /// 
     /// # Returns
@@ -201,54 +425,50 @@ impl Module {
             return Err(ModuleError::AlreadyInstantiated);
         }
 
-        if !self.engine.is_some() {
+        if self.engine.is_none() {
             let engine = Engine::new(&self.config.clone().into())?;
             let mut linker = Linker::<ModuleState>::new(&engine);
 
             linker.func_wrap(
                 "binmod",
                 "host_alloc",
-                |mut caller: Caller<ModuleState>, size: u32| -> u32 {
-                    caller
-                        .get_export("guest_alloc")
-                        .and_then(|e| e.into_func())
-                        .ok_or_else(|| anyhow::anyhow!("failed to find guest_alloc"))
-                        .unwrap()
-                        .typed::<u32, u32>(&caller)
-                        .unwrap()
-                        .call(&mut caller, size)
-                        .unwrap()
+                |mut caller: Caller<ModuleState>, size: u32| -> anyhow::Result<u32> {
+                    Ok(
+                        guest_alloc_from_caller(&mut caller)?
+                            .call(&mut caller, size)
+                            .map_err(|e| ModuleError::MemoryError(format!("Guest alloc failed: {}", e)))?
+                    )
                 }
             )?;
             linker.func_wrap(
                 "binmod",
                 "host_dealloc",
-                |mut caller: Caller<ModuleState>, ptr: u32, size: u32| {
-                    caller
-                        .get_export("guest_dealloc")
-                        .and_then(|e| e.into_func())
-                        .ok_or_else(|| anyhow::anyhow!("failed to find guest_dealloc"))
-                        .unwrap()
-                        .typed::<(u32, u32), ()>(&caller)
-                        .unwrap()
+                |mut caller: Caller<ModuleState>, ptr: u32, size: u32| -> anyhow::Result<()> {
+                    guest_dealloc_from_caller(&mut caller)?
                         .call(&mut caller, (ptr, size))
-                        .unwrap();
+                        .map_err(|e| ModuleError::MemoryError(format!("Guest dealloc failed: {}", e)))?;
+
+                    Ok(())
                 }
             )?;
 
-            for (name, host_fn) in &self.host_fns {
-                linker.func_wrap(
-                    &self.namespace,
-                    name,
-                    host_fn.clone().into_func(),
-                )?;
+            stream::add_to_linker(&mut linker)?;
+
+            for (namespace, group) in host_fns::group_host_fns(&self.namespace, &self.host_fns, &self.host_fn_sets) {
+                for (name, host_fn) in group {
+                    linker.func_wrap(
+                        namespace,
+                        name,
+                        host_fn.for_linking(self.config.validate_schemas).into_func(),
+                    )?;
+                }
             }
 
             self.engine = Some(engine);
             self.linker = Some(linker);
         }
 
-        if !self.instance_pre.is_some() {
+        if self.instance_pre.is_none() {
             p1::add_to_linker_sync(
                 self.linker
                     .as_mut()
@@ -256,14 +476,18 @@ impl Module {
                 |state| &mut state.wasi,
             )?;
 
+            if self.abi_report.is_none() {
+                self.abi_report = Some(abi::check(&self.binary).into_result()?);
+            }
+
+            let module = artifacts::compile_module(self.engine.as_ref().expect("engine should be intialized"), &self.binary, &self.content_hash)
+                .map_err(|e| ModuleError::InstantiationError(format!("failed to compile module {}: {}", self.content_hash, e)))?;
+
             self.instance_pre = Some(
                 self.linker
                     .as_mut()
                     .expect("linker should be initialized")
-                    .instantiate_pre(
-                        &WasmModule::from_binary(self.engine.as_ref().expect("engine should be intialized"), &self.binary)
-                            .map_err(|e| ModuleError::InstantiationError(format!("failed to compile module: {}", e)))?
-                    )
+                    .instantiate_pre(&module)
                     .map_err(|e| ModuleError::InstantiationError(format!("failed to create instance pre: {}", e)))?
             )
         }
@@ -279,6 +503,14 @@ impl Module {
                 limits: self.limits
                     .clone()
                     .into(),
+                profiler: None,
+                abi_version: AbiVersion::V1,
+                codec: Codec::Json,
+                streams: StreamTable::default(),
+                exchange: None,
+                result_exchange: None,
+                callback_depth: 0,
+                max_callback_depth: self.config.max_callback_depth,
             }
         );
         store.limiter(|s| &mut s.limits);
@@ -292,7 +524,23 @@ impl Module {
         );
 
         self.store = Some(store);
+        self.instance_id = next_instance_id();
+        log::debug!("instantiated module '{}' ({}) as instance {}", self.name, self.content_hash, self.instance_id);
+        digest::record_instantiated(&self.name, &self.content_hash);
+        self.memory = None;
+        self.epoch_deadline = None;
+
+        if let Err(e) = self.initialize_instance() {
+            // The guest never finished initializing, so it must not be finalized on drop
+            self.discard();
+            return Err(e);
+        }
+
+        Ok(self)
+    }
 
+    /// Run the guest initializers and set up the ABI state of a new instance
+    fn initialize_instance(&mut self) -> ModuleResult<()> {
         // Invoke method `_initialize` directly through wasmtime's API
         // instead of relying on invoking via binmod because binmod will try to invoke guest exported
         // methods and crash if the initializer haven't been called yet.
@@ -324,14 +572,23 @@ impl Module {
                 .map_err(|e| ModuleError::InstantiationError(format!("failed to call _initialize: {}", e)))?;
         }
 
-        // Now we invoke the binmod initializer `initialize` if it exists.
-        if let Err(e) = self.typed_call::<()>("initialize", ()) {
-            if !matches!(e, ModuleError::FunctionNotFound(_)) {
-                return Err(e);
-            }
+        // Resolve the ABI version, negotiate the wire format and look up the
+        // exchange buffers before the first call into the guest.
+        let store = self.store.as_mut().unwrap();
+        store.data_mut().abi_version = abi::resolve_version(self.instance.as_ref().unwrap(), store, self.config.abi_version)?;
+        store.data_mut().codec = codec::negotiate(self.instance.as_ref().unwrap(), store, self.config.codec)?;
+        store.data_mut().exchange = ExchangeBuffer::from_instance(self.instance.as_ref().unwrap(), store, EXCHANGE_BUFFER_EXPORT)?;
+        store.data_mut().result_exchange = ExchangeBuffer::from_instance(self.instance.as_ref().unwrap(), store, RESULT_BUFFER_EXPORT)?;
+
+        // Now we invoke the binmod initializer `initialize` if it exists
+        // with the binmod signature.
+        if self.abi_report.as_ref().is_some_and(|report| report.initialize.is_valid())
+            && let FnResult::Error { error } = self.call("initialize", self.init_input.clone())?
+        {
+            return Err(ModuleError::InitializationFailed(error));
         }
 
-        Ok(self)
+        Ok(())
     }
 
     /// Call a function within the module with typed arguments and return value.
@@ -354,6 +611,34 @@ impl Module {
         )
     }
 
+    /// Call a function within the module with dynamic JSON arguments and return value.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `args` - An array of positional arguments or an object of keyword arguments
+    /// 
+    /// # Returns
+    /// A result containing the JSON return value of the function or an error
+    /// if the arguments are invalid, the call fails or the module is not instantiated
+    pub fn call_json(&mut self, name: impl AsRef<str>, args: Value) -> ModuleResult<Value> {
+        Ok(
+            self
+            .call(name.as_ref(), FnInput::from_json(args)?)?
+            .into_result::<Value>()?
+        )
+    }
+
+    /// Start building a call mixing positional and keyword arguments.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// 
+    /// # Returns
+    /// An [`Invocation`](crate::invoke::Invocation) collecting the arguments of the call
+    pub fn invoke(&mut self, name: impl Into<String>) -> Invocation<'_> {
+        Invocation::new(self, name)
+    }
+
     /// Call a function within the module.
     /// 
     /// # Arguments
@@ -364,30 +649,328 @@ impl Module {
     /// A result containing the [`FnResult`](crate::result::FnResult) of the function call or an error
     /// if the call fails or the module is not instantiated
     pub fn call(&mut self, name: impl AsRef<str>, input: FnInput) -> ModuleResult<FnResult> {
+        let func = self.resolve_func(name.as_ref())?;
+
+        self.call_func(name.as_ref(), func, input)
+    }
+
+    /// Resolve a function within the module once, returning a handle for repeated calls.
+    /// 
+    /// The handle caches the function export, so calling it skips the lookup by name
+    /// done by [`call`](crate::module::Module::call). Handles are bound to the current
+    /// instance and must be resolved again once the module is instantiated again.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to resolve
+    /// 
+    /// # Returns
+    /// A result containing the [`ModuleFn`](crate::module::ModuleFn) handle or an error
+    /// if the function is not found or the module is not instantiated
+    pub fn func<Args, R>(&mut self, name: impl Into<String>) -> ModuleResult<ModuleFn<Args, R>>
+    where
+        Args: IntoFnInput,
+        R: DeserializeOwned,
+    {
+        let name = name.into();
+        let func = self.resolve_func(&name)?;
+
+        Ok(ModuleFn {
+            name,
+            instance_id: self.instance_id,
+            func,
+            _marker: PhantomData,
+        })
+    }
+
+    fn resolve_func(&mut self, name: &str) -> ModuleResult<GuestFunc> {
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+
+        instance
+            .get_typed_func::<(u32, u32), u64>(store.as_context_mut(), name)
+            .map_err(|e| ModuleError::FunctionNotFound(format!("failed to get function '{}': {}", name, e)))
+    }
+
+    /// Get the memory operations of the instance, looking up the exports on first use
+    fn memory_ops(&mut self) -> ModuleResult<MemoryOps> {
+        if let Some(memory) = &self.memory {
+            return Ok(memory.clone());
+        }
+
         let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
         let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
         let memory = MemoryOps::from_instance(instance, store)?;
 
-        let func = instance
-            .get_typed_func::<(u32, u32), u64>(store.as_context_mut(), name.as_ref())
-            .map_err(|e| ModuleError::FunctionNotFound(format!("failed to get function '{}': {}", name.as_ref(), e)))?;
+        Ok(self.memory.insert(memory).clone())
+    }
+
+    fn call_func(&mut self, name: &str, func: GuestFunc, input: FnInput) -> ModuleResult<FnResult> {
+        let interface = self.interface.clone().filter(|_| self.config.validate_schemas);
+        let schema = interface.as_deref().and_then(|interface| interface.export(name));
+
+        if let Some(schema) = schema {
+            schema.validate_input(&input)?;
+        }
+
+        let memory = self.memory_ops()?;
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
 
-        let (input_ptr, input_len) = memory.write(
+        let codec = store.data().codec;
+        let (input_ptr, input_len) = memory.send(
             store.as_context_mut(),
             &input
-                .to_bytes()?
+                .to_bytes_with(codec)?
         )?;
-        let (result_ptr, result_len) = unpack_ptr(
-            func.call(store.as_context_mut(), (input_ptr as u32, input_len as u32))?,
-        );
+        let output = match func.call(store.as_context_mut(), (input_ptr, input_len as u32)) {
+            Ok(packed) => {
+                let (result_ptr, result_len) = unpack_ptr(packed);
+                memory.receive(
+                    store.as_context_mut(),
+                    result_ptr,
+                    result_len,
+                )
+            },
+            Err(e) => Err(e.into()),
+        };
+
+        // The input is freed even if the call fails, since the guest only borrows it
+        // under ABI versions where the caller frees the input.
+        let freed = match store.data().abi_version.caller_frees_input() {
+            true => memory.release(store.as_context_mut(), input_ptr, input_len),
+            false => Ok(()),
+        };
+        let output = output?;
+        freed?;
+
+        let result = FnResult::from_bytes_with(&output, codec)?;
+
+        if let Some(schema) = schema {
+            schema.validate_result(&result)?;
+        }
 
-        Ok(FnResult::from_bytes(
-            &memory.read(
-                store.as_context_mut(),
-                result_ptr,
-                result_len,
-            )?
-        )?)
+        Ok(result)
+    }
+
+    /// Call a function within the module, exchanging opaque bytes.
+    /// 
+    /// The input is written to guest memory as is and the bytes returned by the guest
+    /// are returned without decoding, bypassing the module's codec so callers can layer
+    /// their own encoding. Schema validation does not apply to raw calls.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `input` - The bytes to pass to the function
+    /// 
+    /// # Returns
+    /// A result containing the bytes returned by the function or an error
+    /// if the call fails or the module is not instantiated
+    pub fn call_raw(&mut self, name: impl AsRef<str>, input: &[u8]) -> ModuleResult<Vec<u8>> {
+        let func = self.resolve_func(name.as_ref())?;
+        let memory = self.memory_ops()?;
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+
+        let (input_ptr, input_len) = memory.send(store.as_context_mut(), input)?;
+        let output = match func.call(store.as_context_mut(), (input_ptr, input_len as u32)) {
+            Ok(packed) => match unpack_ptr(packed) {
+                (_, 0) => Ok(vec![]),
+                (result_ptr, result_len) => memory.receive(store.as_context_mut(), result_ptr, result_len),
+            },
+            Err(e) => Err(e.into()),
+        };
+
+        // The input is freed even if the call fails, since the guest only borrows it
+        // under ABI versions where the caller frees the input.
+        let freed = match store.data().abi_version.caller_frees_input() {
+            true => memory.release(store.as_context_mut(), input_ptr, input_len),
+            false => Ok(()),
+        };
+        let output = output?;
+        freed?;
+
+        Ok(output)
+    }
+
+    /// Shut down the module.
+    /// 
+    /// Invokes the optional `finalize` or `shutdown` guest export so the guest can flush
+    /// buffers and close connections, then discards the instance. The module can be
+    /// instantiated again afterwards. Shutting down a module that is not instantiated
+    /// has no effect.
+    /// 
+    /// If epoch interruption is enabled in the [`ModuleConfig`](crate::config::ModuleConfig),
+    /// a background thread increments the engine's epoch once the shutdown timeout elapses,
+    /// trapping the finalizer. Clones of the module share its engine, so their epoch
+    /// deadlines advance by one as well. With fuel consumption enabled the finalizer is
+    /// also bounded by the store's remaining fuel. Otherwise it runs to completion.
+    /// 
+    /// Dropping an instantiated module shuts it down as well, except that the finalizer is
+    /// skipped unless epoch interruption or fuel consumption is enabled, since the dropping
+    /// thread could otherwise wait forever on a finalizer that never returns.
+    /// 
+    /// # Returns
+    /// A result indicating success or an error if the finalizer fails
+    pub fn shutdown(&mut self) -> ModuleResult<()> {
+        if !self.is_instantiated() {
+            return Ok(());
+        }
+
+        // Profiling and the shutdown deadline both rely on the epoch deadline
+        self.profiling = None;
+        self.epoch_ticker = None;
+
+        let result = self.finalize();
+        self.discard();
+
+        result
+    }
+
+    /// Check if the guest finalizer is bounded by the shutdown timeout or fuel consumption.
+    fn finalizer_is_bounded(&self) -> bool {
+        self.config.epoch_interruption || self.config.consume_fuel
+    }
+
+    fn finalize(&mut self) -> ModuleResult<()> {
+        let Some(name) = self.abi_report.as_ref().and_then(AbiReport::finalizer) else {
+            return Ok(());
+        };
+
+        // The epoch is only incremented once the shutdown timeout elapses,
+        // so the finalizer traps at its first epoch check past the deadline.
+        let _ticker = if self.config.epoch_interruption {
+            let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+
+            // A sampler left behind by profiling must not keep the finalizer running
+            store.epoch_deadline_trap();
+            store.set_epoch_deadline(1);
+
+            Some(EpochTicker::start(store.engine().clone(), self.shutdown_timeout))
+        } else {
+            None
+        };
+
+        Ok(self.call(name, FnInput::default())?.into_result::<()>()?)
+    }
+
+    /// Discard the instance and its store without invoking the finalizer
+    fn discard(&mut self) {
+        self.store = None;
+        self.instance = None;
+        self.memory = None;
+    }
+
+    /// Check if the guest profiler is currently running.
+    pub fn is_profiling(&self) -> bool {
+        self.profiling.is_some()
+    }
+
+    /// Start profiling guest execution.
+    /// 
+    /// Samples are collected every time the engine's epoch is incremented, which a background
+    /// thread does at the given interval until profiling is finished.
+    /// Requires epoch interruption to be enabled in the [`ModuleConfig`](crate::config::ModuleConfig).
+    /// 
+    /// The epoch is shared by every store of the engine, including those of clones of the module
+    /// and of the other modules of its pool. Their epoch deadlines advance while profiling and
+    /// can trap early, so the profiled module should be built on its own, with its own engine.
+    /// 
+    /// While profiling, the store's epoch deadline is managed by the profiler. The deadline
+    /// last set with [`set_epoch_deadline`](crate::module::Module::set_epoch_deadline) still
+    /// applies, counted from the start of profiling, and the remainder is restored once finished.
+    /// 
+    /// # Arguments
+    /// * `interval` - The interval at which samples are taken
+    /// 
+    /// # Returns
+    /// A result indicating success or an error if the module is not instantiated,
+    /// epoch interruption is disabled, the interval is zero or profiling is already running
+    pub fn start_profiling(&mut self, interval: Duration) -> ModuleResult<()> {
+        if !self.config.epoch_interruption {
+            return Err(ModuleError::ProfilingError(
+                "epoch interruption must be enabled to profile a module".to_string(),
+            ));
+        }
+
+        if interval.is_zero() {
+            return Err(ModuleError::ProfilingError("profiling interval must be greater than zero".to_string()));
+        }
+
+        if self.is_profiling() {
+            return Err(ModuleError::ProfilingError("profiling already started".to_string()));
+        }
+
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let module = self.instance_pre
+            .as_ref()
+            .ok_or(ModuleError::NotInstantiated)?
+            .module()
+            .clone();
+
+        store.data_mut().profiler = Some(GuestProfiler::new(
+            &self.name,
+            interval,
+            vec![(self.name.clone(), module)],
+        ));
+
+        self.profiling = Some(Sampler::install(store, self.epoch_deadline));
+        self.epoch_ticker = Some(EpochTicker::start(store.engine().clone(), interval));
+
+        Ok(())
+    }
+
+    /// Stop profiling guest execution and write the collected profile.
+    /// 
+    /// The profile is written in the Firefox Profiler's JSON format, which can
+    /// be loaded at <https://profiler.firefox.com>.
+    /// 
+    /// # Arguments
+    /// * `output` - The writer to write the profile to
+    /// 
+    /// # Returns
+    /// A result indicating success or an error if profiling was not started
+    /// or the profile could not be written
+    pub fn finish_profiling(&mut self, output: impl Write) -> ModuleResult<()> {
+        let sampler = self.profiling
+            .take()
+            .ok_or_else(|| ModuleError::ProfilingError("profiling not started".to_string()))?;
+        self.epoch_ticker = None;
+
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        sampler.uninstall(store);
+
+        store
+            .data_mut()
+            .profiler
+            .take()
+            .ok_or_else(|| ModuleError::ProfilingError("profiling not started".to_string()))?
+            .finish(output)
+            .map_err(|e| ModuleError::ProfilingError(format!("failed to write profile: {}", e)))
+    }
+
+    /// Call a function within the module while profiling its execution.
+    /// 
+    /// The profile is written even if the call itself fails.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `input` - The input to pass to the function
+    /// * `interval` - The interval at which samples are taken
+    /// * `output` - The writer to write the profile to
+    /// 
+    /// # Returns
+    /// A result containing the [`FnResult`](crate::result::FnResult) of the function call or an error
+    /// if the call or profiling fails
+    pub fn profile_call(
+        &mut self,
+        name: impl AsRef<str>,
+        input: FnInput,
+        interval: Duration,
+        output: impl Write,
+    ) -> ModuleResult<FnResult> {
+        self.start_profiling(interval)?;
+        let result = self.call(name, input);
+        self.finish_profiling(output)?;
+
+        result
     }
 }
 
@@ -397,31 +980,126 @@ impl Clone for Module {
             name: self.name.clone(),
             namespace: self.namespace.clone(),
             binary: self.binary.clone(),
+            content_hash: self.content_hash.clone(),
             environment: self.environment.clone(),
             config: self.config.clone(),
             limits: self.limits.clone(),
             host_fns: self.host_fns.clone(),
+            host_fn_sets: self.host_fn_sets.clone(),
+            interface: self.interface.clone(),
+            init_input: self.init_input.clone(),
+            shutdown_timeout: self.shutdown_timeout,
+            abi_report: self.abi_report.clone(),
             engine: self.engine.clone(),
             store: None,
             linker: self.linker.clone(),
             instance_pre: self.instance_pre.clone(),
             instance: None,
+            instance_id: 0,
+            memory: None,
+            epoch_deadline: None,
+            profiling: None,
+            epoch_ticker: None,
         }
     }
 }
 
-/// Represents a Binmod Module with host functions, and provides methods
-/// to instantiate and call functions within the module asynchronously.
+impl Drop for Module {
+    fn drop(&mut self) {
+        // A finalizer that can't be interrupted could hang the dropping
+        // thread, so it is only invoked from `drop` when it is bounded.
+        // Errors can't be reported from `drop`, and the instance is
+        // discarded regardless of the outcome.
+        if self.finalizer_is_bounded() {
+            let _ = self.shutdown();
+        } else if self.is_instantiated() {
+            log::warn!("module '{}' dropped without shutdown, skipping its finalizer", self.name);
+        }
+    }
+}
+
+/// Handle of a function resolved on a [`Module`](crate::module::Module) instance,
+/// created with [`Module::func`](crate::module::Module::func).
+/// 
+/// The handle caches the function export, so repeated calls skip the lookup by name.
+/// It stays bound to the instance it was resolved on.
 /// 
 /// # Examples
-/// ```rust
-/// use binmod::{module::AsyncModule, config::ModuleEnv};
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # fn example(mut module: binmod::module::Module) -> Result<(), Box<dyn std::error::Error>> {
 /// 
-/// let mut module = AsyncModule::builder()
-///     .from_file("path/to/module.wasm")
-///     .with_name("my_module")
-///     .with_namespace("binmod")
-///     .with_environment(
+/// let add = module.func::<(i32, i32), i32>("add")?;
+/// 
+/// for i in 0..1000 {
+///     let sum = add.call(&mut module, (i, i))?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ModuleFn<Args, R> {
+    name: String,
+    instance_id: u64,
+    func: GuestFunc,
+    _marker: PhantomData<fn(Args) -> R>,
+}
+
+impl<Args, R> ModuleFn<Args, R>
+where
+    Args: IntoFnInput,
+    R: DeserializeOwned,
+{
+    /// Get the name of the function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Call the function with typed arguments and return value.
+    /// 
+    /// # Arguments
+    /// * `module` - The module the function was resolved on
+    /// * `args` - The arguments to pass to the function
+    /// 
+    /// # Returns
+    /// A result containing the return value of the function or an error
+    /// if the call fails or the module was instantiated again since the function was resolved
+    pub fn call(&self, module: &mut Module, args: Args) -> ModuleResult<R> {
+        if module.instance_id != self.instance_id {
+            return Err(ModuleError::RuntimeError(format!("function '{}' was resolved on a different instance", self.name)));
+        }
+
+        Ok(
+            module
+                .call_func(&self.name, self.func.clone(), args.into_fn_input()?)?
+                .into_result::<R>()?
+        )
+    }
+}
+
+impl<Args, R> Clone for ModuleFn<Args, R> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            instance_id: self.instance_id,
+            func: self.func.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Represents a Binmod Module with host functions, and provides methods
+/// to instantiate and call functions within the module asynchronously.
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{module::AsyncModule, config::ModuleEnv};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let mut module = AsyncModule::builder()
+///     .from_file("path/to/module.wasm")?
+///     .with_name("my_module")
+///     .with_namespace("binmod")
+///     .with_environment(
 ///         ModuleEnv::default()
 ///             .arg("path/to/module.wasm")
 ///             .inherit_env()
@@ -430,7 +1108,7 @@ impl Clone for Module {
 ///     .host_fn("host_function", |arg1: i32, arg2: String| -> Result<String, String> {
 ///         Ok(format!("Host function called with args: {}, {}", arg1, arg2))
 ///     })
-///     .build()?
+///     .build_async()?
 ///     .instantiate()
 ///     .await?;
 /// 
@@ -438,6 +1116,8 @@ impl Clone for Module {
 ///     .typed_call("guest_function", (42, "Hello".to_string()))
 ///     .await?;
 /// println!("Result from guest function: {}", result);
+/// # Ok(())
+/// # }
 /// ```
 /// 
 /// # Note
@@ -448,16 +1128,25 @@ pub struct AsyncModule {
     name: String,
     namespace: String,
     binary: Vec<u8>,
+    content_hash: String,
     environment: ModuleEnv,
     config: ModuleConfig,
     limits: ModuleLimits,
     fuel_yield_interval: Option<u64>,
     host_fns: HashMap<String, HostFn>,
+    host_fn_sets: Vec<HostFnSet>,
+    interface: Option<Arc<InterfaceSchema>>,
+    init_input: FnInput,
+    shutdown_timeout: Duration,
+    abi_report: Option<AbiReport>,
     engine: Option<Engine>,
     store: Option<Store<ModuleState>>,
     linker: Option<Linker<ModuleState>>,
     instance_pre: Option<InstancePre<ModuleState>>,
     instance: Option<Instance>,
+    instance_id: u64,
+    memory: Option<AsyncMemoryOps>,
+    needs_reset: bool,
 }
 
 impl AsyncModule {
@@ -474,6 +1163,7 @@ impl AsyncModule {
     ///
     /// # Returns
     /// A new [`AsyncModule`](crate::module::AsyncModule) instance
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         binary: Vec<u8>,
         name: impl Into<String>,
@@ -487,17 +1177,26 @@ impl AsyncModule {
         Self {
             name: name.into(),
             namespace: namespace.into(),
+            content_hash: digest::content_hash(&binary),
             binary,
             environment,
             config,
             limits,
             fuel_yield_interval,
             host_fns,
+            host_fn_sets: Vec::new(),
+            interface: None,
+            init_input: FnInput::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            abi_report: None,
             engine: None,
             store: None,
             linker: None,
             instance_pre: None,
             instance: None,
+            instance_id: 0,
+            memory: None,
+            needs_reset: false,
         }
     }
 
@@ -522,16 +1221,309 @@ impl AsyncModule {
         &self.binary
     }
 
+    /// Get the content hash of the module binary.
+    /// 
+    /// # Returns
+    /// The lowercase hex encoded SHA-256 digest of the binary
+    pub fn content_hash(&self) -> &str {
+        &self.content_hash
+    }
+
     /// Get the environment configuration of the module.
     pub fn environment(&self) -> &ModuleEnv {
         &self.environment
     }
 
+    /// Get the input passed to the guest `initialize` export.
+    pub fn init_input(&self) -> &FnInput {
+        &self.init_input
+    }
+
+    /// Set the input passed to the guest `initialize` export.
+    /// 
+    /// Only takes effect the next time the module is instantiated.
+    /// 
+    /// # Arguments
+    /// * `input` - The input to pass to `initialize`
+    pub fn set_init_input(&mut self, input: FnInput) {
+        self.init_input = input;
+    }
+
+    /// Set the host function sets registered alongside the module's host functions.
+    /// 
+    /// Only takes effect the next time the module is linked.
+    pub(crate) fn set_host_fn_sets(&mut self, host_fn_sets: Vec<HostFnSet>) {
+        self.host_fn_sets = host_fn_sets;
+    }
+
+    /// Get the interface schema describing the module's exports, if any.
+    pub fn interface_schema(&self) -> Option<&InterfaceSchema> {
+        self.interface.as_deref()
+    }
+
+    /// Set the interface schema describing the module's exports.
+    /// 
+    /// Calls are validated against it when
+    /// [`validate_schemas`](crate::config::ModuleConfig::validate_schemas) is enabled.
+    /// 
+    /// # Arguments
+    /// * `interface` - The interface schema of the module
+    pub fn set_interface_schema(&mut self, interface: InterfaceSchema) {
+        self.interface = Some(Arc::new(interface));
+    }
+
+    /// Get the report of the ABI check run on the binary, if it has been checked.
+    /// 
+    /// Binaries are checked when the module is built, or on the first
+    /// instantiation for modules created with `new`.
+    pub fn abi_report(&self) -> Option<&AbiReport> {
+        self.abi_report.as_ref()
+    }
+
+    /// Set the report of the ABI check run on the binary, so it is not checked again.
+    pub(crate) fn set_abi_report(&mut self, report: AbiReport) {
+        self.abi_report = Some(report);
+    }
+
+    /// Get the binmod ABI version implemented by the guest, if the module is instantiated.
+    pub fn abi_version(&self) -> Option<AbiVersion> {
+        self.store
+            .as_ref()
+            .map(|store| store.data().abi_version)
+    }
+
+    /// Get the wire format negotiated with the guest, if the module is instantiated.
+    pub fn codec(&self) -> Option<Codec> {
+        self.store
+            .as_ref()
+            .map(|store| store.data().codec)
+    }
+
+    /// Open a stream the guest can read in chunks, see [`StreamHandle`](crate::stream::StreamHandle).
+    /// 
+    /// The reader is read on the executor thread driving the call, so it should not block,
+    /// see [`open_async_reader`](Self::open_async_reader) for readers performing I/O.
+    /// 
+    /// # Arguments
+    /// * `reader` - The reader providing the stream's data
+    /// 
+    /// # Returns
+    /// A result containing the handle to pass to the guest or an error if the module is not instantiated
+    pub fn open_reader(&mut self, reader: impl Read + Send + 'static) -> ModuleResult<StreamHandle> {
+        Ok(self.streams()?.open_reader(reader))
+    }
+
+    /// Open a stream the guest can write in chunks, see [`StreamHandle`](crate::stream::StreamHandle).
+    /// 
+    /// The writer is written on the executor thread driving the call, so it should not block,
+    /// see [`open_async_writer`](Self::open_async_writer) for writers performing I/O.
+    /// 
+    /// # Arguments
+    /// * `writer` - The writer receiving the stream's data
+    /// 
+    /// # Returns
+    /// A result containing the handle to pass to the guest or an error if the module is not instantiated
+    pub fn open_writer(&mut self, writer: impl Write + Send + 'static) -> ModuleResult<StreamHandle> {
+        Ok(self.streams()?.open_writer(writer))
+    }
+
+    /// Remove a stream opened with [`open_reader`](Self::open_reader) and take back its reader.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result containing the reader or an error if the stream is unknown or not a reader of type `R`
+    pub fn take_reader<R: Read + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<R> {
+        self.streams()?.take_reader(handle)
+    }
+
+    /// Remove a stream opened with [`open_writer`](Self::open_writer) and take back its flushed writer.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result containing the writer or an error if the stream is unknown or not a writer of type `W`
+    pub fn take_writer<W: Write + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<W> {
+        self.streams()?.take_writer(handle)
+    }
+
+    /// Open a stream the guest can read in chunks from an asynchronous reader,
+    /// see [`StreamHandle`](crate::stream::StreamHandle).
+    /// 
+    /// Reads are awaited on the executor driving the call.
+    /// 
+    /// # Arguments
+    /// * `reader` - The reader providing the stream's data
+    /// 
+    /// # Returns
+    /// A result containing the handle to pass to the guest or an error if the module is not instantiated
+    pub fn open_async_reader(&mut self, reader: impl AsyncRead + Unpin + Send + 'static) -> ModuleResult<StreamHandle> {
+        Ok(self.streams()?.open_async_reader(reader))
+    }
+
+    /// Open a stream the guest can write in chunks to an asynchronous writer,
+    /// see [`StreamHandle`](crate::stream::StreamHandle).
+    /// 
+    /// Writes are awaited on the executor driving the call.
+    /// 
+    /// # Arguments
+    /// * `writer` - The writer receiving the stream's data
+    /// 
+    /// # Returns
+    /// A result containing the handle to pass to the guest or an error if the module is not instantiated
+    pub fn open_async_writer(&mut self, writer: impl AsyncWrite + Unpin + Send + 'static) -> ModuleResult<StreamHandle> {
+        Ok(self.streams()?.open_async_writer(writer))
+    }
+
+    /// Remove a stream opened with [`open_async_reader`](Self::open_async_reader) and take back its reader.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result containing the reader or an error if the stream is unknown or not an async reader of type `R`
+    pub fn take_async_reader<R: AsyncRead + Unpin + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<R> {
+        self.streams()?.take_async_reader(handle)
+    }
+
+    /// Remove a stream opened with [`open_async_writer`](Self::open_async_writer) and take back its flushed writer.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result containing the writer or an error if the stream is unknown or not an async writer of type `W`
+    pub async fn take_async_writer<W: AsyncWrite + Unpin + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<W> {
+        self.streams()?.take_async_writer(handle)?.await
+    }
+
+    /// Remove a stream, dropping its reader or writer.
+    /// 
+    /// Streams are also dropped when the module is shut down.
+    /// 
+    /// # Arguments
+    /// * `handle` - The handle of the stream
+    /// 
+    /// # Returns
+    /// A result indicating whether the stream was open or an error if the module is not instantiated
+    pub fn close_stream(&mut self, handle: StreamHandle) -> ModuleResult<bool> {
+        Ok(self.streams()?.close(handle))
+    }
+
+    fn streams(&mut self) -> ModuleResult<&mut StreamTable> {
+        Ok(
+            &mut self.store
+                .as_mut()
+                .ok_or(ModuleError::NotInstantiated)?
+                .data_mut()
+                .streams
+        )
+    }
+
+    /// Get the deadline for the guest finalizer invoked on shutdown.
+    pub fn shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    /// Set the deadline for the guest finalizer invoked on shutdown.
+    /// 
+    /// # Arguments
+    /// * `timeout` - The deadline to set
+    pub fn set_shutdown_timeout(&mut self, timeout: Duration) {
+        self.shutdown_timeout = timeout;
+    }
+
     /// Check if the module has been instantiated.
     pub fn is_instantiated(&self) -> bool {
         self.instance.is_some()
     }
 
+    /// Check if the module needs to be reset before it can be used again.
+    /// 
+    /// This is the case when a call was cancelled or its future was dropped
+    /// before completion, leaving the guest in an undefined state.
+    pub fn needs_reset(&self) -> bool {
+        self.needs_reset
+    }
+
+    /// Reset the module by discarding its store and instantiating it again.
+    /// 
+    /// The current instance is shut down first, see
+    /// [`shutdown`](crate::module::AsyncModule::shutdown).
+    /// 
+    /// # Returns
+    /// A result containing the freshly instantiated module or an error
+    /// if instantiation fails
+    pub async fn reset(mut self) -> ModuleResult<Self> {
+        // Finalizer errors don't prevent the reset, the store is discarded regardless
+        let _ = self.shutdown().await;
+
+        self.instantiate().await
+    }
+
+    /// Shut down the module.
+    /// 
+    /// Invokes the optional `finalize` or `shutdown` guest export so the guest can flush
+    /// buffers and close connections, then discards the instance. The module can be
+    /// instantiated again afterwards. Shutting down a module that is not instantiated
+    /// has no effect.
+    /// 
+    /// The finalizer is cancelled once the shutdown timeout elapses, at its next fuel yield point,
+    /// see [`with_fuel_yield_interval`](crate::module::ModuleBuilder::with_fuel_yield_interval),
+    /// or while it awaits an async host function or stream. It is skipped entirely if the module
+    /// needs a reset since the guest is in an undefined state.
+    /// 
+    /// Async modules must be shut down explicitly. Dropping an instantiated
+    /// module discards it without invoking its finalizer.
+    /// 
+    /// # Returns
+    /// A result indicating success or an error if the finalizer fails
+    pub async fn shutdown(&mut self) -> ModuleResult<()> {
+        if !self.is_instantiated() {
+            return Ok(());
+        }
+
+        let result = if self.needs_reset {
+            Ok(())
+        } else {
+            self.finalize().await
+        };
+
+        self.discard();
+        self.needs_reset = false;
+
+        result
+    }
+
+    /// Discard the instance and its store without invoking the finalizer
+    fn discard(&mut self) {
+        self.store = None;
+        self.instance = None;
+        self.memory = None;
+    }
+
+    async fn finalize(&mut self) -> ModuleResult<()> {
+        let Some(name) = self.abi_report.as_ref().and_then(AbiReport::finalizer) else {
+            return Ok(());
+        };
+
+        // The timer cancels the finalizer from its own thread, waking the executor
+        // without depending on the timer of a specific runtime. A finalizer that
+        // times out is dropped midway, leaving the module marked for reset,
+        // which is cleared by `shutdown`.
+        let token = CancellationToken::new();
+        let _timer = CancelTimer::start(token.clone(), self.shutdown_timeout);
+
+        Ok(
+            self
+                .call_cancellable(name, FnInput::default(), &token)
+                .await?
+                .into_result::<()>()?
+        )
+    }
+
     /// Set the fuel for the module's store.
     /// 
     /// # Arguments
@@ -556,13 +1548,11 @@ impl AsyncModule {
     /// A result containing the remaining fuel or an error
     /// if fuel is not enabled or the module is not instantiated
     pub fn get_fuel(&mut self) -> ModuleResult<u64> {
-        Ok(
-            self.store
-                .as_mut()
-                .ok_or(ModuleError::NotInstantiated)?
-                .get_fuel()
-                .map_err(|_| ModuleError::FuelNotEnabled)?
-        )
+        self.store
+            .as_mut()
+            .ok_or(ModuleError::NotInstantiated)?
+            .get_fuel()
+            .map_err(|_| ModuleError::FuelNotEnabled)
     }
 
     /// Set the epoch deadline for the module's store.
@@ -606,7 +1596,7 @@ impl AsyncModule {
             return Err(ModuleError::AlreadyInstantiated);
         }
 
-        if !self.engine.is_some() {
+        if self.engine.is_none() {
             let mut config: Config = self.config
                 .clone()
                 .into();
@@ -620,56 +1610,52 @@ impl AsyncModule {
 
             // All hosts expect a host_alloc and host_dealloc function in
             // the `binmod` namespace to manage memory between host and guest.
-            linker.func_wrap(
+            linker.func_wrap_async(
                 "binmod",
                 "host_alloc",
-                |mut caller: Caller<ModuleState>, size: u32| -> u32 {
-                    futures::executor::block_on(async {
-                        caller
-                            .get_export("guest_alloc")
-                            .and_then(|e| e.into_func())
-                            .ok_or_else(|| anyhow::anyhow!("failed to find guest_alloc"))
-                            .unwrap()
-                            .typed::<u32, u32>(&caller)
-                            .unwrap()
-                            .call_async(&mut caller, size)
-                            .await
-                            .unwrap()
+                |mut caller: Caller<'_, ModuleState>, (size,): (u32,)| -> Box<dyn Future<Output = anyhow::Result<u32>> + Send + '_> {
+                    Box::new(async move {
+                        Ok(
+                            guest_alloc_from_caller(&mut caller)?
+                                .call_async(&mut caller, size)
+                                .await
+                                .map_err(|e| ModuleError::MemoryError(format!("Guest alloc failed: {}", e)))?
+                        )
                     })
                 }
             )?;
-            linker.func_wrap(
+            linker.func_wrap_async(
                 "binmod",
                 "host_dealloc",
-                |mut caller: Caller<ModuleState>, ptr: u32, size: u32| {
-                    futures::executor::block_on(async {
-                        caller
-                            .get_export("guest_dealloc")
-                            .and_then(|e| e.into_func())
-                            .ok_or_else(|| anyhow::anyhow!("failed to find guest_dealloc"))
-                            .unwrap()
-                            .typed::<(u32, u32), ()>(&caller)
-                            .unwrap()
+                |mut caller: Caller<'_, ModuleState>, (ptr, size): (u32, u32)| -> Box<dyn Future<Output = anyhow::Result<()>> + Send + '_> {
+                    Box::new(async move {
+                        guest_dealloc_from_caller(&mut caller)?
                             .call_async(&mut caller, (ptr, size))
                             .await
-                            .unwrap();
-                    });
+                            .map_err(|e| ModuleError::MemoryError(format!("Guest dealloc failed: {}", e)))?;
+
+                        Ok(())
+                    })
                 }
             )?;
 
-            for (name, host_fn) in &self.host_fns {
-                linker.func_wrap(
-                    &self.namespace,
-                    &name,
-                    host_fn.clone().into_func_async(),
-                )?;
+            stream::add_to_linker_async(&mut linker)?;
+
+            for (namespace, group) in host_fns::group_host_fns(&self.namespace, &self.host_fns, &self.host_fn_sets) {
+                for (name, host_fn) in group {
+                    linker.func_wrap_async(
+                        namespace,
+                        name,
+                        host_fn.for_linking(self.config.validate_schemas).into_func_async(),
+                    )?;
+                }
             }
 
             self.engine = Some(engine);
             self.linker = Some(linker);
         }
 
-        if !self.instance_pre.is_some() {
+        if self.instance_pre.is_none() {
             p1::add_to_linker_async(
                 self.linker
                     .as_mut()
@@ -677,14 +1663,18 @@ impl AsyncModule {
                 |state| &mut state.wasi,
             )?;
 
+            if self.abi_report.is_none() {
+                self.abi_report = Some(abi::check(&self.binary).into_result()?);
+            }
+
+            let module = artifacts::compile_module(self.engine.as_ref().expect("engine should be intialized"), &self.binary, &self.content_hash)
+                .map_err(|e| ModuleError::InstantiationError(format!("failed to compile module {}: {}", self.content_hash, e)))?;
+
             self.instance_pre = Some(
                 self.linker
                     .as_mut()
                     .expect("linker should be initialized")
-                    .instantiate_pre(
-                        &WasmModule::from_binary(self.engine.as_ref().expect("engine should be intialized"), &self.binary)
-                            .map_err(|e| ModuleError::InstantiationError(format!("failed to compile module: {}", e)))?
-                    )
+                    .instantiate_pre(&module)
                     .map_err(|e| ModuleError::InstantiationError(format!("failed to create instance pre: {}", e)))?
             )
         }
@@ -700,6 +1690,14 @@ impl AsyncModule {
                 limits: self.limits
                     .clone()
                     .into(),
+                profiler: None,
+                abi_version: AbiVersion::V1,
+                codec: Codec::Json,
+                streams: StreamTable::default(),
+                exchange: None,
+                result_exchange: None,
+                callback_depth: 0,
+                max_callback_depth: self.config.max_callback_depth,
             }
         );
 
@@ -721,7 +1719,22 @@ impl AsyncModule {
         );
 
         self.store = Some(store);
+        self.instance_id = next_instance_id();
+        log::debug!("instantiated module '{}' ({}) as instance {}", self.name, self.content_hash, self.instance_id);
+        digest::record_instantiated(&self.name, &self.content_hash);
+        self.memory = None;
+
+        if let Err(e) = self.initialize_instance().await {
+            // The guest never finished initializing, so it must not be finalized on drop
+            self.discard();
+            return Err(e);
+        }
+
+        Ok(self)
+    }
 
+    /// Run the guest initializers and set up the ABI state of a new instance
+    async fn initialize_instance(&mut self) -> ModuleResult<()> {
         // Invoke method `_initialize` directly through wasmtime's API
         // instead of relying on invoking via binmod because binmod will try to invoke guest exported
         // methods and crash if the initializer haven't been called yet.
@@ -754,14 +1767,23 @@ impl AsyncModule {
                 .map_err(|e| ModuleError::InstantiationError(format!("failed to call _initialize: {}", e)))?;
         }
 
-        // Now we invoke the binmod initializer `initialize` if it exists.
-        if let Err(e) = self.typed_call::<()>("initialize", ()).await {
-            if !matches!(e, ModuleError::FunctionNotFound(_)) {
-                return Err(e);
-            }
+        // Resolve the ABI version, negotiate the wire format and look up the
+        // exchange buffers before the first call into the guest.
+        let store = self.store.as_mut().unwrap();
+        store.data_mut().abi_version = abi::resolve_version_async(self.instance.as_ref().unwrap(), store, self.config.abi_version).await?;
+        store.data_mut().codec = codec::negotiate_async(self.instance.as_ref().unwrap(), store, self.config.codec).await?;
+        store.data_mut().exchange = ExchangeBuffer::from_instance(self.instance.as_ref().unwrap(), store, EXCHANGE_BUFFER_EXPORT)?;
+        store.data_mut().result_exchange = ExchangeBuffer::from_instance(self.instance.as_ref().unwrap(), store, RESULT_BUFFER_EXPORT)?;
+
+        // Now we invoke the binmod initializer `initialize` if it exists
+        // with the binmod signature.
+        if self.abi_report.as_ref().is_some_and(|report| report.initialize.is_valid())
+            && let FnResult::Error { error } = self.call("initialize", self.init_input.clone()).await?
+        {
+            return Err(ModuleError::InitializationFailed(error));
         }
 
-        Ok(self)
+        Ok(())
     }
 
     /// Call a function within the module with typed arguments and return value.
@@ -785,6 +1807,35 @@ impl AsyncModule {
         )
     }
 
+    /// Call a function within the module with dynamic JSON arguments and return value.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `args` - An array of positional arguments or an object of keyword arguments
+    /// 
+    /// # Returns
+    /// A result containing the JSON return value of the function or an error
+    /// if the arguments are invalid, the call fails or the module is not instantiated
+    pub async fn call_json(&mut self, name: impl AsRef<str>, args: Value) -> ModuleResult<Value> {
+        Ok(
+            self
+                .call(name.as_ref(), FnInput::from_json(args)?)
+                .await?
+                .into_result::<Value>()?
+        )
+    }
+
+    /// Start building a call mixing positional and keyword arguments.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// 
+    /// # Returns
+    /// An [`AsyncInvocation`](crate::invoke::AsyncInvocation) collecting the arguments of the call
+    pub fn invoke(&mut self, name: impl Into<String>) -> AsyncInvocation<'_> {
+        AsyncInvocation::new(self, name)
+    }
+
     /// Call a function within the module.
     /// 
     /// # Arguments
@@ -795,60 +1846,352 @@ impl AsyncModule {
     /// A result containing the [`FnResult`](crate::result::FnResult) of the
     /// function call or an error if the call fails or the module is not instantiated
     pub async fn call(&mut self, name: impl AsRef<str>, input: FnInput) -> ModuleResult<FnResult> {
-        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
-        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
-        let memory = AsyncMemoryOps::from_instance(instance, store)?;
+        // The flag is only cleared once the call completes, so a call whose
+        // future is dropped midway leaves the module marked for reset.
+        self.needs_reset = true;
+        let result = self.call_inner(name, input).await;
+        self.needs_reset = false;
 
-        let func = instance
-            .get_typed_func::<(u32, u32), u64>(store.as_context_mut(), name.as_ref())
-            .map_err(|e| ModuleError::FunctionNotFound(format!("failed to get function '{}': {}", name.as_ref(), e)))?;
+        result
+    }
 
-        let (input_ptr, input_len) = memory
-            .write(
-                store.as_context_mut(),
-                &input.to_bytes()?
-            )
-            .await?;
-        let (result_ptr, result_len) = unpack_ptr(
-            func
-                .call_async(store.as_context_mut(), (input_ptr as u32, input_len as u32))
-                .await?,
-        );
+    /// Call a function within the module, exchanging opaque bytes.
+    /// 
+    /// The input is written to guest memory as is and the bytes returned by the guest
+    /// are returned without decoding, bypassing the module's codec so callers can layer
+    /// their own encoding. Schema validation does not apply to raw calls.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `input` - The bytes to pass to the function
+    /// 
+    /// # Returns
+    /// A result containing the bytes returned by the function or an error
+    /// if the call fails or the module is not instantiated
+    pub async fn call_raw(&mut self, name: impl AsRef<str>, input: &[u8]) -> ModuleResult<Vec<u8>> {
+        self.needs_reset = true;
+        let result = self.call_raw_inner(name, input).await;
+        self.needs_reset = false;
 
-        Ok(FnResult::from_bytes(
-            &memory
-                .read(
-                    store.as_context_mut(),
-                    result_ptr,
-                    result_len,
-                )
-                .await?
-        )?)
+        result
     }
-}
 
-impl Clone for AsyncModule {
-    fn clone(&self) -> Self {
-        Self {
-            name: self.name.clone(),
-            namespace: self.namespace.clone(),
-            binary: self.binary.clone(),
-            environment: self.environment.clone(),
-            config: self.config.clone(),
-            limits: self.limits.clone(),
-            fuel_yield_interval: self.fuel_yield_interval.clone(),
-            host_fns: self.host_fns.clone(),
-            engine: self.engine.clone(),
-            store: None,
-            linker: self.linker.clone(),
-            instance_pre: self.instance_pre.clone(),
+    /// Resolve a function within the module once, returning a handle for repeated calls.
+    /// 
+    /// The handle caches the function export, so calling it skips the lookup by name
+    /// done by [`call`](crate::module::AsyncModule::call). Handles are bound to the current
+    /// instance and must be resolved again once the module is reset or instantiated again.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to resolve
+    /// 
+    /// # Returns
+    /// A result containing the [`AsyncModuleFn`](crate::module::AsyncModuleFn) handle or an error
+    /// if the function is not found or the module is not instantiated
+    pub fn func<Args, R>(&mut self, name: impl Into<String>) -> ModuleResult<AsyncModuleFn<Args, R>>
+    where
+        Args: IntoFnInput,
+        R: DeserializeOwned,
+    {
+        let name = name.into();
+        let func = self.resolve_func(&name)?;
+
+        Ok(AsyncModuleFn {
+            name,
+            instance_id: self.instance_id,
+            func,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Call a function within the module, cancelling it when the given token is cancelled.
+    /// 
+    /// The guest is interrupted at its next fuel yield point and the module is
+    /// marked as needing a reset, see [`AsyncModule::needs_reset`](crate::module::AsyncModule::needs_reset).
+    /// Async modules always consume fuel, so the guest yields at least every
+    /// [`fuel yield interval`](crate::module::ModuleBuilder::with_fuel_yield_interval) units
+    /// of fuel, which bounds how long cancellation takes. Host functions can not be
+    /// interrupted, so cancellation takes effect once they return.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `input` - The input to pass to the function
+    /// * `token` - The token used to cancel the call
+    /// 
+    /// # Returns
+    /// A result containing the [`FnResult`](crate::result::FnResult) of the function call
+    /// or a [`Cancelled`](crate::error::ModuleError::Cancelled) error if the call was cancelled
+    pub async fn call_cancellable(
+        &mut self,
+        name: impl AsRef<str>,
+        input: FnInput,
+        token: &CancellationToken,
+    ) -> ModuleResult<FnResult> {
+        if token.is_cancelled() {
+            return Err(ModuleError::Cancelled);
+        }
+
+        let call = self.call(name, input);
+        let cancelled = token.cancelled();
+        pin_mut!(call, cancelled);
+
+        match future::select(call, cancelled).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(ModuleError::Cancelled),
+        }
+    }
+
+    /// Call a function within the module with typed arguments and return value,
+    /// cancelling it when the given token is cancelled.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `args` - The arguments to pass to the function
+    /// * `token` - The token used to cancel the call
+    /// 
+    /// # Returns
+    /// A result containing the return value of the function or an error
+    /// if the call fails, is cancelled or the module is not instantiated
+    pub async fn typed_call_cancellable<R>(
+        &mut self,
+        name: impl AsRef<str>,
+        args: impl IntoFnInput,
+        token: &CancellationToken,
+    ) -> ModuleResult<R>
+    where
+        R: DeserializeOwned,
+    {
+        Ok(
+            self
+                .call_cancellable(name.as_ref(), args.into_fn_input()?, token)
+                .await?
+                .into_result::<R>()?
+        )
+    }
+
+    fn resolve_func(&mut self, name: &str) -> ModuleResult<GuestFunc> {
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+
+        instance
+            .get_typed_func::<(u32, u32), u64>(store.as_context_mut(), name)
+            .map_err(|e| ModuleError::FunctionNotFound(format!("failed to get function '{}': {}", name, e)))
+    }
+
+    /// Get the memory operations of the instance, looking up the exports on first use
+    fn memory_ops(&mut self) -> ModuleResult<AsyncMemoryOps> {
+        if let Some(memory) = &self.memory {
+            return Ok(memory.clone());
+        }
+
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+        let instance = self.instance.as_ref().ok_or(ModuleError::NotInstantiated)?;
+        let memory = AsyncMemoryOps::from_instance(instance, store)?;
+
+        Ok(self.memory.insert(memory).clone())
+    }
+
+    async fn call_inner(&mut self, name: impl AsRef<str>, input: FnInput) -> ModuleResult<FnResult> {
+        let func = self.resolve_func(name.as_ref())?;
+
+        self.call_func_inner(name.as_ref(), func, input).await
+    }
+
+    async fn call_func_inner(&mut self, name: &str, func: GuestFunc, input: FnInput) -> ModuleResult<FnResult> {
+        let interface = self.interface.clone().filter(|_| self.config.validate_schemas);
+        let schema = interface.as_deref().and_then(|interface| interface.export(name));
+
+        if let Some(schema) = schema {
+            schema.validate_input(&input)?;
+        }
+
+        let memory = self.memory_ops()?;
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+
+        let codec = store.data().codec;
+        let (input_ptr, input_len) = memory
+            .send(
+                store.as_context_mut(),
+                &input.to_bytes_with(codec)?
+            )
+            .await?;
+        let output = match func.call_async(store.as_context_mut(), (input_ptr, input_len as u32)).await {
+            Ok(packed) => {
+                let (result_ptr, result_len) = unpack_ptr(packed);
+                memory
+                    .receive(
+                        store.as_context_mut(),
+                        result_ptr,
+                        result_len,
+                    )
+                    .await
+            },
+            Err(e) => Err(e.into()),
+        };
+
+        // The input is freed even if the call fails, since the guest only borrows it
+        // under ABI versions where the caller frees the input.
+        let freed = match store.data().abi_version.caller_frees_input() {
+            true => memory.release(store.as_context_mut(), input_ptr, input_len).await,
+            false => Ok(()),
+        };
+        let output = output?;
+        freed?;
+
+        let result = FnResult::from_bytes_with(&output, codec)?;
+
+        if let Some(schema) = schema {
+            schema.validate_result(&result)?;
+        }
+
+        Ok(result)
+    }
+
+    async fn call_raw_inner(&mut self, name: impl AsRef<str>, input: &[u8]) -> ModuleResult<Vec<u8>> {
+        let func = self.resolve_func(name.as_ref())?;
+        let memory = self.memory_ops()?;
+        let store = self.store.as_mut().ok_or(ModuleError::NotInstantiated)?;
+
+        let (input_ptr, input_len) = memory
+            .send(store.as_context_mut(), input)
+            .await?;
+        let output = match func.call_async(store.as_context_mut(), (input_ptr, input_len as u32)).await {
+            Ok(packed) => match unpack_ptr(packed) {
+                (_, 0) => Ok(vec![]),
+                (result_ptr, result_len) => memory
+                    .receive(store.as_context_mut(), result_ptr, result_len)
+                    .await,
+            },
+            Err(e) => Err(e.into()),
+        };
+
+        // The input is freed even if the call fails, since the guest only borrows it
+        // under ABI versions where the caller frees the input.
+        let freed = match store.data().abi_version.caller_frees_input() {
+            true => memory.release(store.as_context_mut(), input_ptr, input_len).await,
+            false => Ok(()),
+        };
+        let output = output?;
+        freed?;
+
+        Ok(output)
+    }
+}
+
+impl Clone for AsyncModule {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            namespace: self.namespace.clone(),
+            binary: self.binary.clone(),
+            content_hash: self.content_hash.clone(),
+            environment: self.environment.clone(),
+            config: self.config.clone(),
+            limits: self.limits.clone(),
+            fuel_yield_interval: self.fuel_yield_interval,
+            host_fns: self.host_fns.clone(),
+            host_fn_sets: self.host_fn_sets.clone(),
+            interface: self.interface.clone(),
+            init_input: self.init_input.clone(),
+            shutdown_timeout: self.shutdown_timeout,
+            abi_report: self.abi_report.clone(),
+            engine: self.engine.clone(),
+            store: None,
+            linker: self.linker.clone(),
+            instance_pre: self.instance_pre.clone(),
             instance: None,
+            instance_id: 0,
+            memory: None,
+            needs_reset: false,
         }
     }
 }
 
-/// Builder for constructing a [`Module`](crate::module::Module)
-/// or an [`AsyncModule`](crate::module::AsyncModule).
+impl Drop for AsyncModule {
+    fn drop(&mut self) {
+        // Running the finalizer here would block the executor thread
+        // dropping the module, so it must be invoked through `shutdown`.
+        if self.is_instantiated() && !self.needs_reset {
+            log::warn!("async module '{}' dropped without shutdown, skipping its finalizer", self.name);
+        }
+    }
+}
+
+/// Handle of a function resolved on a [`AsyncModule`](crate::module::AsyncModule) instance,
+/// created with [`AsyncModule::func`](crate::module::AsyncModule::func).
+/// 
+/// The handle caches the function export, so repeated calls skip the lookup by name.
+/// It stays bound to the instance it was resolved on. A reset instantiates the module
+/// again, after which the handle returns an error and must be resolved again.
+/// 
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+/// # async fn example(mut module: binmod::module::AsyncModule) -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// let add = module.func::<(i32, i32), i32>("add")?;
+/// 
+/// for i in 0..1000 {
+///     let sum = add.call(&mut module, (i, i)).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncModuleFn<Args, R> {
+    name: String,
+    instance_id: u64,
+    func: GuestFunc,
+    _marker: PhantomData<fn(Args) -> R>,
+}
+
+impl<Args, R> AsyncModuleFn<Args, R>
+where
+    Args: IntoFnInput,
+    R: DeserializeOwned,
+{
+    /// Get the name of the function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Call the function with typed arguments and return value.
+    /// 
+    /// # Arguments
+    /// * `module` - The module the function was resolved on
+    /// * `args` - The arguments to pass to the function
+    /// 
+    /// # Returns
+    /// A result containing the return value of the function or an error
+    /// if the call fails or the module was instantiated again since the function was resolved
+    pub async fn call(&self, module: &mut AsyncModule, args: Args) -> ModuleResult<R> {
+        if module.instance_id != self.instance_id {
+            return Err(ModuleError::RuntimeError(format!("function '{}' was resolved on a different instance", self.name)));
+        }
+
+        let input = args.into_fn_input()?;
+
+        module.needs_reset = true;
+        let result = module.call_func_inner(&self.name, self.func.clone(), input).await;
+        module.needs_reset = false;
+
+        Ok(result?.into_result::<R>()?)
+    }
+}
+
+impl<Args, R> Clone for AsyncModuleFn<Args, R> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            instance_id: self.instance_id,
+            func: self.func.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Builder for constructing a [`Module`](crate::module::Module),
+/// an [`AsyncModule`](crate::module::AsyncModule), or one of their
+/// component counterparts.
 #[derive(Clone)]
 pub struct ModuleBuilder {
     name: Option<String>,
@@ -858,7 +2201,25 @@ pub struct ModuleBuilder {
     limits: Option<ModuleLimits>,
     environment: Option<ModuleEnv>,
     host_fns: HashMap<String, HostFn>,
+    host_fn_sets: Vec<HostFnSet>,
+    interface: Option<InterfaceSchema>,
     fuel_yield_interval: Option<u64>,
+    shutdown_timeout: Option<Duration>,
+    init_input: Option<FnInput>,
+    public_keys: Option<Vec<VerifyingKey>>,
+    signature: Option<Vec<u8>>,
+    expected_sha256: Option<String>,
+    source: Option<ModuleSource>,
+    manifest: Option<PackageManifest>,
+    trust_manifest: bool,
+    assets: Option<(Arc<MountedAssets>, String)>,
+}
+
+/// The file a [`ModuleBuilder`](crate::module::ModuleBuilder) loaded its binary from
+#[derive(Clone)]
+enum ModuleSource {
+    File(PathBuf),
+    Package(PathBuf),
 }
 
 impl ModuleBuilder {
@@ -872,7 +2233,18 @@ impl ModuleBuilder {
             limits: None,
             environment: None,
             host_fns: HashMap::new(),
+            host_fn_sets: Vec::new(),
+            interface: None,
             fuel_yield_interval: None,
+            shutdown_timeout: None,
+            init_input: None,
+            public_keys: None,
+            signature: None,
+            expected_sha256: None,
+            source: None,
+            manifest: None,
+            trust_manifest: false,
+            assets: None,
         }
     }
 
@@ -885,21 +2257,186 @@ impl ModuleBuilder {
     /// The updated ModuleBuilder instance
     pub fn with_binary(mut self, binary: Vec<u8>) -> Self {
         self.binary = Some(binary);
+        self.signature = None;
+        self.source = None;
+        self.clear_package();
         self
     }
 
     /// Set the binary code for the module from a file.
     /// 
+    /// Files containing the WebAssembly text format are detected
+    /// and compiled to a binary. A detached signature stored next to
+    /// a binary file with the `.sig` extension is picked up for
+    /// [`verify_signature`](crate::module::ModuleBuilder::verify_signature),
+    /// replacing any signature set for a previous binary.
+    /// 
     /// # Arguments
-    /// * `path` - The path to the WebAssembly binary file
+    /// * `path` - The path to the WebAssembly binary or text file
     /// 
     /// # Returns
     /// A result containing the updated ModuleBuilder instance or an error
+    /// if the file could not be read or contains invalid WebAssembly text
     pub fn from_file(mut self, path: impl AsRef<Path>) -> ModuleResult<Self> {
-        self.binary = Some(fs::read(path)?);
+        let bytes = fs::read(path.as_ref())?;
+
+        self.signature = None;
+        self.binary = Some(match wat::Detect::from_bytes(&bytes) {
+            wat::Detect::WasmText => parse_wat(Some(path.as_ref()), &bytes)?,
+            _ => {
+                let mut signature_path = path.as_ref().as_os_str().to_owned();
+                signature_path.push(format!(".{}", SIGNATURE_EXTENSION));
+
+                self.signature = fs::read(signature_path).ok();
+                bytes
+            },
+        });
+        self.source = Some(ModuleSource::File(path.as_ref().to_path_buf()));
+        self.clear_package();
+        Ok(self)
+    }
+
+    /// Configure the module from a `.binmod` package.
+    /// 
+    /// Sets the binary, and the name and namespace described by the package manifest
+    /// unless they are set on the builder, and mounts the package assets read-only at
+    /// the manifest's assets mount path.
+    /// 
+    /// A package signature only covers the binary, so the configuration, limits and
+    /// environment policy of the manifest are ignored unless the manifest is trusted with
+    /// [`trust_package_manifest`](crate::module::ModuleBuilder::trust_package_manifest).
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the package file
+    /// 
+    /// # Returns
+    /// A result containing the updated ModuleBuilder instance or an error
+    /// if the package could not be read or its assets could not be extracted
+    pub fn from_package(mut self, path: impl AsRef<Path>) -> ModuleResult<Self> {
+        let package = Package::open(path.as_ref())?;
+        let manifest = package.manifest().clone();
+
+        self.binary = Some(package.binary().to_vec());
+        self.signature = None;
+        self.name = self.name.or_else(|| Some(manifest.name.clone()));
+        self.namespace = self.namespace.or_else(|| manifest.namespace.clone());
+        self.assets = package
+            .mount_assets()?
+            .map(|assets| (Arc::new(assets), manifest.assets_mount.clone()));
+        self.manifest = Some(manifest);
+        self.source = Some(ModuleSource::Package(path.as_ref().to_path_buf()));
+        Ok(self)
+    }
+
+    /// Apply the configuration, limits and environment policy of the package manifest.
+    /// 
+    /// The manifest settings are only used where the builder has none of its own, and
+    /// can grant the module the host's environment variables and network access, so the
+    /// manifest should only be trusted if the package comes from a trusted source.
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn trust_package_manifest(mut self) -> Self {
+        self.trust_manifest = true;
+        self
+    }
+
+    /// Set the binary code for the module from the WebAssembly text format.
+    /// 
+    /// # Arguments
+    /// * `wat` - The module or component in the WebAssembly text format
+    /// 
+    /// # Returns
+    /// A result containing the updated ModuleBuilder instance or an error
+    /// if the text could not be parsed
+    pub fn from_wat(mut self, wat: impl AsRef<str>) -> ModuleResult<Self> {
+        self.binary = Some(parse_wat(None, wat.as_ref().as_bytes())?);
+        self.signature = None;
+        self.source = None;
+        self.clear_package();
         Ok(self)
     }
 
+    /// Require the module binary to be signed by one of the given keys.
+    /// 
+    /// The ed25519 signature is checked over the binary before it is compiled,
+    /// using the detached signature if one was provided and otherwise the
+    /// signature embedded in the binary. Building fails with a
+    /// [`SignatureInvalid`](crate::error::ModuleError::SignatureInvalid) error
+    /// if the binary is not signed by any of the keys.
+    /// 
+    /// # Arguments
+    /// * `public_keys` - The keys of the approved publishers
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn verify_signature(mut self, public_keys: impl IntoIterator<Item = VerifyingKey>) -> Self {
+        self.public_keys = Some(public_keys.into_iter().collect());
+        self
+    }
+
+    /// Pin the module binary to a content hash.
+    /// 
+    /// Building fails with a
+    /// [`ContentHashMismatch`](crate::error::ModuleError::ContentHashMismatch) error
+    /// if the SHA-256 digest of the binary differs.
+    /// 
+    /// # Arguments
+    /// * `hash` - The expected hex encoded SHA-256 digest of the binary
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn expect_sha256(mut self, hash: impl Into<String>) -> Self {
+        self.expected_sha256 = Some(hash.into());
+        self
+    }
+
+    /// Set the detached signature of the module binary.
+    /// 
+    /// The signature is cleared whenever the binary is replaced,
+    /// so it must be set after the binary.
+    /// 
+    /// # Arguments
+    /// * `signature` - The ed25519 signature of the binary
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn with_signature(mut self, signature: Vec<u8>) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    /// Get the path of the file the binary was loaded from with
+    /// [`from_file`](crate::module::ModuleBuilder::from_file) or
+    /// [`from_package`](crate::module::ModuleBuilder::from_package).
+    pub fn source_path(&self) -> Option<&Path> {
+        match &self.source {
+            Some(ModuleSource::File(path) | ModuleSource::Package(path)) => Some(path),
+            None => None,
+        }
+    }
+
+    /// Load the binary again from the file it was originally loaded from.
+    /// 
+    /// Only the binary and its detached signature are replaced. The name,
+    /// configuration, limits, environment and other settings of the builder
+    /// are kept, including those originally read from a package manifest.
+    /// 
+    /// # Returns
+    /// A result containing the updated ModuleBuilder instance or an error
+    /// if the builder has no source file or it could not be loaded
+    pub fn reload_source(mut self) -> ModuleResult<Self> {
+        match self.source.clone() {
+            Some(ModuleSource::File(path)) => self.from_file(path),
+            Some(ModuleSource::Package(path)) => {
+                self.binary = Some(Package::open(&path)?.binary().to_vec());
+                self.signature = None;
+                Ok(self)
+            },
+            None => Err(ModuleError::InvalidModuleConfig("Binary was not loaded from a file".into())),
+        }
+    }
+
     /// Set the name for the module.
     /// 
     /// # Arguments
@@ -976,8 +2513,146 @@ impl ModuleBuilder {
         self
     }
 
+    /// Add a host function to the module, describing it with a schema derived
+    /// from its argument and return types.
+    /// 
+    /// The argument and return types must implement [`JsonSchema`](crate::schema::JsonSchema).
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The Rust function or closure to be wrapped as a Host Function
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn host_fn_with_schema<F, Args>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: DescribeHostFn<Args>,
+        HostFnWrapper<F, Args>: HostFnCallable + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::new(func).with_schema(F::describe()));
+        self
+    }
+
+    /// Add a host function exchanging opaque bytes with the guest.
+    /// 
+    /// The input and output bytes are passed through without encoding, so guests
+    /// can layer their own format on top. An error returned by the function traps the guest.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The closure handling the input bytes of each call
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn host_fn_raw<F, E>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, E> + Send + Sync + 'static,
+        E: ToString,
+    {
+        self.host_fns.insert(name.into(), HostFn::raw(func));
+        self
+    }
+
+    /// Add a host function calling back into the guest to the module.
+    /// 
+    /// The closure receives a [`GuestCaller`](crate::callback::GuestCaller) calling guest
+    /// exports by name while the host function is running, up to the configured
+    /// [`max_callback_depth`](crate::config::ModuleConfig::max_callback_depth).
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The closure handling each call
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn host_fn_with_guest<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&mut GuestCaller<'_, '_>, &FnInput) -> FnResult + Send + Sync + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::with_guest(func));
+        self
+    }
+
+    /// Add a host function calling back into the guest of an asynchronous module to the module.
+    /// 
+    /// The closure receives an [`AsyncGuestCaller`](crate::callback::AsyncGuestCaller) whose
+    /// calls are awaited on the executor driving the module, up to the configured
+    /// [`max_callback_depth`](crate::config::ModuleConfig::max_callback_depth).
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the host function
+    /// * `func` - The closure returning the future handling each call
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn host_fn_with_guest_async<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: for<'a, 'b, 'c> Fn(&'a mut AsyncGuestCaller<'b, 'c>, FnInput) -> BoxFuture<'a, FnResult> + Send + Sync + 'static,
+    {
+        self.host_fns.insert(name.into(), HostFn::with_guest_async(func));
+        self
+    }
+
+    /// Add a set of host functions to the module.
+    /// 
+    /// Functions of a set without a namespace are registered under the module's namespace.
+    /// 
+    /// # Arguments
+    /// * `host_fns` - The [`HostFnSet`](crate::host_fns::HostFnSet), or a shared
+    ///   instance implementing [`HostFunctions`](crate::host_fns::HostFunctions)
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn with_host_fns(mut self, host_fns: impl Into<HostFnSet>) -> Self {
+        self.host_fn_sets.push(host_fns.into());
+        self
+    }
+
+    /// Set the interface schema describing the module's exports.
+    /// 
+    /// Calls are validated against it when
+    /// [`validate_schemas`](crate::config::ModuleConfig::validate_schemas) is enabled.
+    /// 
+    /// # Arguments
+    /// * `interface` - The [`InterfaceSchema`](crate::schema::InterfaceSchema) of the module
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn with_interface_schema(mut self, interface: InterfaceSchema) -> Self {
+        self.interface = Some(interface);
+        self
+    }
+
+    /// Describe the host functions registered with the builder.
+    /// 
+    /// Functions registered without a schema are listed with an empty [`FnSchema`](crate::schema::FnSchema).
+    /// 
+    /// # Returns
+    /// An [`InterfaceSchema`](crate::schema::InterfaceSchema) listing the host functions as imports
+    pub fn describe_host_fns(&self) -> InterfaceSchema {
+        let namespace = self.namespace.as_deref().unwrap_or("env");
+
+        InterfaceSchema {
+            name: self.name.clone(),
+            imports: host_fns::group_host_fns(namespace, &self.host_fns, &self.host_fn_sets)
+                .into_iter()
+                .map(|(namespace, group)| (
+                    namespace.to_string(),
+                    group
+                        .into_iter()
+                        .map(|(name, host_fn)| (name.to_string(), host_fn.schema().cloned().unwrap_or_default()))
+                        .collect(),
+                ))
+                .collect(),
+            exports: BTreeMap::new(),
+        }
+    }
+
     /// Set the fuel yield interval for async modules.
     /// 
+    /// The guest yields to the executor every `interval` units of fuel, which is also
+    /// where cancelled calls are interrupted. Defaults to 10000, and must be greater than zero.
+    /// 
     /// # Arguments
     /// * `interval` - The fuel yield interval to set
     /// 
@@ -988,36 +2663,286 @@ impl ModuleBuilder {
         self
     }
 
+    /// Set the input passed to the guest `initialize` export.
+    /// 
+    /// # Arguments
+    /// * `input` - The input to pass to `initialize`
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn with_init_input(mut self, input: FnInput) -> Self {
+        self.init_input = Some(input);
+        self
+    }
+
+    /// Set the arguments passed to the guest `initialize` export.
+    /// 
+    /// This allows passing per-deployment settings to the guest at startup.
+    /// A failure reported by the guest initializer surfaces as an
+    /// [`InitializationFailed`](crate::error::ModuleError::InitializationFailed) error
+    /// when the module is instantiated.
+    /// 
+    /// # Arguments
+    /// * `args` - The arguments to pass to `initialize`
+    /// 
+    /// # Returns
+    /// A result containing the updated ModuleBuilder instance or an error
+    /// if the arguments could not be serialized
+    pub fn with_init_args(mut self, args: impl IntoFnInput) -> ModuleResult<Self> {
+        self.init_input = Some(args.into_fn_input()?);
+        Ok(self)
+    }
+
+    /// Set the deadline for the guest finalizer invoked on shutdown.
+    /// 
+    /// Defaults to [`DEFAULT_SHUTDOWN_TIMEOUT`](crate::module::DEFAULT_SHUTDOWN_TIMEOUT), and
+    /// must be greater than zero. A [`Module`](crate::module::Module) can only enforce the
+    /// deadline with epoch interruption enabled, so building one with a shutdown timeout
+    /// but without epoch interruption fails.
+    /// 
+    /// # Arguments
+    /// * `timeout` - The deadline to set
+    /// 
+    /// # Returns
+    /// The updated ModuleBuilder instance
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = Some(timeout);
+        self
+    }
+
     /// Build a [`Module`](crate::module::Module) from the builder configuration.
     /// 
     /// # Returns
     /// A result containing the constructed Module or an error
-    pub fn build(self) -> ModuleResult<Module> {
-        Ok(Module::new(
-            self.binary.ok_or_else(|| ModuleError::InvalidModuleConfig("Binary not provided".into()))?,
+    pub fn build(mut self) -> ModuleResult<Module> {
+        if self.shutdown_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(ModuleError::InvalidModuleConfig("Shutdown timeout must be greater than zero".into()));
+        }
+
+        let binary = self.take_binary()?;
+        let (environment, config, limits) = self.take_settings();
+
+        if self.shutdown_timeout.is_some() && !config.epoch_interruption {
+            return Err(ModuleError::InvalidModuleConfig("Shutdown timeout requires epoch interruption".into()));
+        }
+        let abi_report = abi::check(&binary).into_result()?;
+
+        let mut module = Module::new(
+            binary,
             self.name.ok_or_else(|| ModuleError::InvalidModuleConfig("Name not provided".into()))?,
             self.namespace.unwrap_or("env".into()),
-            self.environment.unwrap_or(ModuleEnv::default()),
-            self.config.unwrap_or(ModuleConfig::default()),
-            self.limits.unwrap_or(ModuleLimits::default()),
+            environment,
+            config,
+            limits,
             self.host_fns,
-        ))
+        );
+        module.set_shutdown_timeout(self.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT));
+        module.set_init_input(self.init_input.unwrap_or_default());
+        module.set_host_fn_sets(self.host_fn_sets);
+        module.set_abi_report(abi_report);
+
+        if let Some(interface) = self.interface {
+            module.set_interface_schema(interface);
+        }
+
+        log::info!("loaded module '{}' ({})", module.name(), module.content_hash());
+        digest::record_loaded(module.name(), module.content_hash());
+
+        Ok(module)
     }
 
     /// Build an [`AsyncModule`](crate::module::AsyncModule) from the builder configuration.
     /// 
     /// # Returns
     /// A result containing the constructed AsyncModule or an error
-    pub fn build_async(self) -> ModuleResult<AsyncModule> {
-        Ok(AsyncModule::new(
-            self.binary.ok_or_else(|| ModuleError::InvalidModuleConfig("Binary not provided".into()))?,
+    pub fn build_async(mut self) -> ModuleResult<AsyncModule> {
+        if self.fuel_yield_interval == Some(0) {
+            return Err(ModuleError::InvalidModuleConfig("Fuel yield interval must be greater than zero".into()));
+        }
+
+        if self.shutdown_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(ModuleError::InvalidModuleConfig("Shutdown timeout must be greater than zero".into()));
+        }
+
+        let binary = self.take_binary()?;
+        let (environment, config, limits) = self.take_settings();
+        let abi_report = abi::check(&binary).into_result()?;
+
+        let mut module = AsyncModule::new(
+            binary,
+            self.name.ok_or_else(|| ModuleError::InvalidModuleConfig("Name not provided".into()))?,
+            self.namespace.unwrap_or("env".into()),
+            environment,
+            config,
+            limits,
+            self.fuel_yield_interval,
+            self.host_fns,
+        );
+        module.set_shutdown_timeout(self.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT));
+        module.set_init_input(self.init_input.unwrap_or_default());
+        module.set_host_fn_sets(self.host_fn_sets);
+        module.set_abi_report(abi_report);
+
+        if let Some(interface) = self.interface {
+            module.set_interface_schema(interface);
+        }
+
+        log::info!("loaded module '{}' ({})", module.name(), module.content_hash());
+        digest::record_loaded(module.name(), module.content_hash());
+
+        Ok(module)
+    }
+
+    /// Build a [`ComponentModule`](crate::component::ComponentModule) from the builder configuration.
+    /// 
+    /// The interface schema, `initialize` input and shutdown timeout only apply
+    /// to core modules, so building a component with any of them set fails.
+    /// 
+    /// # Returns
+    /// A result containing the constructed ComponentModule or an error
+    /// if the binary is not a WebAssembly component or a core module setting is set
+    pub fn build_component(mut self) -> ModuleResult<ComponentModule> {
+        self.check_component_settings()?;
+
+        let binary = self.take_binary()?;
+        let (environment, config, limits) = self.take_settings();
+        if !wasmparser::Parser::is_component(&binary) {
+            return Err(ModuleError::InvalidModuleConfig("Binary is not a WebAssembly component".into()));
+        }
+
+        let mut module = ComponentModule::new(
+            binary,
+            self.name.ok_or_else(|| ModuleError::InvalidModuleConfig("Name not provided".into()))?,
+            self.namespace.unwrap_or("env".into()),
+            environment,
+            config,
+            limits,
+            self.host_fns,
+        );
+        module.set_host_fn_sets(self.host_fn_sets);
+
+        log::info!("loaded module '{}' ({})", module.name(), module.content_hash());
+        digest::record_loaded(module.name(), module.content_hash());
+
+        Ok(module)
+    }
+
+    /// Build an [`AsyncComponentModule`](crate::component::AsyncComponentModule) from the builder configuration.
+    /// 
+    /// The interface schema, `initialize` input and shutdown timeout only apply
+    /// to core modules, so building a component with any of them set fails.
+    /// 
+    /// # Returns
+    /// A result containing the constructed AsyncComponentModule or an error
+    /// if the binary is not a WebAssembly component or a core module setting is set
+    pub fn build_component_async(mut self) -> ModuleResult<AsyncComponentModule> {
+        self.check_component_settings()?;
+
+        let binary = self.take_binary()?;
+        let (environment, config, limits) = self.take_settings();
+        if !wasmparser::Parser::is_component(&binary) {
+            return Err(ModuleError::InvalidModuleConfig("Binary is not a WebAssembly component".into()));
+        }
+
+        let mut module = AsyncComponentModule::new(
+            binary,
             self.name.ok_or_else(|| ModuleError::InvalidModuleConfig("Name not provided".into()))?,
             self.namespace.unwrap_or("env".into()),
-            self.environment.unwrap_or(ModuleEnv::default()),
-            self.config.unwrap_or(ModuleConfig::default()),
-            self.limits.unwrap_or(ModuleLimits::default()),
+            environment,
+            config,
+            limits,
             self.fuel_yield_interval,
             self.host_fns,
-        ))
+        );
+        module.set_host_fn_sets(self.host_fn_sets);
+
+        log::info!("loaded module '{}' ({})", module.name(), module.content_hash());
+        digest::record_loaded(module.name(), module.content_hash());
+
+        Ok(module)
+    }
+
+    /// Forget the manifest and assets of a previously loaded package
+    fn clear_package(&mut self) {
+        self.manifest = None;
+        self.assets = None;
     }
-}
\ No newline at end of file
+
+    /// Resolve the environment, configuration and limits of the module, falling back
+    /// to those of a trusted package manifest and mounting the package assets
+    fn take_settings(&mut self) -> (ModuleEnv, ModuleConfig, ModuleLimits) {
+        let (policy, config, limits) = match self.manifest.take().filter(|_| self.trust_manifest) {
+            Some(manifest) => (Some(manifest.environment), Some(manifest.config), Some(manifest.limits)),
+            None => (None, None, None),
+        };
+
+        let mut environment = self.environment
+            .take()
+            .or_else(|| policy.map(ModuleEnv::from))
+            .unwrap_or_default();
+        if let Some((assets, guest_path)) = self.assets.take() {
+            environment = environment.mount_shared_assets(assets, guest_path);
+        }
+
+        (
+            environment,
+            self.config.take().or(config).unwrap_or_default(),
+            self.limits.take().or(limits).unwrap_or_default(),
+        )
+    }
+
+    /// Reject settings that only apply to core modules
+    fn check_component_settings(&self) -> ModuleResult<()> {
+        let unsupported = [
+            ("interface schema", self.interface.is_some()),
+            ("init input", self.init_input.is_some()),
+            ("shutdown timeout", self.shutdown_timeout.is_some()),
+        ];
+
+        match unsupported.iter().find(|(_, set)| *set) {
+            Some((setting, _)) => Err(ModuleError::InvalidModuleConfig(
+                format!("The {} is not supported for components", setting)
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Take the binary out of the builder, verifying its content hash and signature if required
+    fn take_binary(&mut self) -> ModuleResult<Vec<u8>> {
+        let binary = self.binary
+            .take()
+            .ok_or_else(|| ModuleError::InvalidModuleConfig("Binary not provided".into()))?;
+
+        if let Some(expected) = &self.expected_sha256 {
+            let actual = digest::content_hash(&binary);
+
+            if !actual.eq_ignore_ascii_case(expected.trim()) {
+                return Err(ModuleError::ContentHashMismatch {
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
+        if let Some(public_keys) = &self.public_keys {
+            signature::verify(&binary, self.signature.as_deref(), public_keys)?;
+        }
+
+        Ok(binary)
+    }
+}
+
+impl Default for ModuleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compile the WebAssembly text format to a binary, reporting
+/// parse errors with their line and column.
+fn parse_wat(path: Option<&Path>, wat: &[u8]) -> ModuleResult<Vec<u8>> {
+    wat::Parser::new()
+        .parse_bytes(path, wat)
+        .map(|binary| binary.into_owned())
+        .map_err(|e| ModuleError::InvalidModuleConfig(format!("Invalid WebAssembly text: {}", e)))
+}
@@ -0,0 +1,565 @@
+use std::{collections::HashMap, fs, io::{Cursor, Read, Write}, path::{Component, Path}};
+use serde::{Serialize, Deserialize};
+use tempfile::TempDir;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    config::{ModuleEnv, ModuleConfig, ModuleLimits},
+    error::{ModuleResult, ModuleError},
+};
+
+
+/// Path of the manifest inside a package archive.
+pub const MANIFEST_PATH: &str = "manifest.json";
+
+/// Directory containing the asset files inside a package archive.
+pub const ASSETS_DIR: &str = "assets/";
+
+/// Default path of the module binary inside a package archive.
+pub const DEFAULT_MODULE_PATH: &str = "module.wasm";
+
+/// Default guest path the package assets are mounted at.
+pub const DEFAULT_ASSETS_MOUNT: &str = "/assets";
+
+/// Maximum uncompressed size of an entry read from a package archive.
+pub const MAX_ENTRY_SIZE: u64 = 1 << 30;
+
+/// Maximum uncompressed size of all entries read from a package archive.
+pub const MAX_PACKAGE_SIZE: u64 = 1 << 31;
+
+/// Maximum number of entries in a package archive.
+pub const MAX_ENTRIES: usize = 10_000;
+
+/// Environment policy of a packaged module.
+///
+/// Unlike [`ModuleEnv`](crate::config::ModuleEnv), the policy only
+/// describes settings that can be shipped alongside the module.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PackageEnv {
+    /// Arguments to pass to the module.
+    pub args: Vec<String>,
+    /// Environment variables to set for the module.
+    pub env: HashMap<String, String>,
+    /// Inherit the host process's environment variables.
+    pub inherit_env: bool,
+    /// Inherit the same network permissions as the host.
+    pub inherit_network: bool,
+}
+
+impl From<PackageEnv> for ModuleEnv {
+    fn from(policy: PackageEnv) -> Self {
+        let mut env = ModuleEnv::default();
+
+        if policy.inherit_env {
+            env = env.inherit_env();
+        }
+
+        if policy.inherit_network {
+            env = env.inherit_network();
+        }
+
+        env
+            .args(policy.args)
+            .env_vars(policy.env)
+    }
+}
+
+/// Manifest describing a packaged module.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PackageManifest {
+    /// The name of the module.
+    pub name: String,
+    /// The namespace for the module's host functions.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Path of the module binary inside the archive.
+    #[serde(default = "default_module_path")]
+    pub module: String,
+    /// The configuration for the module.
+    #[serde(default)]
+    pub config: ModuleConfig,
+    /// The resource limits for the module.
+    #[serde(default)]
+    pub limits: ModuleLimits,
+    /// The environment policy for the module.
+    #[serde(default)]
+    pub environment: PackageEnv,
+    /// Guest path the assets are mounted at.
+    #[serde(default = "default_assets_mount")]
+    pub assets_mount: String,
+}
+
+impl PackageManifest {
+    /// Create a new manifest with default settings.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the module
+    ///
+    /// # Returns
+    /// A new [`PackageManifest`](crate::package::PackageManifest) instance
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            namespace: None,
+            module: default_module_path(),
+            config: ModuleConfig::default(),
+            limits: ModuleLimits::default(),
+            environment: PackageEnv::default(),
+            assets_mount: default_assets_mount(),
+        }
+    }
+
+    /// Set the namespace for the module.
+    ///
+    /// # Arguments
+    /// * `namespace` - The namespace to set
+    ///
+    /// # Returns
+    /// The updated PackageManifest instance
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Set the configuration for the module.
+    ///
+    /// # Arguments
+    /// * `config` - The configuration to set
+    ///
+    /// # Returns
+    /// The updated PackageManifest instance
+    pub fn with_config(mut self, config: ModuleConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the resource limits for the module.
+    ///
+    /// # Arguments
+    /// * `limits` - The resource limits to set
+    ///
+    /// # Returns
+    /// The updated PackageManifest instance
+    pub fn with_limits(mut self, limits: ModuleLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the environment policy for the module.
+    ///
+    /// # Arguments
+    /// * `environment` - The environment policy to set
+    ///
+    /// # Returns
+    /// The updated PackageManifest instance
+    pub fn with_environment(mut self, environment: PackageEnv) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Set the guest path the assets are mounted at.
+    ///
+    /// # Arguments
+    /// * `path` - The guest path to mount the assets at
+    ///
+    /// # Returns
+    /// The updated PackageManifest instance
+    pub fn with_assets_mount(mut self, path: impl Into<String>) -> Self {
+        self.assets_mount = path.into();
+        self
+    }
+}
+
+fn default_module_path() -> String {
+    DEFAULT_MODULE_PATH.to_string()
+}
+
+fn default_assets_mount() -> String {
+    DEFAULT_ASSETS_MOUNT.to_string()
+}
+
+/// A `.binmod` package bundling a module binary, a manifest describing how
+/// to run it, and read-only asset files.
+///
+/// Packages are zip archives containing a `manifest.json`, the module binary
+/// and an `assets/` directory.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{module::Module, package::{Package, PackageManifest}};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// Package::new(
+///     PackageManifest::new("my_calculator").with_namespace("calculator"),
+///     std::fs::read("my_calculator.wasm")?,
+/// )
+///     .with_asset("constants.json", std::fs::read("constants.json")?)?
+///     .save("my_calculator.binmod")?;
+///
+/// let module = Module::builder()
+///     .from_package("my_calculator.binmod")?
+///     .build()?
+///     .instantiate()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    manifest: PackageManifest,
+    binary: Vec<u8>,
+    assets: HashMap<String, Vec<u8>>,
+}
+
+impl Package {
+    /// Create a new package without assets.
+    ///
+    /// # Arguments
+    /// * `manifest` - The manifest describing the module
+    /// * `binary` - The WebAssembly binary code of the module
+    ///
+    /// # Returns
+    /// A new [`Package`](crate::package::Package) instance
+    pub fn new(manifest: PackageManifest, binary: Vec<u8>) -> Self {
+        Self {
+            manifest,
+            binary,
+            assets: HashMap::new(),
+        }
+    }
+
+    /// Add an asset file to the package.
+    ///
+    /// # Arguments
+    /// * `path` - The relative path of the asset
+    /// * `contents` - The contents of the asset
+    ///
+    /// # Returns
+    /// A result containing the updated Package instance or an error
+    /// if the path is not a relative path
+    pub fn with_asset(mut self, path: impl Into<String>, contents: Vec<u8>) -> ModuleResult<Self> {
+        let path = path.into();
+        validate_asset_path(&path)?;
+
+        self.assets.insert(path, contents);
+        Ok(self)
+    }
+
+    /// Open a package from a file.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the package file
+    ///
+    /// # Returns
+    /// A result containing the package or an error
+    /// if the file could not be read or is not a valid package
+    pub fn open(path: impl AsRef<Path>) -> ModuleResult<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Read a package from the bytes of a package archive.
+    ///
+    /// # Arguments
+    /// * `bytes` - The package archive
+    ///
+    /// # Returns
+    /// A result containing the package or an error if the bytes are not a valid package,
+    /// or the archive exceeds [`MAX_ENTRIES`](crate::package::MAX_ENTRIES) entries or
+    /// [`MAX_PACKAGE_SIZE`](crate::package::MAX_PACKAGE_SIZE) bytes once uncompressed
+    pub fn from_bytes(bytes: &[u8]) -> ModuleResult<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| ModuleError::PackageError(format!("failed to read archive: {}", e)))?;
+
+        if archive.len() > MAX_ENTRIES {
+            return Err(ModuleError::PackageError(format!("archive exceeds the maximum of {} entries", MAX_ENTRIES)));
+        }
+
+        let mut budget = MAX_PACKAGE_SIZE;
+        let manifest: PackageManifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST_PATH, &mut budget)?)
+            .map_err(|e| ModuleError::PackageError(format!("invalid `{}`: {}", MANIFEST_PATH, e)))?;
+        let binary = read_entry(&mut archive, &manifest.module, &mut budget)?;
+        let mut package = Self::new(manifest, binary);
+
+        for index in 0..archive.len() {
+            let mut file = archive
+                .by_index(index)
+                .map_err(|e| ModuleError::PackageError(format!("failed to read archive: {}", e)))?;
+
+            let Some(path) = file.name().strip_prefix(ASSETS_DIR).map(str::to_string) else {
+                continue;
+            };
+
+            if file.is_dir() || path.is_empty() {
+                continue;
+            }
+
+            let contents = read_file(&mut file, &path, &mut budget)?;
+            package = package.with_asset(path, contents)?;
+        }
+
+        Ok(package)
+    }
+
+    /// Get the manifest of the package.
+    pub fn manifest(&self) -> &PackageManifest {
+        &self.manifest
+    }
+
+    /// Get the binary code of the packaged module.
+    pub fn binary(&self) -> &[u8] {
+        &self.binary
+    }
+
+    /// Get the asset files of the package, keyed by their relative path.
+    pub fn assets(&self) -> &HashMap<String, Vec<u8>> {
+        &self.assets
+    }
+
+    /// Write the package into a package archive.
+    ///
+    /// # Returns
+    /// A result containing the package archive or an error
+    pub fn to_bytes(&self) -> ModuleResult<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+
+        let mut entries = vec![
+            (MANIFEST_PATH.to_string(), serde_json::to_vec_pretty(&self.manifest)?),
+            (self.manifest.module.clone(), self.binary.clone()),
+        ];
+        entries.extend(
+            self.assets
+                .iter()
+                .map(|(path, contents)| (format!("{}{}", ASSETS_DIR, path), contents.clone()))
+        );
+
+        for (path, contents) in entries {
+            writer
+                .start_file(path, options)
+                .map_err(|e| ModuleError::PackageError(format!("failed to write archive: {}", e)))?;
+            writer.write_all(&contents)?;
+        }
+
+        Ok(
+            writer
+                .finish()
+                .map_err(|e| ModuleError::PackageError(format!("failed to write archive: {}", e)))?
+                .into_inner()
+        )
+    }
+
+    /// Write the package to a file.
+    ///
+    /// # Arguments
+    /// * `path` - The path to write the package file to
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    pub fn save(&self, path: impl AsRef<Path>) -> ModuleResult<()> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Extract the asset files of the package into a directory.
+    ///
+    /// # Arguments
+    /// * `dir` - The directory to extract the assets into
+    ///
+    /// # Returns
+    /// A result indicating success or an error
+    pub fn extract_assets(&self, dir: impl AsRef<Path>) -> ModuleResult<()> {
+        for (path, contents) in &self.assets {
+            let path = dir.as_ref().join(path);
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(path, contents)?;
+        }
+
+        Ok(())
+    }
+
+    /// Extract the asset files of the package into a private temporary
+    /// directory, so they can be mounted into a module.
+    ///
+    /// # Returns
+    /// A result containing the directory holding the assets, `None` if the
+    /// package has no assets, or an error if the assets could not be extracted
+    pub fn mount_assets(&self) -> ModuleResult<Option<MountedAssets>> {
+        if self.assets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("binmod-assets-");
+
+        #[cfg(unix)]
+        builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+
+        let dir = builder.tempdir()?;
+        self.extract_assets(dir.path())?;
+
+        Ok(Some(MountedAssets { dir }))
+    }
+}
+
+/// Asset files of a package extracted by
+/// [`Package::mount_assets`](crate::package::Package::mount_assets).
+///
+/// The directory is only accessible to the current user, and is
+/// removed once the last module mounting it is dropped.
+#[derive(Debug)]
+pub struct MountedAssets {
+    dir: TempDir,
+}
+
+impl MountedAssets {
+    /// Get the path of the directory holding the assets.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Read an entry of a package archive
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, path: &str, budget: &mut u64) -> ModuleResult<Vec<u8>> {
+    let mut file = archive
+        .by_name(path)
+        .map_err(|e| ModuleError::PackageError(format!("failed to read `{}`: {}", path, e)))?;
+
+    read_file(&mut file, path, budget)
+}
+
+/// Read the contents of an archive entry, rejecting entries larger than
+/// [`MAX_ENTRY_SIZE`](crate::package::MAX_ENTRY_SIZE) or than the `budget`
+/// remaining for the whole archive, which is reduced by the size read
+fn read_file(file: &mut impl Read, path: &str, budget: &mut u64) -> ModuleResult<Vec<u8>> {
+    let limit = MAX_ENTRY_SIZE.min(*budget);
+
+    // The size recorded in the archive is untrusted, so the read
+    // is bounded instead of preallocating the recorded size.
+    let mut contents = vec![];
+    file.take(limit + 1).read_to_end(&mut contents)?;
+
+    if contents.len() as u64 > MAX_ENTRY_SIZE {
+        return Err(ModuleError::PackageError(format!("`{}` exceeds the maximum size of {} bytes", path, MAX_ENTRY_SIZE)));
+    }
+
+    if contents.len() as u64 > limit {
+        return Err(ModuleError::PackageError(format!("archive exceeds the maximum size of {} bytes at `{}`", MAX_PACKAGE_SIZE, path)));
+    }
+
+    *budget -= contents.len() as u64;
+    Ok(contents)
+}
+
+/// Ensure an asset path stays within the assets directory once extracted
+fn validate_asset_path(path: &str) -> ModuleResult<()> {
+    let is_relative = !path.is_empty() && Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if is_relative {
+        Ok(())
+    } else {
+        Err(ModuleError::PackageError(format!("invalid asset path `{}`", path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn package() -> Package {
+        Package::new(
+            PackageManifest::new("packaged")
+                .with_namespace("host")
+                .with_limits(ModuleLimits { memory_size: 1 << 20 }),
+            EMPTY_MODULE.to_vec(),
+        )
+            .with_asset("constants.json", b"{\"pi\":3.14}".to_vec())
+            .unwrap()
+            .with_asset("nested/data.txt", b"data".to_vec())
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips_package() {
+        let package = package();
+
+        assert_eq!(Package::from_bytes(&package.to_bytes().unwrap()).unwrap(), package);
+    }
+
+    #[test]
+    fn extracts_assets() {
+        let assets = package().mount_assets().unwrap().unwrap();
+
+        assert_eq!(fs::read(assets.path().join("constants.json")).unwrap(), b"{\"pi\":3.14}");
+        assert_eq!(fs::read(assets.path().join("nested/data.txt")).unwrap(), b"data");
+        assert!(Package::new(PackageManifest::new("empty"), EMPTY_MODULE.to_vec()).mount_assets().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_asset_paths_outside_assets_dir() {
+        for path in ["", "../escape", "nested/../../escape", "/absolute", "./relative"] {
+            assert!(validate_asset_path(path).is_err(), "{path}");
+        }
+
+        assert!(validate_asset_path("nested/data.txt").is_ok());
+    }
+
+    #[test]
+    fn rejects_archive_with_escaping_asset() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let entries = [
+            (MANIFEST_PATH, serde_json::to_vec(&PackageManifest::new("escaping")).unwrap()),
+            (DEFAULT_MODULE_PATH, EMPTY_MODULE.to_vec()),
+            ("assets/../escape", b"data".to_vec()),
+        ];
+
+        for (path, contents) in entries {
+            writer.start_file(path, SimpleFileOptions::default()).unwrap();
+            writer.write_all(&contents).unwrap();
+        }
+
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(matches!(Package::from_bytes(&bytes), Err(ModuleError::PackageError(_))));
+    }
+
+    #[test]
+    fn bounds_total_size_of_entries() {
+        let mut budget = 10;
+
+        assert_eq!(read_file(&mut &b"module"[..], "module.wasm", &mut budget).unwrap(), b"module");
+        assert_eq!(budget, 4);
+        assert!(matches!(
+            read_file(&mut &b"asset"[..], "asset.txt", &mut budget),
+            Err(ModuleError::PackageError(message)) if message.contains("asset.txt")
+        ));
+    }
+
+    #[test]
+    fn rejects_archive_with_too_many_entries() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for index in 0..=MAX_ENTRIES {
+            writer.start_file(format!("assets/{}", index), SimpleFileOptions::default()).unwrap();
+        }
+
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(matches!(Package::from_bytes(&bytes), Err(ModuleError::PackageError(message)) if message.contains("entries")));
+    }
+
+    #[test]
+    fn reports_invalid_manifest_as_package_error() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file(MANIFEST_PATH, SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"{\"name\":").unwrap();
+
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(matches!(Package::from_bytes(&bytes), Err(ModuleError::PackageError(message)) if message.contains(MANIFEST_PATH)));
+    }
+}
@@ -1,11 +1,12 @@
 use std::{
+    borrow::{Borrow, BorrowMut},
     collections::VecDeque,
     ops::{Deref, DerefMut},
-    sync::{Arc, Condvar, Mutex},
+    sync::{Arc, Condvar, Mutex, atomic::{AtomicUsize, Ordering}},
     future::Future,
 };
 use futures::stream::{self, StreamExt, TryStreamExt};
-use mea::{condvar::{Condvar as AsyncCondvar}, mutex::{Mutex as AsyncMutex}};
+use mea::semaphore::Semaphore;
 
 use crate::{module::{Module, AsyncModule, ModuleBuilder}, error::{ModuleResult, ModuleError}};
 
@@ -16,15 +17,16 @@ use crate::{module::{Module, AsyncModule, ModuleBuilder}, error::{ModuleResult,
 /// 
 /// # Examples
 /// 
-/// ```rust
-/// use binmod::{module::ModuleBuilder, pool::ModulePool};
+/// ```rust,no_run
+/// use binmod::{module::Module, pool::ModulePool};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// 
 /// // Create a module
 /// let module = Module::builder()
 ///     .from_file("my_module.wasm")?
 ///     .with_name("my_module")
 ///     .with_namespace("binmod")
-///     .host_fn("host_add", |a: i64, b: i64| -> Result<i64> { Ok(a + b) })
+///     .host_fn("host_add", |a: i64, b: i64| -> Result<i64, String> { Ok(a + b) })
 ///     .build()?
 ///     .instantiate()?;
 /// 
@@ -47,6 +49,8 @@ use crate::{module::{Module, AsyncModule, ModuleBuilder}, error::{ModuleResult,
 ///     let result = module.typed_call::<i64>("add", (5, 7));
 ///     result
 /// })?;
+/// # Ok(())
+/// # }
 /// ```
 #[derive(Clone)]
 pub struct ModulePool {
@@ -123,6 +127,42 @@ impl ModulePool {
         cvar.notify_one();
     }
 
+    /// Removes up to `count` idle modules from the pool and shuts them down.
+    /// 
+    /// Leased modules are not affected.
+    /// 
+    /// # Arguments
+    /// * `count` - The maximum number of modules to remove.
+    /// 
+    /// # Returns
+    /// The number of modules removed, or the first error returned while shutting them down.
+    pub fn shrink(&self, count: usize) -> ModuleResult<usize> {
+        let removed = {
+            let (lock, _) = &*self.modules;
+            let mut modules = lock.lock().unwrap();
+            let count = count.min(modules.len());
+
+            modules.drain(..count).collect::<Vec<_>>()
+        };
+
+        let count = removed.len();
+        let mut result = Ok(());
+
+        for mut module in removed {
+            result = result.and(module.shutdown());
+        }
+
+        result.map(|_| count)
+    }
+
+    /// Removes all idle modules from the pool and shuts them down.
+    /// 
+    /// # Returns
+    /// The number of modules removed, or the first error returned while shutting them down.
+    pub fn drain(&self) -> ModuleResult<usize> {
+        self.shrink(usize::MAX)
+    }
+
     /// Executes a function with a leased module from the pool.
     /// The module is automatically returned to the pool after the function completes.
     /// 
@@ -171,6 +211,18 @@ impl DerefMut for ModuleLease<'_> {
     }
 }
 
+impl Borrow<Module> for ModuleLease<'_> {
+    fn borrow(&self) -> &Module {
+        self
+    }
+}
+
+impl BorrowMut<Module> for ModuleLease<'_> {
+    fn borrow_mut(&mut self) -> &mut Module {
+        self
+    }
+}
+
 impl Drop for ModuleLease<'_> {
     fn drop(&mut self) {
         self.release();
@@ -253,7 +305,7 @@ impl ModulePoolBuilder {
                     .map(|_| template.clone().instantiate())
                     .collect::<Result<Vec<Module>, _>>()?
                     .into_iter()
-                    .chain(vec![template].into_iter())
+                    .chain(vec![template])
                     .collect::<Vec<_>>()
             );
         } else if let Some(builder) = self.builder.take() {
@@ -284,17 +336,17 @@ impl Default for ModulePoolBuilder {
 /// 
 /// # Examples
 /// 
-/// ```rust
+/// ```rust,no_run
 /// use binmod::{module::AsyncModule, pool::AsyncModulePool};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// 
 /// // Create an asynchronous module
 /// let module = AsyncModule::builder()
 ///     .from_file("my_module.wasm")?
 ///     .with_name("my_module")
 ///     .with_namespace("binmod")
-///     .host_fn("host_add", |a: i64, b: i64| -> Result<i64> { Ok(a + b) })
-///     .build_async()
-///     .await?
+///     .host_fn("host_add", |a: i64, b: i64| -> Result<i64, String> { Ok(a + b) })
+///     .build_async()?
 ///     .instantiate()
 ///     .await?;
 /// 
@@ -308,20 +360,19 @@ impl Default for ModulePoolBuilder {
 /// // Lease an asynchronous module from the pool
 /// let mut leased_module = pool.lease().await;
 /// leased_module.typed_call::<i64>("add", (2, 3)).await?;
-/// // The module is NOT automatically returned to the pool when `leased_module` goes out of scope
-/// // unlike the synchronous ModulePool. It must be manually released using `leased_module.release().await`.
+/// // Release the module to reset it right away if the call was cancelled. Dropping
+/// // `leased_module` also hands it back, deferring the reset to its next lease.
 /// leased_module.release().await;
-/// 
-/// // Or use `scoped` to automatically manage the lease
-/// pool.scoped(|module| {
-///     // Use the leased module here
-///     let result = module.typed_call::<i64>("add", (5, 7)).await;
-///     result
-/// }).await?;
+/// # Ok(())
+/// # }
 /// ```
 #[derive(Clone)]
 pub struct AsyncModulePool {
-    modules: Arc<(AsyncMutex<VecDeque<AsyncModule>>, AsyncCondvar)>,
+    modules: Arc<Mutex<VecDeque<AsyncModule>>>,
+    /// One permit for every idle module in `modules`.
+    available: Arc<Semaphore>,
+    /// Number of modules owned by the pool, leased or not.
+    size: Arc<AtomicUsize>,
 }
 
 impl AsyncModulePool {
@@ -334,10 +385,17 @@ impl AsyncModulePool {
     /// A new AsyncModulePool instance.
     pub fn new(modules: Vec<AsyncModule>) -> Self {
         Self {
-            modules: Arc::new((AsyncMutex::new(VecDeque::from(modules)), AsyncCondvar::new())),
+            size: Arc::new(AtomicUsize::new(modules.len())),
+            available: Arc::new(Semaphore::new(modules.len())),
+            modules: Arc::new(Mutex::new(VecDeque::from(modules))),
         }
     }
 
+    /// Get the number of modules owned by the pool, including leased modules.
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Acquire)
+    }
+
     /// Creates a new AsyncModulePoolBuilder.
     /// 
     /// # Returns
@@ -348,32 +406,136 @@ impl AsyncModulePool {
 
     /// Leases a module from the pool, asynchronously blocking if necessary until one is available.
     /// 
+    /// Modules whose lease was dropped during a call are reset first, and discarded
+    /// if they can not be instantiated again. Once every module has been discarded
+    /// or removed, this waits until a module is returned with
+    /// [`return_module`](crate::pool::AsyncModulePool::return_module).
+    /// 
     /// # Returns
     /// An AsyncModuleLease representing the leased module.
     pub async fn lease(&self) -> AsyncModuleLease<'_> {
-        let (lock, cvar) = &*self.modules;
-        let mut modules = lock.lock().await;
-        
-        while modules.is_empty() {
-            modules = cvar.wait(modules).await;
-        }
+        loop {
+            self.available.acquire_and_forget(1).await;
+
+            let module = self.modules
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("a permit is only available for an idle module");
+
+            if !module.needs_reset() {
+                return AsyncModuleLease {
+                    pool: self,
+                    module: Some(module),
+                };
+            }
 
-        AsyncModuleLease {
-            pool: self,
-            module: Some(modules.pop_front().unwrap()),
+            match self.reset(module).await {
+                Ok(module) => return AsyncModuleLease {
+                    pool: self,
+                    module: Some(module),
+                },
+                Err(e) => log::warn!("discarding pooled module that failed to reset: {}", e),
+            }
         }
     }
 
     /// Returns a module to the pool.
     /// 
+    /// Modules with a cancelled or interrupted call are reset before being returned.
+    /// A module that can not be instantiated again is discarded, shrinking the pool,
+    /// and the error is logged. Use
+    /// [`try_return_module`](crate::pool::AsyncModulePool::try_return_module)
+    /// to handle the error instead.
+    /// 
     /// # Arguments
     /// * `module` - The asynchronous module to return to the pool.
     pub async fn return_module(&self, module: AsyncModule) {
-        let (lock, cvar) = &*self.modules;
-        let mut modules = lock.lock().await;
-        
-        modules.push_back(module);
-        cvar.notify_one();
+        if let Err(e) = self.try_return_module(module).await {
+            log::warn!("discarding pooled module that failed to reset: {}", e);
+        }
+    }
+
+    /// Returns a module to the pool, reporting a failed reset.
+    /// 
+    /// Like [`return_module`](crate::pool::AsyncModulePool::return_module), modules with
+    /// a cancelled or interrupted call are reset before being returned, and a module that
+    /// can not be instantiated again is discarded, shrinking the pool.
+    /// 
+    /// # Arguments
+    /// * `module` - The asynchronous module to return to the pool.
+    /// 
+    /// # Returns
+    /// A result indicating success or the error returned while resetting the module.
+    pub async fn try_return_module(&self, module: AsyncModule) -> ModuleResult<()> {
+        let module = if module.needs_reset() {
+            self.reset(module).await?
+        } else {
+            module
+        };
+
+        self.push(module);
+
+        Ok(())
+    }
+
+    /// Reset a module of the pool, which is discarded if the reset fails.
+    async fn reset(&self, module: AsyncModule) -> ModuleResult<AsyncModule> {
+        // The module leaves the pool while it is reset, so it stays
+        // discarded if the reset fails or its future is dropped.
+        self.size.fetch_sub(1, Ordering::AcqRel);
+        let module = module.reset().await?;
+        self.size.fetch_add(1, Ordering::AcqRel);
+
+        Ok(module)
+    }
+
+    /// Add an idle module to the pool, waking up a waiting lease.
+    fn push(&self, module: AsyncModule) {
+        self.modules.lock().unwrap().push_back(module);
+        self.available.release(1);
+    }
+
+    /// Removes up to `count` idle modules from the pool and shuts them down.
+    /// 
+    /// Leased modules are not affected.
+    /// 
+    /// # Arguments
+    /// * `count` - The maximum number of modules to remove.
+    /// 
+    /// # Returns
+    /// The number of modules removed, or the first error returned while shutting them down.
+    pub async fn shrink(&self, count: usize) -> ModuleResult<usize> {
+        let mut removed = vec![];
+
+        while removed.len() < count && self.available.try_acquire_and_forget(1) {
+            removed.push(
+                self.modules
+                    .lock()
+                    .unwrap()
+                    .pop_front()
+                    .expect("a permit is only available for an idle module")
+            );
+        }
+
+        let count = removed.len();
+        self.size.fetch_sub(count, Ordering::AcqRel);
+
+        let mut result = Ok(());
+
+        for mut module in removed {
+            result = result.and(module.shutdown().await);
+        }
+
+        result.map(|_| count)
+    }
+
+    /// Removes all idle modules from the pool and shuts them down.
+    /// 
+    /// # Returns
+    /// The number of modules removed, or the first error returned while shutting them down.
+    pub async fn drain(&self) -> ModuleResult<usize> {
+        self.shrink(usize::MAX).await
     }
 
     /// Executes a function with a leased asynchronous module from the pool.
@@ -397,6 +559,9 @@ impl AsyncModulePool {
 }
 
 /// A lease on an asynchronous module from an AsyncModulePool.
+/// 
+/// Dropping the lease without releasing it hands the module back to the pool,
+/// which resets it on its next lease if it was dropped during a call.
 pub struct AsyncModuleLease<'a> {
     pool: &'a AsyncModulePool,
     module: Option<AsyncModule>,
@@ -404,11 +569,31 @@ pub struct AsyncModuleLease<'a> {
 
 impl<'a> AsyncModuleLease<'a> {
     /// Releases the leased asynchronous module back to the pool.
+    /// 
+    /// A module that fails to reset is discarded, and the error is logged. Use
+    /// [`AsyncModulePool::try_return_module`](crate::pool::AsyncModulePool::try_return_module)
+    /// with the module taken out of the lease to handle the error instead.
     pub async fn release(&mut self) {
         if let Some(module) = self.module.take() {
             self.pool.return_module(module).await;
         }
     }
+
+    /// Take the module out of the lease without returning it to the pool.
+    /// 
+    /// The module still counts towards the pool's size, and should be handed back with
+    /// [`AsyncModulePool::return_module`](crate::pool::AsyncModulePool::return_module).
+    pub fn take(mut self) -> AsyncModule {
+        self.module.take().unwrap()
+    }
+}
+
+impl Drop for AsyncModuleLease<'_> {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            self.pool.push(module);
+        }
+    }
 }
 
 impl Deref for AsyncModuleLease<'_> {
@@ -425,6 +610,18 @@ impl DerefMut for AsyncModuleLease<'_> {
     }
 }
 
+impl Borrow<AsyncModule> for AsyncModuleLease<'_> {
+    fn borrow(&self) -> &AsyncModule {
+        self
+    }
+}
+
+impl BorrowMut<AsyncModule> for AsyncModuleLease<'_> {
+    fn borrow_mut(&mut self) -> &mut AsyncModule {
+        self
+    }
+}
+
 /// A builder for creating AsyncModulePool instances.
 pub struct AsyncModulePoolBuilder {
     template: Option<AsyncModule>,
@@ -502,7 +699,7 @@ impl AsyncModulePoolBuilder {
                     .try_collect::<Vec<_>>()
                     .await?
                     .into_iter()
-                    .chain(vec![template].into_iter())
+                    .chain(vec![template])
                     .collect::<Vec<_>>()
             );
 
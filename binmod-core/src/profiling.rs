@@ -0,0 +1,120 @@
+use std::{
+    sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use wasmtime::{Engine, Store, Trap, UpdateDeadline};
+
+use crate::state::ModuleState;
+
+
+/// Epoch deadline callback installed on a store while it is being profiled.
+///
+/// The sampler only reacts to the epoch increments of the store's engine,
+/// which are driven by an [`EpochTicker`] for the duration of profiling.
+pub(crate) struct Sampler {
+    ticks: Arc<AtomicU64>,
+    deadline: Option<u64>,
+}
+
+impl Sampler {
+    /// Install an epoch deadline callback on the store that records a sample
+    /// with the store's guest profiler every time the engine's epoch is incremented.
+    ///
+    /// The store still traps once `deadline` epoch increments have elapsed,
+    /// so the deadline set before profiling keeps applying.
+    pub fn install(store: &mut Store<ModuleState>, deadline: Option<u64>) -> Self {
+        let ticks = Arc::new(AtomicU64::new(0));
+        let mut last_sample = Instant::now();
+
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback({
+            let ticks = ticks.clone();
+            move |mut ctx| {
+                if let Some(mut profiler) = ctx.data_mut().profiler.take() {
+                    profiler.sample(&ctx, last_sample.elapsed());
+                    ctx.data_mut().profiler = Some(profiler);
+                }
+
+                last_sample = Instant::now();
+                let elapsed = ticks.fetch_add(1, Ordering::Relaxed) + 1;
+
+                match deadline {
+                    Some(deadline) if elapsed >= deadline => Err(Trap::Interrupt.into()),
+                    _ => Ok(UpdateDeadline::Continue(1)),
+                }
+            }
+        });
+
+        Self {
+            ticks,
+            deadline,
+        }
+    }
+
+    /// Remove the sampler, restoring the deadline that was set before profiling
+    /// minus the epoch increments that elapsed in the meantime.
+    pub fn uninstall(self, store: &mut Store<ModuleState>) {
+        let elapsed = self.ticks.load(Ordering::Relaxed);
+
+        store.epoch_deadline_trap();
+        store.set_epoch_deadline(
+            self.deadline
+                .unwrap_or_default()
+                .saturating_sub(elapsed)
+        );
+    }
+}
+
+/// Background thread that increments an engine's epoch at a fixed interval
+/// for the duration of a profiling window.
+///
+/// The epoch is shared by every store of the engine, so their epoch deadlines
+/// advance as well. The thread is stopped when the ticker is dropped.
+pub(crate) struct EpochTicker {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EpochTicker {
+    /// Start ticking the given engine's epoch every `interval`, which must not be zero.
+    pub fn start(engine: Engine, interval: Duration) -> Self {
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let mut next_tick = Instant::now() + interval;
+
+                // Parking instead of sleeping lets `stop` wake the thread
+                // early, so stopping never waits for a full interval.
+                while !stop.load(Ordering::Relaxed) {
+                    let now = Instant::now();
+
+                    if now >= next_tick {
+                        engine.increment_epoch();
+                        next_tick += interval;
+                    } else {
+                        thread::park_timeout(next_tick - now);
+                    }
+                }
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,641 @@
+use std::{
+    borrow::{Borrow, BorrowMut},
+    collections::VecDeque,
+    fs,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, Weak, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    input::{FnInput, IntoFnInput},
+    result::FnResult,
+    module::{Module, ModuleBuilder},
+    error::{ModuleResult, ModuleError},
+};
+
+
+/// Default interval at which the source file of a reloadable module is checked for changes.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Outcome of a reload reported to the reload callback.
+#[derive(Debug)]
+pub enum ReloadEvent {
+    /// The new version was loaded and has replaced the previous one.
+    Reloaded {
+        /// Content hash of the new module binary.
+        content_hash: String,
+    },
+    /// The new version failed to load and the previous one is kept.
+    Failed {
+        /// The error that caused the reload to fail.
+        error: ModuleError,
+    },
+}
+
+/// Callback invoked after every reload attempt.
+pub type ReloadCallback = dyn Fn(&ReloadEvent) + Send + Sync + 'static;
+
+/// A pool of modules that is reloaded whenever the module's source file changes.
+///
+/// On change the module is rebuilt from the original
+/// [`ModuleBuilder`](crate::module::ModuleBuilder), validated and instantiated.
+/// The new instances replace the previous ones all at once after every
+/// in-flight lease has been returned, so callers never observe a mix of
+/// versions. If the new version fails to load, the previous one is kept.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{module::Module, reload::{ReloadablePool, ReloadEvent}};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let pool = ReloadablePool::builder()
+///     .with_builder(
+///         Module::builder()
+///             .from_file("my_module.wasm")?
+///             .with_name("my_module")
+///     )
+///     .with_count(4)
+///     .on_reload(|event| match event {
+///         ReloadEvent::Reloaded { content_hash } => println!("Reloaded {}", content_hash),
+///         ReloadEvent::Failed { error } => eprintln!("Reload failed: {}", error),
+///     })
+///     .build()?;
+///
+/// pool.scoped(|module| module.typed_call::<i64>("add", (2, 3)))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReloadablePool {
+    inner: Arc<ReloadInner>,
+    watcher: Option<Watcher>,
+}
+
+struct ReloadInner {
+    builder: ModuleBuilder,
+    count: usize,
+    on_reload: Option<Arc<ReloadCallback>>,
+    state: Mutex<ReloadState>,
+    available: Condvar,
+    // Serializes reloads so concurrent reloads can't interleave their swaps
+    reloading: Mutex<()>,
+}
+
+struct ReloadState {
+    idle: VecDeque<Module>,
+    leased: usize,
+    pending: Option<Vec<Module>>,
+    content_hash: String,
+}
+
+impl ReloadablePool {
+    /// Creates a new ReloadablePoolBuilder.
+    ///
+    /// # Returns
+    /// A new ReloadablePoolBuilder instance.
+    pub fn builder() -> ReloadablePoolBuilder {
+        ReloadablePoolBuilder::new()
+    }
+
+    /// Get the content hash of the module version currently served by the pool.
+    pub fn content_hash(&self) -> String {
+        self.inner.state.lock().unwrap().content_hash.clone()
+    }
+
+    /// Leases a module from the pool, blocking if necessary until one is available.
+    ///
+    /// Leasing also blocks while a new version is waiting for in-flight leases to be returned,
+    /// so a thread must not lease again while it already holds a lease: if a reload becomes
+    /// pending in between, the second lease waits for the first one to be returned and never
+    /// returns. Use [`try_lease`](crate::reload::ReloadablePool::try_lease) for nested leases.
+    ///
+    /// # Returns
+    /// A ReloadableLease representing the leased module.
+    pub fn lease(&self) -> ReloadableLease<'_> {
+        let mut state = self.inner.state.lock().unwrap();
+
+        while state.idle.is_empty() || state.pending.is_some() {
+            state = self.inner.available.wait(state).unwrap();
+        }
+
+        self.lease_idle(&mut state)
+    }
+
+    /// Attempts to lease a module from the pool without blocking.
+    ///
+    /// # Returns
+    /// An Option containing a ReloadableLease if a module was available and no new
+    /// version is waiting to be swapped in, or None otherwise.
+    pub fn try_lease(&self) -> Option<ReloadableLease<'_>> {
+        let mut state = self.inner.state.lock().unwrap();
+
+        if state.idle.is_empty() || state.pending.is_some() {
+            None
+        } else {
+            Some(self.lease_idle(&mut state))
+        }
+    }
+
+    fn lease_idle(&self, state: &mut ReloadState) -> ReloadableLease<'_> {
+        state.leased += 1;
+
+        ReloadableLease {
+            pool: self,
+            module: Some(state.idle.pop_front().unwrap()),
+        }
+    }
+
+    /// Returns a module to the pool.
+    ///
+    /// # Arguments
+    /// * `module` - The module to return to the pool.
+    pub fn return_module(&self, module: Module) {
+        let retired = {
+            let mut state = self.inner.state.lock().unwrap();
+
+            state.leased -= 1;
+            state.idle.push_back(module);
+            self.inner.swap_if_idle(&mut state)
+        };
+
+        self.inner.available.notify_all();
+        drop(retired);
+    }
+
+    /// Reload the module from its source file.
+    ///
+    /// This is done automatically when the source file changes, but can be
+    /// used to force a reload. The reload callback is invoked with the outcome.
+    ///
+    /// # Returns
+    /// A result indicating success or the error that caused the reload to fail,
+    /// in which case the previous version is kept
+    pub fn reload(&self) -> ModuleResult<()> {
+        self.inner.reload()
+    }
+
+    /// Executes a function with a leased module from the pool.
+    /// The module is automatically returned to the pool after the function completes.
+    ///
+    /// # Arguments
+    /// * `func` - The function to execute with the leased module.
+    ///
+    /// # Returns
+    /// The result of the function.
+    pub fn scoped<F, R>(&self, func: F) -> R
+    where
+        F: FnOnce(&mut Module) -> R,
+    {
+        let mut lease = self.lease();
+        let result = (func)(&mut lease);
+        lease.release();
+        result
+    }
+}
+
+impl Drop for ReloadablePool {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.stop();
+        }
+    }
+}
+
+impl ReloadInner {
+    fn reload(&self) -> ModuleResult<()> {
+        let _reloading = self.reloading.lock().unwrap();
+
+        let result = self.load();
+        let event = match result {
+            Ok(modules) => {
+                let content_hash = modules[0].content_hash().to_string();
+                let retired = {
+                    let mut state = self.state.lock().unwrap();
+
+                    state.pending = Some(modules);
+                    self.swap_if_idle(&mut state)
+                };
+
+                self.available.notify_all();
+                drop(retired);
+
+                ReloadEvent::Reloaded { content_hash }
+            },
+            Err(error) => ReloadEvent::Failed { error },
+        };
+
+        if let Some(on_reload) = &self.on_reload {
+            on_reload(&event);
+        }
+
+        match event {
+            ReloadEvent::Reloaded { .. } => Ok(()),
+            ReloadEvent::Failed { error } => Err(error),
+        }
+    }
+
+    /// Build and instantiate a new set of modules from the source file
+    fn load(&self) -> ModuleResult<Vec<Module>> {
+        let template = self.builder
+            .clone()
+            .reload_source()?
+            .build()?
+            .instantiate()?;
+
+        let mut modules = (0..self.count - 1)
+            .map(|_| template.clone().instantiate())
+            .collect::<ModuleResult<Vec<_>>>()?;
+        modules.push(template);
+
+        Ok(modules)
+    }
+
+    /// Swap in the pending modules if no lease is in flight, returning the retired modules
+    /// so they can be shut down without holding the lock
+    fn swap_if_idle(&self, state: &mut ReloadState) -> Vec<Module> {
+        if state.leased > 0 {
+            return vec![];
+        }
+
+        match state.pending.take() {
+            Some(modules) => {
+                state.content_hash = modules[0].content_hash().to_string();
+                std::mem::replace(&mut state.idle, modules.into()).into()
+            },
+            None => vec![],
+        }
+    }
+}
+
+/// A lease on a module from a ReloadablePool.
+pub struct ReloadableLease<'a> {
+    pool: &'a ReloadablePool,
+    module: Option<Module>,
+}
+
+impl ReloadableLease<'_> {
+    /// Releases the leased module back to the pool.
+    pub fn release(&mut self) {
+        if let Some(module) = self.module.take() {
+            self.pool.return_module(module);
+        }
+    }
+}
+
+impl Deref for ReloadableLease<'_> {
+    type Target = Module;
+
+    fn deref(&self) -> &Self::Target {
+        self.module.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ReloadableLease<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.module.as_mut().unwrap()
+    }
+}
+
+impl Borrow<Module> for ReloadableLease<'_> {
+    fn borrow(&self) -> &Module {
+        self
+    }
+}
+
+impl BorrowMut<Module> for ReloadableLease<'_> {
+    fn borrow_mut(&mut self) -> &mut Module {
+        self
+    }
+}
+
+impl Drop for ReloadableLease<'_> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// A single module that is reloaded whenever its source file changes.
+///
+/// Calls are serialized, and a reload takes effect between calls.
+/// See [`ReloadablePool`](crate::reload::ReloadablePool) for details.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::{module::Module, reload::ReloadableModule};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let module = ReloadableModule::builder()
+///     .with_builder(
+///         Module::builder()
+///             .from_file("my_module.wasm")?
+///             .with_name("my_module")
+///     )
+///     .build_module()?;
+///
+/// let sum: i64 = module.typed_call("add", (2, 3))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReloadableModule {
+    pool: ReloadablePool,
+}
+
+impl ReloadableModule {
+    /// Creates a new ReloadablePoolBuilder.
+    ///
+    /// # Returns
+    /// A new ReloadablePoolBuilder instance.
+    pub fn builder() -> ReloadablePoolBuilder {
+        ReloadablePoolBuilder::new()
+    }
+
+    /// Get the content hash of the module version currently loaded.
+    pub fn content_hash(&self) -> String {
+        self.pool.content_hash()
+    }
+
+    /// Call a function within the module with typed arguments and return value.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A result containing the return value of the function or an error
+    /// if the call fails
+    pub fn typed_call<R>(&self, name: impl AsRef<str>, args: impl IntoFnInput) -> ModuleResult<R>
+    where
+        R: DeserializeOwned,
+    {
+        self.pool.scoped(|module| module.typed_call(name, args))
+    }
+
+    /// Call a function within the module.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to call
+    /// * `input` - The input to pass to the function
+    ///
+    /// # Returns
+    /// A result containing the [`FnResult`](crate::result::FnResult) of the function call or an error
+    /// if the call fails
+    pub fn call(&self, name: impl AsRef<str>, input: FnInput) -> ModuleResult<FnResult> {
+        self.pool.scoped(|module| module.call(name, input))
+    }
+
+    /// Executes a function with exclusive access to the current version of the module.
+    ///
+    /// # Arguments
+    /// * `func` - The function to execute with the module.
+    ///
+    /// # Returns
+    /// The result of the function.
+    pub fn scoped<F, R>(&self, func: F) -> R
+    where
+        F: FnOnce(&mut Module) -> R,
+    {
+        self.pool.scoped(func)
+    }
+
+    /// Reload the module from its source file.
+    ///
+    /// # Returns
+    /// A result indicating success or the error that caused the reload to fail,
+    /// in which case the previous version is kept
+    pub fn reload(&self) -> ModuleResult<()> {
+        self.pool.reload()
+    }
+}
+
+/// A builder for creating ReloadablePool and ReloadableModule instances.
+pub struct ReloadablePoolBuilder {
+    builder: Option<ModuleBuilder>,
+    count: usize,
+    poll_interval: Duration,
+    watch: bool,
+    on_reload: Option<Arc<ReloadCallback>>,
+}
+
+impl ReloadablePoolBuilder {
+    /// Creates a new ReloadablePoolBuilder.
+    ///
+    /// # Returns
+    /// A new ReloadablePoolBuilder instance.
+    pub fn new() -> Self {
+        Self {
+            builder: None,
+            count: 1,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            watch: true,
+            on_reload: None,
+        }
+    }
+
+    /// Sets the module builder to use for instantiation.
+    ///
+    /// The builder must load the module with
+    /// [`from_file`](crate::module::ModuleBuilder::from_file) or
+    /// [`from_package`](crate::module::ModuleBuilder::from_package),
+    /// whose file is watched for changes.
+    ///
+    /// # Arguments
+    /// * `builder` - The module builder to use.
+    ///
+    /// # Returns
+    /// The updated ReloadablePoolBuilder instance.
+    pub fn with_builder(mut self, builder: ModuleBuilder) -> Self {
+        self.builder = Some(builder);
+        self
+    }
+
+    /// Sets the number of modules to instantiate in the pool.
+    ///
+    /// # Arguments
+    /// * `count` - The number of modules to instantiate.
+    ///
+    /// # Returns
+    /// The updated ReloadablePoolBuilder instance.
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Sets the interval at which the source file is checked for changes.
+    ///
+    /// # Arguments
+    /// * `interval` - The interval to poll the source file at.
+    ///
+    /// # Returns
+    /// The updated ReloadablePoolBuilder instance.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Enables or disables watching the source file for changes.
+    ///
+    /// When disabled, modules are only reloaded by calling `reload`.
+    ///
+    /// # Arguments
+    /// * `watch` - Whether to watch the source file.
+    ///
+    /// # Returns
+    /// The updated ReloadablePoolBuilder instance.
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    /// Sets the callback invoked after every reload attempt.
+    ///
+    /// # Arguments
+    /// * `callback` - The callback to invoke with the outcome of the reload.
+    ///
+    /// # Returns
+    /// The updated ReloadablePoolBuilder instance.
+    pub fn on_reload<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ReloadEvent) + Send + Sync + 'static,
+    {
+        self.on_reload = Some(Arc::new(callback));
+        self
+    }
+
+    /// Build a ReloadablePool instance from the provided configuration.
+    pub fn build(self) -> ModuleResult<ReloadablePool> {
+        if self.count == 0 {
+            return Err(ModuleError::InstantiationError(
+                "ReloadablePool must have a count greater than zero".to_string(),
+            ));
+        }
+
+        let builder = self.builder.ok_or_else(|| ModuleError::InstantiationError(
+            "A builder must be provided to build a ReloadablePool".to_string(),
+        ))?;
+        let path = builder
+            .source_path()
+            .map(|path| path.to_path_buf())
+            .ok_or_else(|| ModuleError::InvalidModuleConfig(
+                "Hot reload requires a module loaded from a file".to_string(),
+            ))?;
+
+        let inner = Arc::new(ReloadInner {
+            builder,
+            count: self.count,
+            on_reload: self.on_reload,
+            state: Mutex::new(ReloadState {
+                idle: VecDeque::new(),
+                leased: 0,
+                pending: None,
+                content_hash: String::new(),
+            }),
+            available: Condvar::new(),
+            reloading: Mutex::new(()),
+        });
+
+        // The initial load is not a reload, so failures are
+        // returned directly instead of being reported.
+        let modules = inner.load()?;
+        {
+            let mut state = inner.state.lock().unwrap();
+
+            state.content_hash = modules[0].content_hash().to_string();
+            state.idle = modules.into();
+        }
+
+        let watcher = self.watch.then(|| Watcher::start(
+            Arc::downgrade(&inner),
+            path,
+            self.poll_interval,
+        ));
+
+        Ok(ReloadablePool {
+            inner,
+            watcher,
+        })
+    }
+
+    /// Build a ReloadableModule instance from the provided configuration.
+    pub fn build_module(self) -> ModuleResult<ReloadableModule> {
+        Ok(ReloadableModule {
+            pool: self.with_count(1).build()?,
+        })
+    }
+}
+
+impl Default for ReloadablePoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Background thread that polls a source file and reloads the pool when it changes.
+struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    fn start(inner: Weak<ReloadInner>, path: PathBuf, interval: Duration) -> Self {
+        let stamp = |path: &PathBuf| fs::metadata(path)
+            .ok()
+            .map(|metadata| (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len()));
+
+        // The file is stamped before the thread starts, so changes made
+        // right after the pool is built are not taken as the baseline.
+        let mut current = stamp(&path);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let mut changed = None;
+
+                while !stop.load(Ordering::Relaxed) {
+                    thread::park_timeout(interval);
+
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    let latest = stamp(&path);
+
+                    // Only reload once the file has stayed the same for a full
+                    // interval, so a binary that is still being written is skipped.
+                    if latest.is_some() && latest != current {
+                        if changed == latest {
+                            let Some(inner) = inner.upgrade() else {
+                                break;
+                            };
+
+                            // Failures are reported through the reload callback
+                            let _ = inner.reload();
+                            current = latest;
+                            changed = None;
+                        } else {
+                            changed = latest;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the watcher and wait for the background thread to exit.
+    fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
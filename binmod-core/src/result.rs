@@ -1,8 +1,8 @@
 use std::any;
 use serde::{Serialize, Deserialize};
-use serde_json::{to_value, to_vec, from_value, from_slice, Value};
+use serde_json::{to_value, from_value, Value};
 
-use crate::error::FnError;
+use crate::{codec::Codec, error::FnError};
 
 
 /// Result type for function calls
@@ -82,19 +82,28 @@ impl FnResult {
         matches!(self, Self::Data { .. })
     }
 
-    /// Serialize the Function result to bytes.
+    /// Serialize the Function result to JSON bytes.
     /// 
     /// # Returns
     /// A Result containing the serialized bytes or an [`FnError`](crate::error::FnError)
     /// if serialization fails
     pub fn to_bytes(&self) -> Result<Vec<u8>, FnError> {
-        Ok(
-            to_vec(self)
-                .map_err(|e| FnError::new("SerializationError", e.to_string()))?
-        )
+        self.to_bytes_with(Codec::Json)
     }
 
-    /// Deserialize Function result from bytes.
+    /// Serialize the Function result to bytes using the given codec.
+    /// 
+    /// # Arguments
+    /// * `codec` - The codec to serialize with
+    /// 
+    /// # Returns
+    /// A Result containing the serialized bytes or an [`FnError`](crate::error::FnError)
+    /// if serialization fails
+    pub fn to_bytes_with(&self, codec: Codec) -> Result<Vec<u8>, FnError> {
+        codec.encode(self)
+    }
+
+    /// Deserialize Function result from JSON bytes.
     /// 
     /// # Arguments
     /// * `bytes` - The bytes to deserialize from
@@ -103,10 +112,20 @@ impl FnResult {
     /// A Result containing the deserialized [`FnResult`](crate::result::FnResult) instance
     /// or an [`FnError`](crate::error::FnError) if deserialization fails
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, FnError> {
-        Ok(
-            from_slice(bytes)
-                .map_err(|e| FnError::new("DeserializationError", e.to_string()))?
-        )
+        Self::from_bytes_with(bytes, Codec::Json)
+    }
+
+    /// Deserialize Function result from bytes using the given codec.
+    /// 
+    /// # Arguments
+    /// * `bytes` - The bytes to deserialize from
+    /// * `codec` - The codec to deserialize with
+    /// 
+    /// # Returns
+    /// A Result containing the deserialized [`FnResult`](crate::result::FnResult) instance
+    /// or an [`FnError`](crate::error::FnError) if deserialization fails
+    pub fn from_bytes_with(bytes: &[u8], codec: Codec) -> Result<Self, FnError> {
+        codec.decode(bytes)
     }
 }
 
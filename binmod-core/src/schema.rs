@@ -0,0 +1,688 @@
+use std::collections::BTreeMap;
+use schemars::generate::SchemaSettings;
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{FnError, ModuleResult},
+    input::FnInput,
+    result::FnResult,
+};
+
+pub use schemars::{self, JsonSchema, Schema};
+
+
+/// Generate the self-contained JSON Schema of a type.
+///
+/// # Returns
+/// The draft 2020-12 [`Schema`](crate::schema::Schema) of the type
+pub fn schema_for<T: JsonSchema + ?Sized>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .with(|settings| settings.meta_schema = None)
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// The JSON Schema of a positional argument
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArgSchema {
+    /// The name of the argument, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The schema of the argument
+    pub schema: Schema,
+}
+
+/// Describes the arguments and return value of a function with JSON Schemas
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FnSchema {
+    /// A description of the function
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The positional arguments of the function
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<ArgSchema>,
+    /// The schema of the keyword arguments, as an object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kwargs: Option<Schema>,
+    /// The schema of the return value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub returns: Option<Schema>,
+}
+
+impl FnSchema {
+    /// Create a new, empty function schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the schema of a host function from its argument and return types.
+    ///
+    /// # Returns
+    /// A new [`FnSchema`](crate::schema::FnSchema) with unnamed positional arguments
+    pub fn of<Args: ArgsSchema, R: ResultSchema>() -> Self {
+        Self {
+            args: Args::args_schema(),
+            returns: R::result_schema(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the description of the function.
+    ///
+    /// # Arguments
+    /// * `description` - The description to set
+    ///
+    /// # Returns
+    /// The updated FnSchema instance
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add a named positional argument of type `T`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the argument
+    ///
+    /// # Returns
+    /// The updated FnSchema instance
+    pub fn with_arg<T: JsonSchema + ?Sized>(mut self, name: impl Into<String>) -> Self {
+        self.args.push(ArgSchema {
+            name: Some(name.into()),
+            schema: schema_for::<T>(),
+        });
+        self
+    }
+
+    /// Set the keyword arguments to the fields of type `T`.
+    ///
+    /// # Returns
+    /// The updated FnSchema instance
+    pub fn with_kwargs<T: JsonSchema + ?Sized>(mut self) -> Self {
+        self.kwargs = Some(schema_for::<T>());
+        self
+    }
+
+    /// Set the return value to type `T`.
+    ///
+    /// # Returns
+    /// The updated FnSchema instance
+    pub fn with_returns<T: JsonSchema + ?Sized>(mut self) -> Self {
+        self.returns = Some(schema_for::<T>());
+        self
+    }
+
+    /// Set the return value from the result type `R` of a host function.
+    ///
+    /// # Returns
+    /// The updated FnSchema instance
+    pub fn with_result<R: ResultSchema>(mut self) -> Self {
+        self.returns = R::result_schema();
+        self
+    }
+
+    /// Validate the arguments of a call against the schema.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the call
+    ///
+    /// # Returns
+    /// A result indicating success or a `ValidationError` [`FnError`](crate::error::FnError)
+    /// locating the first invalid value, such as `args[1][3]: expected number, found string`
+    pub fn validate_input(&self, input: &FnInput) -> Result<(), FnError> {
+        let args = input.args.as_deref().unwrap_or_default();
+
+        if args.len() > self.args.len() {
+            return Err(validation_error(
+                "args",
+                format!("expected at most {} positional arguments, found {}", self.args.len(), args.len()),
+            ));
+        }
+
+        for (index, arg) in self.args.iter().enumerate() {
+            let path = format!("args[{}]", index);
+
+            match args.get(index) {
+                Some(value) => validate(&arg.schema, value, &path)?,
+                None => return Err(validation_error(&path, "missing argument")),
+            }
+        }
+
+        if let Some(kwargs) = &self.kwargs {
+            let value = Value::Object(
+                input.kwargs
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect::<Map<_, _>>()
+            );
+
+            validate(kwargs, &value, "kwargs")?;
+        }
+
+        Ok(())
+    }
+
+    /// Validate the return value of a call against the schema.
+    ///
+    /// Error results are not validated.
+    ///
+    /// # Arguments
+    /// * `result` - The result of the call
+    ///
+    /// # Returns
+    /// A result indicating success or a `ValidationError` [`FnError`](crate::error::FnError)
+    /// locating the first invalid value
+    pub fn validate_result(&self, result: &FnResult) -> Result<(), FnError> {
+        match (&self.returns, result) {
+            (Some(returns), FnResult::Data { value }) => validate(returns, value.as_ref().unwrap_or(&Value::Null), "result"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Trait for argument tuples describing their positional arguments
+pub trait ArgsSchema {
+    fn args_schema() -> Vec<ArgSchema>;
+}
+
+impl ArgsSchema for () {
+    fn args_schema() -> Vec<ArgSchema> {
+        vec![]
+    }
+}
+
+macro_rules! impl_args_schema {
+    ($($T:ident),+) => {
+        impl<$($T: JsonSchema),+> ArgsSchema for ($($T,)+) {
+            fn args_schema() -> Vec<ArgSchema> {
+                vec![$(
+                    ArgSchema {
+                        name: None,
+                        schema: schema_for::<$T>(),
+                    }
+                ),+]
+            }
+        }
+    };
+}
+
+impl_args_schema!(A1);
+impl_args_schema!(A1, A2);
+impl_args_schema!(A1, A2, A3);
+impl_args_schema!(A1, A2, A3, A4);
+impl_args_schema!(A1, A2, A3, A4, A5);
+impl_args_schema!(A1, A2, A3, A4, A5, A6);
+impl_args_schema!(A1, A2, A3, A4, A5, A6, A7);
+impl_args_schema!(A1, A2, A3, A4, A5, A6, A7, A8);
+
+/// Trait for host function results describing their return value
+pub trait ResultSchema {
+    fn result_schema() -> Option<Schema>;
+}
+
+impl ResultSchema for () {
+    fn result_schema() -> Option<Schema> {
+        None
+    }
+}
+
+impl<T: JsonSchema, E> ResultSchema for Result<T, E> {
+    fn result_schema() -> Option<Schema> {
+        Some(schema_for::<T>())
+    }
+}
+
+/// Trait for host functions describing their signature, implemented
+/// for functions and closures whose argument and return types have schemas
+pub trait DescribeHostFn<Args> {
+    fn describe() -> FnSchema;
+}
+
+impl<F, R> DescribeHostFn<()> for F
+where
+    F: Fn() -> R,
+    R: ResultSchema,
+{
+    fn describe() -> FnSchema {
+        FnSchema::of::<(), R>()
+    }
+}
+
+macro_rules! impl_describe_host_fn {
+    ($($T:ident),+) => {
+        impl<F, R, $($T),+> DescribeHostFn<($($T,)+)> for F
+        where
+            F: Fn($($T),+) -> R,
+            ($($T,)+): ArgsSchema,
+            R: ResultSchema,
+        {
+            fn describe() -> FnSchema {
+                FnSchema::of::<($($T,)+), R>()
+            }
+        }
+    };
+}
+
+impl_describe_host_fn!(A1);
+impl_describe_host_fn!(A1, A2);
+impl_describe_host_fn!(A1, A2, A3);
+impl_describe_host_fn!(A1, A2, A3, A4);
+impl_describe_host_fn!(A1, A2, A3, A4, A5);
+impl_describe_host_fn!(A1, A2, A3, A4, A5, A6);
+impl_describe_host_fn!(A1, A2, A3, A4, A5, A6, A7);
+impl_describe_host_fn!(A1, A2, A3, A4, A5, A6, A7, A8);
+
+/// A machine-readable description of the functions a module imports from
+/// the host and exports to it, for use by MDKs generating bindings.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::module::Module;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let builder = Module::builder()
+///     .with_name("my_module")
+///     .host_fn_with_schema("get_pi", || -> Result<f64, String> { Ok(3.14159) });
+///
+/// std::fs::write("my_module.interface.json", builder.describe_host_fns().to_json()?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct InterfaceSchema {
+    /// The name of the module or interface
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The host functions available to the module, by namespace and name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub imports: BTreeMap<String, BTreeMap<String, FnSchema>>,
+    /// The functions exported by the module, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub exports: BTreeMap<String, FnSchema>,
+}
+
+impl InterfaceSchema {
+    /// Create a new, empty interface schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the schema of an imported host function.
+    ///
+    /// # Arguments
+    /// * `namespace` - The namespace of the host function
+    /// * `name` - The name of the host function
+    pub fn import(&self, namespace: &str, name: &str) -> Option<&FnSchema> {
+        self.imports.get(namespace)?.get(name)
+    }
+
+    /// Get the schema of an exported function.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function
+    pub fn export(&self, name: &str) -> Option<&FnSchema> {
+        self.exports.get(name)
+    }
+
+    /// Serialize the interface schema to pretty-printed JSON.
+    ///
+    /// # Returns
+    /// A result containing the JSON document or an error if serialization fails
+    pub fn to_json(&self) -> ModuleResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Deserialize an interface schema from JSON.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON document
+    ///
+    /// # Returns
+    /// A result containing the interface schema or an error if the document is invalid
+    pub fn from_json(json: &str) -> ModuleResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Validate a value against a schema, reporting the first violation under the given path.
+///
+/// Supports the subset of JSON Schema produced by [`schema_for`](crate::schema::schema_for):
+/// types, enums, constants, numeric and length bounds, objects, arrays, tuples, local
+/// `$ref`s and the `allOf`, `anyOf` and `oneOf` combinators. Formats and patterns are not checked.
+///
+/// # Arguments
+/// * `schema` - The schema to validate against
+/// * `value` - The value to validate
+/// * `path` - The path of the value, used as the prefix of error messages
+///
+/// # Returns
+/// A result indicating success or a `ValidationError` [`FnError`](crate::error::FnError)
+pub fn validate(schema: &Schema, value: &Value, path: &str) -> Result<(), FnError> {
+    let root = schema.as_value();
+
+    check(root, root, value, path.to_string())
+        .map_err(|violation| validation_error(&violation.path, violation.message))
+}
+
+/// A value that does not conform to a schema
+struct Violation {
+    path: String,
+    message: String,
+}
+
+fn validation_error(path: &str, message: impl std::fmt::Display) -> FnError {
+    FnError::new("ValidationError", format!("{}: {}", path, message))
+}
+
+fn violation(path: &str, message: impl Into<String>) -> Violation {
+    Violation {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Check a value against a schema, resolving references against the root schema
+fn check(root: &Value, schema: &Value, value: &Value, path: String) -> Result<(), Violation> {
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return Err(violation(&path, "no value is allowed")),
+        Value::Object(schema) => schema,
+        _ => return Ok(()),
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str)
+        && let Some(target) = reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer))
+    {
+        check(root, target, value, path.clone())?;
+    }
+
+    if let Some(types) = schema.get("type") {
+        let types = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            types => types.as_str().into_iter().collect::<Vec<_>>(),
+        };
+
+        if !types.is_empty() && !types.iter().any(|ty| is_type(ty, value)) {
+            return Err(violation(&path, format!("expected {}, found {}", types.join(" or "), type_name(value))));
+        }
+    }
+
+    if let Some(constant) = schema.get("const")
+        && constant != value
+    {
+        return Err(violation(&path, format!("expected {}", constant)));
+    }
+
+    if let Some(Value::Array(variants)) = schema.get("enum")
+        && !variants.contains(value)
+    {
+        let variants = variants.iter().map(Value::to_string).collect::<Vec<_>>();
+        return Err(violation(&path, format!("expected one of {}", variants.join(", "))));
+    }
+
+    match value {
+        Value::Number(number) => check_number(schema, number.as_f64().unwrap_or_default(), &path)?,
+        Value::String(string) => check_length(schema, string.chars().count(), "minLength", "maxLength", "characters", &path)?,
+        Value::Array(items) => check_array(root, schema, items, &path)?,
+        Value::Object(object) => check_object(root, schema, object, &path)?,
+        _ => {},
+    }
+
+    if let Some(Value::Array(schemas)) = schema.get("allOf") {
+        for schema in schemas {
+            check(root, schema, value, path.clone())?;
+        }
+    }
+
+    if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+        let mut violations = vec![];
+
+        for schema in schemas {
+            match check(root, schema, value, path.clone()) {
+                Ok(()) => return Ok(()),
+                Err(violation) => violations.push(violation),
+            }
+        }
+
+        if let Some(violation) = deepest(violations) {
+            return Err(violation);
+        }
+    }
+
+    if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+        let mut violations = vec![];
+
+        for schema in schemas {
+            if let Err(violation) = check(root, schema, value, path.clone()) {
+                violations.push(violation);
+            }
+        }
+
+        match schemas.len() - violations.len() {
+            1 => {},
+            0 => return Err(deepest(violations).unwrap_or_else(|| violation(&path, "matches no schema"))),
+            _ => return Err(violation(&path, "matches more than one schema")),
+        }
+    }
+
+    Ok(())
+}
+
+/// Pick the violation furthest into the value, which best explains a failed combinator
+fn deepest(violations: Vec<Violation>) -> Option<Violation> {
+    violations
+        .into_iter()
+        .rev()
+        .max_by_key(|violation| violation.path.len())
+}
+
+fn check_number(schema: &Map<String, Value>, number: f64, path: &str) -> Result<(), Violation> {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+
+    if let Some(minimum) = bound("minimum") && number < minimum {
+        return Err(violation(path, format!("expected a number >= {}", minimum)));
+    }
+
+    if let Some(maximum) = bound("maximum") && number > maximum {
+        return Err(violation(path, format!("expected a number <= {}", maximum)));
+    }
+
+    if let Some(minimum) = bound("exclusiveMinimum") && number <= minimum {
+        return Err(violation(path, format!("expected a number > {}", minimum)));
+    }
+
+    if let Some(maximum) = bound("exclusiveMaximum") && number >= maximum {
+        return Err(violation(path, format!("expected a number < {}", maximum)));
+    }
+
+    Ok(())
+}
+
+fn check_length(schema: &Map<String, Value>, length: usize, min: &str, max: &str, unit: &str, path: &str) -> Result<(), Violation> {
+    if let Some(min) = schema.get(min).and_then(Value::as_u64) && (length as u64) < min {
+        return Err(violation(path, format!("expected at least {} {}, found {}", min, unit, length)));
+    }
+
+    if let Some(max) = schema.get(max).and_then(Value::as_u64) && (length as u64) > max {
+        return Err(violation(path, format!("expected at most {} {}, found {}", max, unit, length)));
+    }
+
+    Ok(())
+}
+
+fn check_array(root: &Value, schema: &Map<String, Value>, items: &[Value], path: &str) -> Result<(), Violation> {
+    check_length(schema, items.len(), "minItems", "maxItems", "items", path)?;
+
+    let prefix = match schema.get("prefixItems") {
+        Some(Value::Array(prefix)) => prefix.as_slice(),
+        _ => &[],
+    };
+
+    for (index, item) in items.iter().enumerate() {
+        let item_schema = match prefix.get(index) {
+            Some(schema) => Some(schema),
+            None => schema.get("items"),
+        };
+
+        if let Some(item_schema) = item_schema {
+            check(root, item_schema, item, format!("{}[{}]", path, index))?;
+        }
+    }
+
+    if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+        for (index, item) in items.iter().enumerate() {
+            if items[..index].contains(item) {
+                return Err(violation(&format!("{}[{}]", path, index), "duplicate item"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_object(root: &Value, schema: &Map<String, Value>, object: &Map<String, Value>, path: &str) -> Result<(), Violation> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                return Err(violation(&format!("{}.{}", path, name), "missing required property"));
+            }
+        }
+    }
+
+    for (name, value) in object {
+        let property_path = format!("{}.{}", path, name);
+
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(property) => check(root, property, value, property_path)?,
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => return Err(violation(&property_path, "unexpected property")),
+                Some(additional) => check(root, additional, value, property_path)?,
+                None => {},
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|number| number.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    fn message(result: Result<(), FnError>) -> String {
+        result.unwrap_err().message
+    }
+
+    #[test]
+    fn reports_nested_array_path() {
+        let schema = FnSchema::new()
+            .with_arg::<String>("name")
+            .with_arg::<Vec<f64>>("values");
+        let input = FnInput::new()
+            .with_args([json!("a"), json!([1, 2, 3, "four"])])
+            .unwrap();
+
+        assert_eq!(message(schema.validate_input(&input)), "args[1][3]: expected number, found string");
+    }
+
+    #[test]
+    fn reports_property_path() {
+        let schema = FnSchema::new().with_arg::<Point>("point");
+        let input = FnInput::new().with_arg(json!({ "x": 1.0, "y": "2" })).unwrap();
+
+        assert_eq!(message(schema.validate_input(&input)), "args[0].y: expected number, found string");
+    }
+
+    #[test]
+    fn reports_missing_property() {
+        let schema = FnSchema::new().with_arg::<Point>("point");
+        let input = FnInput::new().with_arg(json!({ "x": 1.0 })).unwrap();
+
+        assert_eq!(message(schema.validate_input(&input)), "args[0].y: missing required property");
+    }
+
+    #[test]
+    fn reports_missing_and_extra_arguments() {
+        let schema = FnSchema::new()
+            .with_arg::<i32>("a")
+            .with_arg::<i32>("b");
+
+        let missing = FnInput::new().with_arg(1).unwrap();
+        assert_eq!(message(schema.validate_input(&missing)), "args[1]: missing argument");
+
+        let extra = FnInput::new().with_args([1, 2, 3]).unwrap();
+        assert_eq!(message(schema.validate_input(&extra)), "args: expected at most 2 positional arguments, found 3");
+    }
+
+    #[test]
+    fn reports_kwargs_path() {
+        let schema = FnSchema::new().with_kwargs::<Point>();
+        let input = FnInput::new()
+            .with_kwarg("x", 1.0)
+            .unwrap()
+            .with_kwarg("y", true)
+            .unwrap();
+
+        assert_eq!(message(schema.validate_input(&input)), "kwargs.y: expected number, found boolean");
+    }
+
+    #[test]
+    fn reports_result_path() {
+        let schema = FnSchema::new().with_returns::<Vec<u32>>();
+
+        assert!(schema.validate_result(&FnResult::Data { value: Some(json!([1, 2])) }).is_ok());
+        assert_eq!(
+            message(schema.validate_result(&FnResult::Data { value: Some(json!([1, -2])) })),
+            "result[1]: expected a number >= 0",
+        );
+    }
+
+    #[test]
+    fn accepts_valid_input() {
+        let schema = FnSchema::new()
+            .with_arg::<Point>("point")
+            .with_arg::<Option<String>>("label");
+        let input = FnInput::new()
+            .with_args([json!({ "x": 1.0, "y": 2.0 }), json!(null)])
+            .unwrap();
+
+        assert!(schema.validate_input(&input).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use wasmtime::{Engine, Store, Config};
+use wasmtime::component::{InstancePre, Linker, ResourceTable};
+use wasmtime_wasi::p2;
+
+use crate::{
+    artifacts,
+    state::ComponentState,
+    host_fns::{self, HostFn, HostFnSet},
+    config::{ModuleEnv, ModuleConfig, ModuleLimits},
+    error::{ModuleResult, ModuleError},
+};
+
+
+/// Create the engine for a component module.
+///
+/// Async engines always consume fuel, since async execution
+/// yields every time it consumes a set amount of fuel.
+///
+/// # Arguments
+/// * `config` - The configuration for the module
+/// * `is_async` - Whether the engine runs components asynchronously
+///
+/// # Returns
+/// The engine or an error if the configuration is invalid
+pub(crate) fn component_engine(config: &ModuleConfig, is_async: bool) -> ModuleResult<Engine> {
+    let mut engine_config: Config = config
+        .clone()
+        .into();
+    engine_config.wasm_component_model(true);
+
+    if is_async {
+        engine_config.async_support(true);
+        engine_config.consume_fuel(true);
+    }
+
+    Ok(Engine::new(&engine_config)?)
+}
+
+/// Create a linker providing WASI Preview 2 and the host functions of a component module.
+///
+/// # Arguments
+/// * `engine` - The engine of the module
+/// * `namespace` - The interface the component imports host functions from
+/// * `host_fns` - The host functions of the module
+/// * `host_fn_sets` - The host function sets registered alongside the host functions
+/// * `validate_schemas` - Whether host function inputs and outputs are validated
+/// * `is_async` - Whether WASI is linked for asynchronous execution
+///
+/// # Returns
+/// The linker or an error if a host function could not be defined
+pub(crate) fn component_linker(
+    engine: &Engine,
+    namespace: &str,
+    host_fns: &HashMap<String, HostFn>,
+    host_fn_sets: &[HostFnSet],
+    validate_schemas: bool,
+    is_async: bool,
+) -> ModuleResult<Linker<ComponentState>> {
+    let mut linker = Linker::<ComponentState>::new(engine);
+
+    match is_async {
+        true => p2::add_to_linker_async(&mut linker)?,
+        false => p2::add_to_linker_sync(&mut linker)?,
+    }
+
+    for (namespace, group) in host_fns::group_host_fns(namespace, host_fns, host_fn_sets) {
+        let mut host = linker.instance(namespace)?;
+
+        for (name, host_fn) in group {
+            match host_fn.is_raw() {
+                true => host.func_wrap(name, host_fn.clone().into_component_raw_func())?,
+                false => host.func_wrap(name, host_fn.for_linking(validate_schemas).into_component_func())?,
+            }
+        }
+    }
+
+    Ok(linker)
+}
+
+/// Compile a component and resolve its imports against the linker.
+///
+/// # Arguments
+/// * `engine` - The engine to compile the component for
+/// * `linker` - The linker providing the component's imports
+/// * `binary` - The WebAssembly binary of the component
+/// * `content_hash` - The content hash of the binary
+///
+/// # Returns
+/// The pre-instantiated component or an error if it could not be compiled or linked
+pub(crate) fn component_instance_pre(
+    engine: &Engine,
+    linker: &Linker<ComponentState>,
+    binary: &[u8],
+    content_hash: &str,
+) -> ModuleResult<InstancePre<ComponentState>> {
+    let component = artifacts::compile_component(engine, binary, content_hash)
+        .map_err(|e| ModuleError::InstantiationError(format!("failed to compile component {}: {}", content_hash, e)))?;
+
+    linker
+        .instantiate_pre(&component)
+        .map_err(|e| ModuleError::InstantiationError(format!("failed to create instance pre: {}", e)))
+}
+
+/// Create a store for a new instance of a component module, enforcing its resource limits.
+///
+/// # Arguments
+/// * `engine` - The engine of the module
+/// * `environment` - The environment configuration for the module
+/// * `limits` - The resource limits for the module
+///
+/// # Returns
+/// The store for the instance
+pub(crate) fn component_store(engine: &Engine, environment: &ModuleEnv, limits: &ModuleLimits) -> Store<ComponentState> {
+    let mut store = Store::new(
+        engine,
+        ComponentState {
+            wasi: environment
+                .clone()
+                .into(),
+            table: ResourceTable::new(),
+            limits: limits
+                .clone()
+                .into(),
+        }
+    );
+    store.limiter(|s| &mut s.limits);
+
+    store
+}
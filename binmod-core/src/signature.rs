@@ -0,0 +1,260 @@
+use ed25519_dalek::{Signer, Verifier};
+
+use crate::error::{ModuleError, ModuleResult};
+
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey, SIGNATURE_LENGTH};
+
+
+/// Name of the custom section holding an embedded signature.
+pub const SIGNATURE_SECTION: &str = "binmod.signature";
+
+/// Extension appended to a module path to locate its detached signature.
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// Sign a WebAssembly binary, producing a detached signature.
+///
+/// Any embedded signature is excluded from the signed bytes, so the
+/// signature can be verified whether or not the binary is later embedded.
+///
+/// # Arguments
+/// * `binary` - The WebAssembly binary to sign
+/// * `key` - The key to sign the binary with
+///
+/// # Returns
+/// A result containing the signature or an error
+/// if the binary is malformed
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::signature::{self, SigningKey};
+/// # fn example(secret: [u8; 32]) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let key = SigningKey::from_bytes(&secret);
+/// let binary = std::fs::read("my_module.wasm")?;
+///
+/// std::fs::write("my_module.wasm.sig", signature::sign(&binary, &key)?.to_bytes())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn sign(binary: &[u8], key: &SigningKey) -> ModuleResult<Signature> {
+    let (unsigned, _) = split_signature(binary)?;
+    Ok(key.sign(&unsigned))
+}
+
+/// Sign a WebAssembly binary, embedding the signature in a
+/// [`SIGNATURE_SECTION`](crate::signature::SIGNATURE_SECTION) custom section.
+///
+/// An existing embedded signature is replaced.
+///
+/// # Arguments
+/// * `binary` - The WebAssembly binary to sign
+/// * `key` - The key to sign the binary with
+///
+/// # Returns
+/// A result containing the signed binary or an error
+/// if the binary is malformed
+pub fn sign_embedded(binary: &[u8], key: &SigningKey) -> ModuleResult<Vec<u8>> {
+    let (mut unsigned, _) = split_signature(binary)?;
+    let signature = key.sign(&unsigned);
+
+    let mut payload = vec![];
+    write_leb128(&mut payload, SIGNATURE_SECTION.len() as u32);
+    payload.extend_from_slice(SIGNATURE_SECTION.as_bytes());
+    payload.extend_from_slice(&signature.to_bytes());
+
+    unsigned.push(0);
+    write_leb128(&mut unsigned, payload.len() as u32);
+    unsigned.extend(payload);
+
+    Ok(unsigned)
+}
+
+/// Verify the signature of a WebAssembly binary against a set of trusted keys.
+///
+/// # Arguments
+/// * `binary` - The WebAssembly binary to verify
+/// * `signature` - The detached signature, or `None` to use the embedded signature
+/// * `public_keys` - The keys of the approved publishers
+///
+/// # Returns
+/// A result indicating success or a
+/// [`SignatureInvalid`](crate::error::ModuleError::SignatureInvalid) error
+/// if the binary is not signed by any of the keys
+pub fn verify(binary: &[u8], signature: Option<&[u8]>, public_keys: &[VerifyingKey]) -> ModuleResult<()> {
+    let (unsigned, embedded) = split_signature(binary)
+        .map_err(|e| ModuleError::SignatureInvalid(e.to_string()))?;
+
+    let signature = signature
+        .or(embedded.as_deref())
+        .ok_or_else(|| ModuleError::SignatureInvalid("module is not signed".to_string()))?;
+    let signature = Signature::from_slice(signature)
+        .map_err(|_| ModuleError::SignatureInvalid("malformed signature".to_string()))?;
+
+    if public_keys.iter().any(|key| key.verify(&unsigned, &signature).is_ok()) {
+        Ok(())
+    } else {
+        Err(ModuleError::SignatureInvalid("signature does not match any trusted key".to_string()))
+    }
+}
+
+/// Split a binary into its bytes without the signature section and the embedded signature
+fn split_signature(binary: &[u8]) -> ModuleResult<(Vec<u8>, Option<Vec<u8>>)> {
+    let malformed = || ModuleError::InvalidModuleConfig("malformed WebAssembly binary".to_string());
+
+    if binary.len() < 8 || &binary[..4] != b"\0asm" {
+        return Err(malformed());
+    }
+
+    let mut unsigned = binary[..8].to_vec();
+    let mut signature = None;
+    let mut offset = 8;
+
+    while offset < binary.len() {
+        let start = offset;
+        let id = binary[offset];
+        let (size, len) = read_leb128(&binary[offset + 1..]).ok_or_else(malformed)?;
+        let payload = offset + 1 + len..offset + 1 + len + size as usize;
+
+        if payload.end > binary.len() {
+            return Err(malformed());
+        }
+
+        offset = payload.end;
+
+        if id == 0 {
+            let section = &binary[payload.clone()];
+            let (name, name_end) = custom_section_name(section).ok_or_else(malformed)?;
+
+            if name == SIGNATURE_SECTION.as_bytes() {
+                signature = Some(section[name_end..].to_vec());
+                continue;
+            }
+        }
+
+        unsigned.extend_from_slice(&binary[start..payload.end]);
+    }
+
+    Ok((unsigned, signature))
+}
+
+/// Read the name of a custom section from its payload, returning
+/// the name and the offset of the section's data within the payload
+fn custom_section_name(section: &[u8]) -> Option<(&[u8], usize)> {
+    let (name_len, len) = read_leb128(section)?;
+    let name_end = len.checked_add(name_len as usize)?;
+
+    Some((section.get(len..name_end)?, name_end))
+}
+
+/// Read an unsigned LEB128 integer, returning the value and its encoded length
+fn read_leb128(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut value = 0u32;
+
+    for (index, byte) in bytes.iter().take(5).enumerate() {
+        value |= ((byte & 0x7f) as u32) << (index * 7);
+
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+
+    None
+}
+
+/// Write an unsigned LEB128 integer
+fn write_leb128(bytes: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            break;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    #[test]
+    fn verifies_detached_signature() {
+        let signature = sign(EMPTY_MODULE, &key()).unwrap();
+
+        assert!(verify(EMPTY_MODULE, Some(&signature.to_bytes()), &[key().verifying_key()]).is_ok());
+    }
+
+    #[test]
+    fn verifies_embedded_signature() {
+        let signed = sign_embedded(EMPTY_MODULE, &key()).unwrap();
+        let (unsigned, signature) = split_signature(&signed).unwrap();
+
+        assert_eq!(unsigned, EMPTY_MODULE);
+        assert_eq!(signature.map(|signature| signature.len()), Some(SIGNATURE_LENGTH));
+        assert!(verify(&signed, None, &[key().verifying_key()]).is_ok());
+    }
+
+    #[test]
+    fn splits_unsigned_binary() {
+        let mut binary = EMPTY_MODULE.to_vec();
+        binary.extend_from_slice(&[0, 5, 4, b'n', b'a', b'm', b'e']);
+
+        assert_eq!(split_signature(&binary).unwrap(), (binary.clone(), None));
+    }
+
+    #[test]
+    fn rejects_invalid_header() {
+        assert!(split_signature(b"\0asm").is_err());
+        assert!(split_signature(b"not wasm").is_err());
+    }
+
+    #[test]
+    fn replaces_embedded_signature() {
+        let signed = sign_embedded(EMPTY_MODULE, &key()).unwrap();
+
+        assert_eq!(sign_embedded(&signed, &key()).unwrap(), signed);
+    }
+
+    #[test]
+    fn rejects_unknown_key() {
+        let signed = sign_embedded(EMPTY_MODULE, &key()).unwrap();
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key();
+
+        assert!(matches!(verify(&signed, None, &[other]), Err(ModuleError::SignatureInvalid(_))));
+    }
+
+    #[test]
+    fn rejects_unsigned_binary() {
+        assert!(matches!(verify(EMPTY_MODULE, None, &[key().verifying_key()]), Err(ModuleError::SignatureInvalid(_))));
+    }
+
+    #[test]
+    fn rejects_section_name_past_payload() {
+        // A custom section of 2 bytes whose name claims to be 16 bytes long,
+        // so the signature section name only matches past the payload.
+        let mut binary = EMPTY_MODULE.to_vec();
+        binary.extend_from_slice(&[0, 2, SIGNATURE_SECTION.len() as u8, b'b']);
+        binary.extend_from_slice(&SIGNATURE_SECTION.as_bytes()[1..]);
+
+        assert!(split_signature(&binary).is_err());
+        assert!(verify(&binary, None, &[key().verifying_key()]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_section() {
+        let mut binary = EMPTY_MODULE.to_vec();
+        binary.extend_from_slice(&[0, 10, 1]);
+
+        assert!(split_signature(&binary).is_err());
+    }
+}
@@ -1,8 +1,33 @@
-use wasmtime::StoreLimits;
-use wasmtime_wasi::p1::WasiP1Ctx;
+use wasmtime::{GuestProfiler, StoreLimits, component::ResourceTable};
+use wasmtime_wasi::{WasiCtx, WasiCtxView, WasiView, p1::WasiP1Ctx};
+
+use crate::{abi::AbiVersion, codec::Codec, memory::ExchangeBuffer, stream::StreamTable};
 
 
 pub struct ModuleState {
     pub wasi: WasiP1Ctx,
     pub limits: StoreLimits,
+    pub profiler: Option<GuestProfiler>,
+    pub abi_version: AbiVersion,
+    pub codec: Codec,
+    pub streams: StreamTable,
+    pub exchange: Option<ExchangeBuffer>,
+    pub result_exchange: Option<ExchangeBuffer>,
+    pub callback_depth: u32,
+    pub max_callback_depth: u32,
+}
+
+pub struct ComponentState {
+    pub wasi: WasiCtx,
+    pub table: ResourceTable,
+    pub limits: StoreLimits,
+}
+
+impl WasiView for ComponentState {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        WasiCtxView {
+            ctx: &mut self.wasi,
+            table: &mut self.table,
+        }
+    }
 }
@@ -0,0 +1,486 @@
+use std::{any::Any, collections::HashMap, future::Future, io::{Read, Write}};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use wasmtime::{Caller, Linker};
+
+use crate::{
+    state::ModuleState,
+    error::{ModuleError, ModuleResult},
+};
+
+
+/// Handle of a stream opened on a module, passed to the guest
+/// as a regular argument to read or write the stream in chunks.
+///
+/// Guests access streams through functions imported from the `binmod` namespace:
+/// * `stream_read(handle: u32, ptr: u32, len: u32) -> u32` reads up to `len` bytes into
+///   the guest buffer at `ptr` and returns the number of bytes read, `0` at the end of the stream
+/// * `stream_write(handle: u32, ptr: u32, len: u32) -> u32` writes the `len` bytes at `ptr`
+///   and returns the number of bytes written
+/// * `stream_close(handle: u32)` flushes and closes the stream for the guest
+///
+/// The guest owns the buffers, so payloads never have to fit in guest memory at once.
+///
+/// [`AsyncModule`](crate::module::AsyncModule) can also open streams over
+/// [`AsyncRead`](futures::io::AsyncRead) and [`AsyncWrite`](futures::io::AsyncWrite)
+/// types, which are awaited on the executor driving the call instead of blocking it.
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::fs::File;
+/// use binmod::prelude::*;
+/// # fn example(mut module: Module) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let input = module.open_reader(File::open("large_input.csv")?)?;
+/// let output = module.open_writer(Vec::<u8>::new())?;
+///
+/// module.typed_call::<()>("transform", (input, output))?;
+///
+/// let transformed: Vec<u8> = module.take_writer(output)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct StreamHandle(u32);
+
+impl StreamHandle {
+    /// Get the id of the stream passed to the guest.
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A readable stream whose concrete type can be recovered by the host
+trait ReadStream: Read + Send {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<R: Read + Send + 'static> ReadStream for R {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// A writable stream whose concrete type can be recovered by the host
+trait WriteStream: Write + Send {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<W: Write + Send + 'static> WriteStream for W {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// An asynchronously readable stream whose concrete type can be recovered by the host
+trait AsyncReadStream: AsyncRead + Unpin + Send {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<R: AsyncRead + Unpin + Send + 'static> AsyncReadStream for R {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// An asynchronously writable stream whose concrete type can be recovered by the host
+trait AsyncWriteStream: AsyncWrite + Unpin + Send {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<W: AsyncWrite + Unpin + Send + 'static> AsyncWriteStream for W {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+enum Stream {
+    Reader(Box<dyn ReadStream>),
+    Writer(Box<dyn WriteStream>),
+    AsyncReader(Box<dyn AsyncReadStream>),
+    AsyncWriter(Box<dyn AsyncWriteStream>),
+}
+
+struct StreamEntry {
+    stream: Stream,
+    closed: bool,
+}
+
+/// Streams opened on an instance, keyed by the id of their handle.
+#[derive(Default)]
+pub struct StreamTable {
+    next_id: u32,
+    streams: HashMap<u32, StreamEntry>,
+}
+
+impl StreamTable {
+    /// Get the number of open streams.
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Check if no streams are open.
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    fn insert(&mut self, stream: Stream) -> StreamHandle {
+        // Ids start at 1 so guests can use 0 as an invalid handle
+        self.next_id += 1;
+        self.streams.insert(self.next_id, StreamEntry { stream, closed: false });
+
+        StreamHandle(self.next_id)
+    }
+
+    pub(crate) fn open_reader(&mut self, reader: impl Read + Send + 'static) -> StreamHandle {
+        self.insert(Stream::Reader(Box::new(reader)))
+    }
+
+    pub(crate) fn open_writer(&mut self, writer: impl Write + Send + 'static) -> StreamHandle {
+        self.insert(Stream::Writer(Box::new(writer)))
+    }
+
+    pub(crate) fn take_reader<R: Read + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<R> {
+        match self.streams.get(&handle.0).map(|entry| &entry.stream) {
+            Some(Stream::Reader(reader)) if (**reader).as_any().is::<R>() => {},
+            Some(Stream::Reader(_)) => return Err(mismatch::<R>(handle)),
+            Some(_) => return Err(ModuleError::StreamError(format!("stream {} is not a reader", handle.0))),
+            None => return Err(unknown(handle.0)),
+        }
+
+        match self.streams.remove(&handle.0).map(|entry| entry.stream) {
+            Some(Stream::Reader(reader)) => downcast(reader.into_any(), handle),
+            _ => Err(unknown(handle.0)),
+        }
+    }
+
+    pub(crate) fn take_writer<W: Write + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<W> {
+        match self.streams.get(&handle.0).map(|entry| &entry.stream) {
+            Some(Stream::Writer(writer)) if (**writer).as_any().is::<W>() => {},
+            Some(Stream::Writer(_)) => return Err(mismatch::<W>(handle)),
+            Some(_) => return Err(ModuleError::StreamError(format!("stream {} is not a writer", handle.0))),
+            None => return Err(unknown(handle.0)),
+        }
+
+        match self.streams.remove(&handle.0).map(|entry| entry.stream) {
+            Some(Stream::Writer(mut writer)) => {
+                writer.flush()?;
+                downcast(writer.into_any(), handle)
+            },
+            _ => Err(unknown(handle.0)),
+        }
+    }
+
+    pub(crate) fn open_async_reader(&mut self, reader: impl AsyncRead + Unpin + Send + 'static) -> StreamHandle {
+        self.insert(Stream::AsyncReader(Box::new(reader)))
+    }
+
+    pub(crate) fn open_async_writer(&mut self, writer: impl AsyncWrite + Unpin + Send + 'static) -> StreamHandle {
+        self.insert(Stream::AsyncWriter(Box::new(writer)))
+    }
+
+    pub(crate) fn take_async_reader<R: AsyncRead + Unpin + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<R> {
+        match self.streams.get(&handle.0).map(|entry| &entry.stream) {
+            Some(Stream::AsyncReader(reader)) if (**reader).as_any().is::<R>() => {},
+            Some(Stream::AsyncReader(_)) => return Err(mismatch::<R>(handle)),
+            Some(_) => return Err(ModuleError::StreamError(format!("stream {} is not an async reader", handle.0))),
+            None => return Err(unknown(handle.0)),
+        }
+
+        match self.streams.remove(&handle.0).map(|entry| entry.stream) {
+            Some(Stream::AsyncReader(reader)) => downcast(reader.into_any(), handle),
+            _ => Err(unknown(handle.0)),
+        }
+    }
+
+    /// Remove an async writer, returning a future flushing and recovering it.
+    /// 
+    /// The writer is removed before the future is awaited, so the
+    /// table is not borrowed while it is being flushed.
+    pub(crate) fn take_async_writer<W: AsyncWrite + Unpin + Send + 'static>(&mut self, handle: StreamHandle) -> ModuleResult<impl Future<Output = ModuleResult<W>> + use<W>> {
+        match self.streams.get(&handle.0).map(|entry| &entry.stream) {
+            Some(Stream::AsyncWriter(writer)) if (**writer).as_any().is::<W>() => {},
+            Some(Stream::AsyncWriter(_)) => return Err(mismatch::<W>(handle)),
+            Some(_) => return Err(ModuleError::StreamError(format!("stream {} is not an async writer", handle.0))),
+            None => return Err(unknown(handle.0)),
+        }
+
+        let Some(Stream::AsyncWriter(mut writer)) = self.streams.remove(&handle.0).map(|entry| entry.stream) else {
+            return Err(unknown(handle.0));
+        };
+
+        Ok(async move {
+            writer.flush().await?;
+            downcast(writer.into_any(), handle)
+        })
+    }
+
+    pub(crate) fn close(&mut self, handle: StreamHandle) -> bool {
+        self.streams.remove(&handle.0).is_some()
+    }
+
+    /// Get an open stream for the guest
+    fn guest_stream(&mut self, id: u32) -> ModuleResult<&mut Stream> {
+        match self.streams.get_mut(&id) {
+            Some(StreamEntry { closed: true, .. }) => Err(ModuleError::StreamError(format!("stream {} is closed", id))),
+            Some(entry) => Ok(&mut entry.stream),
+            None => Err(unknown(id)),
+        }
+    }
+}
+
+fn unknown(id: u32) -> ModuleError {
+    ModuleError::StreamError(format!("unknown stream {}", id))
+}
+
+fn mismatch<T>(handle: StreamHandle) -> ModuleError {
+    ModuleError::StreamError(format!("stream {} is not a {}", handle.0, std::any::type_name::<T>()))
+}
+
+fn downcast<T: 'static>(stream: Box<dyn Any>, handle: StreamHandle) -> ModuleResult<T> {
+    stream
+        .downcast::<T>()
+        .map(|stream| *stream)
+        .map_err(|_| mismatch::<T>(handle))
+}
+
+/// Get the guest memory and the module state from within a stream function
+fn guest_memory<'a>(caller: &'a mut Caller<'_, ModuleState>, ptr: u32, len: u32) -> ModuleResult<(&'a mut [u8], &'a mut ModuleState)> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| ModuleError::MemoryError("failed to find memory export".to_string()))?;
+    let (data, state) = memory.data_and_store_mut(caller);
+    let buffer = data
+        .get_mut(ptr as usize..ptr as usize + len as usize)
+        .ok_or_else(|| ModuleError::MemoryError(format!("stream buffer {}..{} is out of bounds", ptr, ptr as u64 + len as u64)))?;
+
+    Ok((buffer, state))
+}
+
+fn not_readable(id: u32) -> ModuleError {
+    ModuleError::StreamError(format!("stream {} is not readable", id))
+}
+
+fn not_writable(id: u32) -> ModuleError {
+    ModuleError::StreamError(format!("stream {} is not writable", id))
+}
+
+/// Get a stream the guest is closing
+fn closing_entry(state: &mut ModuleState, id: u32) -> ModuleResult<&mut StreamEntry> {
+    // The stream is kept until the host takes it, so writers
+    // closed by the guest can still be collected after the call.
+    state
+        .streams
+        .streams
+        .get_mut(&id)
+        .ok_or_else(|| unknown(id))
+}
+
+/// Add the stream functions to the `binmod` namespace of a linker
+pub(crate) fn add_to_linker(linker: &mut Linker<ModuleState>) -> ModuleResult<()> {
+    linker.func_wrap(
+        "binmod",
+        "stream_read",
+        |mut caller: Caller<ModuleState>, id: u32, ptr: u32, len: u32| -> anyhow::Result<u32> {
+            let (buffer, state) = guest_memory(&mut caller, ptr, len)?;
+
+            match state.streams.guest_stream(id)? {
+                Stream::Reader(reader) => Ok(reader.read(buffer).map_err(ModuleError::from)? as u32),
+                _ => Err(not_readable(id).into()),
+            }
+        }
+    )?;
+    linker.func_wrap(
+        "binmod",
+        "stream_write",
+        |mut caller: Caller<ModuleState>, id: u32, ptr: u32, len: u32| -> anyhow::Result<u32> {
+            let (buffer, state) = guest_memory(&mut caller, ptr, len)?;
+
+            match state.streams.guest_stream(id)? {
+                Stream::Writer(writer) => Ok(writer.write(buffer).map_err(ModuleError::from)? as u32),
+                _ => Err(not_writable(id).into()),
+            }
+        }
+    )?;
+    linker.func_wrap(
+        "binmod",
+        "stream_close",
+        |mut caller: Caller<ModuleState>, id: u32| -> anyhow::Result<()> {
+            let entry = closing_entry(caller.data_mut(), id)?;
+
+            if let Stream::Writer(writer) = &mut entry.stream {
+                writer.flush().map_err(ModuleError::from)?;
+            }
+            entry.closed = true;
+
+            Ok(())
+        }
+    )?;
+
+    Ok(())
+}
+
+/// Add the stream functions to the `binmod` namespace of the linker of an
+/// [`AsyncModule`](crate::module::AsyncModule), awaiting async streams
+pub(crate) fn add_to_linker_async(linker: &mut Linker<ModuleState>) -> ModuleResult<()> {
+    linker.func_wrap_async(
+        "binmod",
+        "stream_read",
+        |mut caller: Caller<'_, ModuleState>, (id, ptr, len): (u32, u32, u32)| -> Box<dyn Future<Output = anyhow::Result<u32>> + Send + '_> {
+            Box::new(async move {
+                let (buffer, state) = guest_memory(&mut caller, ptr, len)?;
+
+                let read = match state.streams.guest_stream(id)? {
+                    Stream::Reader(reader) => return Ok(reader.read(buffer).map_err(ModuleError::from)? as u32),
+                    // Guest memory can not be borrowed across an await point,
+                    // so async reads go through an intermediate buffer.
+                    Stream::AsyncReader(reader) => {
+                        let mut chunk = vec![0u8; buffer.len()];
+                        let read = reader.read(&mut chunk).await.map_err(ModuleError::from)?;
+
+                        chunk.truncate(read);
+                        chunk
+                    },
+                    _ => return Err(not_readable(id).into()),
+                };
+
+                let (buffer, _) = guest_memory(&mut caller, ptr, read.len() as u32)?;
+                buffer.copy_from_slice(&read);
+
+                Ok(read.len() as u32)
+            })
+        }
+    )?;
+    linker.func_wrap_async(
+        "binmod",
+        "stream_write",
+        |mut caller: Caller<'_, ModuleState>, (id, ptr, len): (u32, u32, u32)| -> Box<dyn Future<Output = anyhow::Result<u32>> + Send + '_> {
+            Box::new(async move {
+                let (buffer, state) = guest_memory(&mut caller, ptr, len)?;
+
+                match state.streams.guest_stream(id)? {
+                    Stream::Writer(writer) => Ok(writer.write(buffer).map_err(ModuleError::from)? as u32),
+                    Stream::AsyncWriter(writer) => {
+                        let chunk = buffer.to_vec();
+
+                        Ok(writer.write(&chunk).await.map_err(ModuleError::from)? as u32)
+                    },
+                    _ => Err(not_writable(id).into()),
+                }
+            })
+        }
+    )?;
+    linker.func_wrap_async(
+        "binmod",
+        "stream_close",
+        |mut caller: Caller<'_, ModuleState>, (id,): (u32,)| -> Box<dyn Future<Output = anyhow::Result<()>> + Send + '_> {
+            Box::new(async move {
+                let entry = closing_entry(caller.data_mut(), id)?;
+
+                match &mut entry.stream {
+                    Stream::Writer(writer) => writer.flush().map_err(ModuleError::from)?,
+                    Stream::AsyncWriter(writer) => writer.flush().await.map_err(ModuleError::from)?,
+                    _ => {},
+                }
+                entry.closed = true;
+
+                Ok(())
+            })
+        }
+    )?;
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn handles_start_at_one() {
+        let mut table = StreamTable::default();
+
+        assert_eq!(table.open_reader(Cursor::new(vec![])).id(), 1);
+        assert_eq!(table.open_writer(Vec::<u8>::new()).id(), 2);
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn takes_stream_of_its_type() {
+        let mut table = StreamTable::default();
+        let reader = table.open_reader(Cursor::new(b"data".to_vec()));
+        let writer = table.open_writer(Vec::<u8>::new());
+
+        assert_eq!(table.take_reader::<Cursor<Vec<u8>>>(reader).unwrap().into_inner(), b"data");
+        assert_eq!(table.take_writer::<Vec<u8>>(writer).unwrap(), Vec::<u8>::new());
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn keeps_stream_on_type_mismatch() {
+        let mut table = StreamTable::default();
+        let handle = table.open_writer(Vec::<u8>::new());
+
+        assert!(matches!(table.take_writer::<Cursor<Vec<u8>>>(handle), Err(ModuleError::StreamError(_))));
+        assert!(matches!(table.take_reader::<Cursor<Vec<u8>>>(handle), Err(ModuleError::StreamError(_))));
+        assert!(table.take_writer::<Vec<u8>>(handle).is_ok());
+    }
+
+    #[test]
+    fn rejects_closed_and_unknown_streams() {
+        let mut table = StreamTable::default();
+        let handle = table.open_reader(Cursor::new(vec![]));
+
+        assert!(table.close(handle));
+        assert!(!table.close(handle));
+        assert!(table.guest_stream(handle.id()).is_err());
+        assert!(table.take_reader::<Cursor<Vec<u8>>>(handle).is_err());
+    }
+
+    #[test]
+    fn rejects_guest_access_to_closed_stream() {
+        let mut table = StreamTable::default();
+        let handle = table.open_writer(Vec::<u8>::new());
+        table.streams.get_mut(&handle.id()).unwrap().closed = true;
+
+        assert!(matches!(table.guest_stream(handle.id()), Err(ModuleError::StreamError(_))));
+        assert!(table.take_writer::<Vec<u8>>(handle).is_ok());
+    }
+
+    #[test]
+    fn flushes_async_writer_when_taken() {
+        let mut table = StreamTable::default();
+        let handle = table.open_async_writer(futures::io::Cursor::new(Vec::<u8>::new()));
+        let writer = futures::executor::block_on(
+            table.take_async_writer::<futures::io::Cursor<Vec<u8>>>(handle).unwrap()
+        );
+
+        assert!(writer.is_ok());
+        assert!(table.is_empty());
+    }
+}
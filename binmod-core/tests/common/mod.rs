@@ -0,0 +1,185 @@
+#![allow(dead_code)]
+
+use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+use binmod_core::{module::{Module, ModuleBuilder}, result::FnResult};
+
+
+/// A guest implementing the binmod ABI with a bump allocator and exchange buffers for inputs and results.
+///
+/// `guest_dealloc` traps on data inside the exchange buffers, since the host must never free it.
+pub const GUEST: &str = r#"
+(module
+    (import "env" "double" (func $double (param i64) (result i64)))
+    (import "env" "upper" (func $upper (param i64) (result i64)))
+    (import "env" "add_one" (func $add_one (param i64) (result i64)))
+
+    (memory (export "memory") 4)
+
+    (global $heap (mut i32) (i32.const 1024))
+    (global $exchange_ptr (mut i32) (i32.const 0))
+    (global $exchange_cap (mut i32) (i32.const 0))
+    (global $exchange_grows (mut i32) (i32.const 0))
+    (global $result_ptr (mut i32) (i32.const 0))
+    (global $result_cap (mut i32) (i32.const 0))
+    (global $result_grows (mut i32) (i32.const 0))
+
+    (data (i32.const 64) "{\"object\":\"data\",\"value\":42}")
+
+    (func $pack (param $ptr i32) (param $len i32) (result i64)
+        (i64.or
+            (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+            (i64.extend_i32_u (local.get $len))))
+
+    (func $alloc (export "guest_alloc") (param $size i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap
+            (i32.and
+                (i32.add (i32.add (local.get $ptr) (local.get $size)) (i32.const 7))
+                (i32.const -8)))
+        (local.get $ptr))
+
+    (func $within (param $ptr i32) (param $start i32) (param $cap i32) (result i32)
+        (i32.and
+            (i32.ge_u (local.get $ptr) (local.get $start))
+            (i32.lt_u (local.get $ptr) (i32.add (local.get $start) (local.get $cap)))))
+
+    (func (export "guest_dealloc") (param $ptr i32) (param $len i32)
+        (if (i32.or
+                (call $within (local.get $ptr) (global.get $exchange_ptr) (global.get $exchange_cap))
+                (call $within (local.get $ptr) (global.get $result_ptr) (global.get $result_cap)))
+            (then unreachable)))
+
+    (func (export "binmod_exchange_buffer") (param $size i32) (result i32)
+        (global.set $exchange_grows (i32.add (global.get $exchange_grows) (i32.const 1)))
+        (global.set $exchange_ptr (call $alloc (local.get $size)))
+        (global.set $exchange_cap (local.get $size))
+        (global.get $exchange_ptr))
+
+    (func (export "binmod_result_buffer") (param $size i32) (result i32)
+        (global.set $result_grows (i32.add (global.get $result_grows) (i32.const 1)))
+        (global.set $result_ptr (call $alloc (local.get $size)))
+        (global.set $result_cap (local.get $size))
+        (global.get $result_ptr))
+
+    ;; Returns its input unchanged
+    (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+        (call $pack (local.get $ptr) (local.get $len)))
+
+    ;; Returns the number of times the exchange buffer was grown as a single digit
+    (func (export "exchange_grows") (param $ptr i32) (param $len i32) (result i64)
+        (i32.store8 (i32.const 16) (i32.add (i32.const 48) (global.get $exchange_grows)))
+        (call $pack (i32.const 16) (i32.const 1)))
+
+    ;; Returns the number of times the result buffer was grown as a single digit
+    (func (export "result_grows") (param $ptr i32) (param $len i32) (result i64)
+        (i32.store8 (i32.const 16) (i32.add (i32.const 48) (global.get $result_grows)))
+        (call $pack (i32.const 16) (i32.const 1)))
+
+    ;; Returns 42, copied out of the data segment since the host frees results
+    (func (export "constant") (param $ptr i32) (param $len i32) (result i64)
+        (local $result i32)
+        (local.set $result (call $alloc (i32.const 28)))
+        (memory.copy (local.get $result) (i32.const 64) (i32.const 28))
+        (call $pack (local.get $result) (i32.const 28)))
+
+    ;; Never returns, to be interrupted by cancellation or fuel
+    (func (export "spin") (param $ptr i32) (param $len i32) (result i64)
+        (loop $spin (br $spin))
+        (unreachable))
+
+    ;; Forwards its input to the `double` host function
+    (func (export "call_double") (param $ptr i32) (param $len i32) (result i64)
+        (call $double (call $pack (local.get $ptr) (local.get $len))))
+
+    ;; Forwards its input to the raw `upper` host function
+    (func (export "call_upper") (param $ptr i32) (param $len i32) (result i64)
+        (call $upper (call $pack (local.get $ptr) (local.get $len))))
+
+    ;; Forwards its input to the `add_one` host function, which calls back into this export
+    (func (export "call_add_one") (param $ptr i32) (param $len i32) (result i64)
+        (call $add_one (call $pack (local.get $ptr) (local.get $len))))
+)
+"#;
+
+/// Create a builder for the guest, with the host functions it imports.
+pub fn guest() -> ModuleBuilder {
+    Module::builder()
+        .from_wat(GUEST)
+        .unwrap()
+        .with_name("guest")
+        .host_fn("double", |value: i64| -> Result<i64, String> {
+            Ok(value * 2)
+        })
+        .host_fn_raw("upper", |input: &[u8]| -> Result<Vec<u8>, String> {
+            Ok(input.to_ascii_uppercase())
+        })
+        // Counts down to zero by calling back into `call_add_one`, adding one per callback
+        .host_fn_with_guest("add_one", |guest, input| {
+            let value: i64 = match input.get_arg(0) {
+                Ok(value) => value,
+                Err(e) => return FnResult::err(&e),
+            };
+
+            if value == 0 {
+                return FnResult::ok(0).unwrap();
+            }
+
+            match guest.typed_call::<i64>("call_add_one", (value - 1,)) {
+                Ok(result) => FnResult::ok(result + 1).unwrap(),
+                Err(e) => FnResult::err(&e),
+            }
+        })
+}
+
+/// Build and instantiate the guest.
+pub fn module() -> Module {
+    guest()
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap()
+}
+
+/// A guest whose `finalize` export runs the given body, with its input in `$ptr` and `$len`.
+///
+/// The guest imports a `finalized` host function, which counts its calls in `finalized`.
+pub fn finalizing_guest(body: &str, finalized: Arc<AtomicUsize>) -> ModuleBuilder {
+    let wat = r#"
+        (module
+            (import "env" "finalized" (func $finalized (param i64) (result i64)))
+
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+
+            (func $pack (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))
+
+            (func (export "guest_alloc") (param $size i32) (result i32)
+                (global.get $heap)
+                (global.set $heap (i32.add (global.get $heap) (local.get $size))))
+
+            (func (export "guest_dealloc") (param i32) (param i32))
+
+            (func (export "finalize") (param $ptr i32) (param $len i32) (result i64)
+                BODY)
+        )
+    "#;
+
+    Module::builder()
+        .from_wat(wat.replace("BODY", body))
+        .unwrap()
+        .with_name("finalizing")
+        .host_fn("finalized", move || -> Result<(), String> {
+            finalized.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })
+}
+
+/// Finalizer body forwarding its input to the `finalized` host function.
+pub const FINALIZE: &str = "(call $finalized (call $pack (local.get $ptr) (local.get $len)))";
+
+/// Finalizer body that never returns.
+pub const SPIN: &str = "(loop $spin (br $spin)) (unreachable)";
@@ -0,0 +1,122 @@
+use std::time::Duration;
+use binmod_core::{
+    component::ComponentModule,
+    error::ModuleError,
+    input::{FnInput, IntoFnInput},
+    module::ModuleBuilder,
+    result::FnResult,
+    schema::InterfaceSchema,
+};
+use futures::executor::block_on;
+use wasmtime::component::Val;
+
+
+/// A component exporting `add`, `add` again from the `example:calculator/ops` interface,
+/// and `call-double`, which forwards its input to the `double` host function.
+const COMPONENT: &str = r#"
+(component
+    (import "example:calculator/host" (instance $host
+        (export "double" (func (param "input" string) (result string)))))
+    (alias export $host "double" (func $double))
+
+    (core module $libc
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (func (export "realloc") (param i32 i32) (param $align i32) (param $size i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (local.get $ptr) (i32.and (i32.add (local.get $size) (i32.const 7)) (i32.const -8))))
+            (local.get $ptr)))
+    (core instance $libc (instantiate $libc))
+
+    (core func $double_lowered (canon lower (func $double)
+        (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+
+    (core module $calculator
+        (import "host" "double" (func $double (param i32 i32 i32)))
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1)))
+        (func (export "call-double") (param $ptr i32) (param $len i32) (result i32)
+            (call $double (local.get $ptr) (local.get $len) (i32.const 16))
+            (i32.const 16)))
+    (core instance $calculator (instantiate $calculator
+        (with "host" (instance (export "double" (func $double_lowered))))))
+
+    (func $add (param "a" u32) (param "b" u32) (result u32)
+        (canon lift (core func $calculator "add")))
+    (func $call_double (param "input" string) (result string)
+        (canon lift (core func $calculator "call-double")
+            (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+
+    (export "add" (func $add))
+    (export "call-double" (func $call_double))
+    (instance $ops (export "add" (func $add)))
+    (export "example:calculator/ops" (instance $ops))
+)
+"#;
+
+/// Create a builder for the component, with the host function it imports.
+fn component() -> ModuleBuilder {
+    ComponentModule::builder()
+        .from_wat(COMPONENT)
+        .unwrap()
+        .with_name("calculator")
+        .with_namespace("example:calculator/host")
+        .host_fn("double", |value: i64| -> Result<i64, String> {
+            Ok(value * 2)
+        })
+}
+
+#[test]
+fn calls_component_exports() {
+    let mut module = component().build_component().unwrap().instantiate().unwrap();
+
+    assert_eq!(module.typed_call::<(u32, u32), (u32,)>("add", (2, 3)).unwrap(), (5,));
+    assert!(matches!(
+        module.call("example:calculator/ops#add", &[Val::U32(4), Val::U32(5)]).unwrap()[..],
+        [Val::U32(9)]
+    ));
+    assert!(matches!(module.typed_call::<(u32, u32), (u32,)>("missing", (2, 3)), Err(ModuleError::FunctionNotFound(_))));
+}
+
+#[test]
+fn calls_host_function_from_component() {
+    let mut module = component().build_component().unwrap().instantiate().unwrap();
+    let input = String::from_utf8((21,).into_fn_input().unwrap().to_bytes().unwrap()).unwrap();
+
+    let (output,) = module.typed_call::<(String,), (String,)>("call-double", (input,)).unwrap();
+
+    assert_eq!(FnResult::from_bytes(output.as_bytes()).unwrap().into_result::<i64>().unwrap(), 42);
+}
+
+#[test]
+fn calls_async_component_exports() {
+    let mut module = block_on(component().build_component_async().unwrap().instantiate()).unwrap();
+
+    assert_eq!(block_on(module.typed_call::<(u32, u32), (u32,)>("example:calculator/ops#add", (2, 3))).unwrap(), (5,));
+}
+
+#[test]
+fn rejects_core_module_settings() {
+    let builders = [
+        component().with_interface_schema(InterfaceSchema::new()),
+        component().with_init_input(FnInput::new()),
+        component().with_shutdown_timeout(Duration::from_secs(1)),
+    ];
+
+    for builder in builders {
+        assert!(matches!(builder.clone().build_component(), Err(ModuleError::InvalidModuleConfig(_))));
+        assert!(matches!(builder.build_component_async(), Err(ModuleError::InvalidModuleConfig(_))));
+    }
+}
+
+#[test]
+fn rejects_core_module_binary() {
+    let result = ComponentModule::builder()
+        .from_wat("(module)")
+        .unwrap()
+        .with_name("core")
+        .build_component();
+
+    assert!(matches!(result, Err(ModuleError::InvalidModuleConfig(_))));
+}
@@ -0,0 +1,91 @@
+use binmod_core::{
+    error::{FnError, ModuleError},
+    input::IntoFnInput,
+    module::{Module, ModuleBuilder},
+};
+use futures::executor::block_on;
+
+
+/// A guest whose `initialize` export keeps its input and returns the given result.
+///
+/// The kept input is returned by the `init_input` export.
+fn guest(result: &str) -> ModuleBuilder {
+    let wat = r#"
+        (module
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+            (global $input_ptr (mut i32) (i32.const 0))
+            (global $input_len (mut i32) (i32.const 0))
+            (data (i32.const 64) "RESULT")
+
+            (func $pack (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))
+
+            (func $alloc (export "guest_alloc") (param $size i32) (result i32)
+                (global.get $heap)
+                (global.set $heap (i32.add (global.get $heap) (local.get $size))))
+
+            (func (export "guest_dealloc") (param i32) (param i32))
+
+            (func (export "initialize") (param $ptr i32) (param $len i32) (result i64)
+                (local $result i32)
+                (global.set $input_ptr (local.get $ptr))
+                (global.set $input_len (local.get $len))
+                (local.set $result (call $alloc (i32.const LENGTH)))
+                (memory.copy (local.get $result) (i32.const 64) (i32.const LENGTH))
+                (call $pack (local.get $result) (i32.const LENGTH)))
+
+            (func (export "init_input") (param i32) (param i32) (result i64)
+                (call $pack (global.get $input_ptr) (global.get $input_len)))
+        )
+    "#;
+
+    Module::builder()
+        .from_wat(
+            wat
+                .replace("RESULT", &result.replace('"', "\\\""))
+                .replace("LENGTH", &result.len().to_string())
+        )
+        .unwrap()
+        .with_name("init")
+}
+
+const SUCCESS: &str = r#"{"object":"data","value":null}"#;
+const FAILURE: &str = r#"{"object":"error","type":"ConfigError","message":"missing endpoint"}"#;
+
+#[test]
+fn passes_init_args_to_initialize() {
+    let args = ("https://api.example.com", 30);
+    let mut module = guest(SUCCESS)
+        .with_init_args(args)
+        .unwrap()
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    let expected = args.into_fn_input().unwrap().to_bytes().unwrap();
+    assert_eq!(module.call_raw("init_input", b"").unwrap(), expected);
+}
+
+#[test]
+fn reports_initialization_failure() {
+    let result = guest(FAILURE)
+        .build()
+        .unwrap()
+        .instantiate();
+
+    assert!(matches!(
+        result,
+        Err(ModuleError::InitializationFailed(error)) if error == FnError::new("ConfigError", "missing endpoint")
+    ));
+}
+
+#[test]
+fn reports_async_initialization_failure() {
+    let result = block_on(guest(FAILURE).build_async().unwrap().instantiate());
+
+    assert!(matches!(result, Err(ModuleError::InitializationFailed(_))));
+}
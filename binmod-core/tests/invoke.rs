@@ -0,0 +1,97 @@
+mod common;
+
+use std::collections::HashMap;
+use binmod_core::{error::ModuleError, module::{AsyncModule, Module}, result::FnResult};
+use futures::executor::block_on;
+use serde_json::{Value, json};
+
+
+/// Build the guest with a `double` host function returning the input it
+/// received, so `call_double` returns the input of the call.
+fn module() -> Module {
+    common::guest()
+        .host_fn_with_guest("double", |_, input| FnResult::ok(input).unwrap())
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap()
+}
+
+/// Build the guest as an async module, with the same `double` host function.
+fn async_module() -> AsyncModule {
+    let builder = common::guest()
+        .host_fn_with_guest_async("double", |_, input| Box::pin(async move { FnResult::ok(input).unwrap() }));
+
+    block_on(builder.build_async().unwrap().instantiate()).unwrap()
+}
+
+#[test]
+fn calls_with_json_arguments() {
+    let mut module = module();
+
+    assert_eq!(module.call_json("call_double", json!([1, "two"])).unwrap(), json!({ "args": [1, "two"] }));
+    assert_eq!(module.call_json("call_double", json!({ "key": true })).unwrap(), json!({ "kwargs": { "key": true } }));
+    assert_eq!(module.call_json("call_double", Value::Null).unwrap(), json!({}));
+    assert_eq!(module.call_json("constant", json!([])).unwrap(), json!(42));
+}
+
+#[test]
+fn rejects_scalar_json_arguments() {
+    let mut module = module();
+
+    for args in [json!(1), json!("one"), json!(true)] {
+        assert!(matches!(module.call_json("call_double", args), Err(ModuleError::FunctionError(_))));
+    }
+}
+
+#[test]
+fn calls_async_with_json_arguments() {
+    let mut module = async_module();
+
+    assert_eq!(block_on(module.call_json("call_double", json!([1]))).unwrap(), json!({ "args": [1] }));
+    assert!(matches!(block_on(module.call_json("call_double", json!(1))), Err(ModuleError::FunctionError(_))));
+}
+
+#[test]
+fn invokes_with_mixed_arguments() {
+    let mut module = module();
+
+    let input: Value = module
+        .invoke("call_double")
+        .arg(1)
+        .kwarg("key", "value")
+        .arg("two")
+        .call()
+        .unwrap();
+
+    assert_eq!(input, json!({ "args": [1, "two"], "kwargs": { "key": "value" } }));
+}
+
+#[test]
+fn reports_invalid_invocation_arguments_on_call() {
+    let mut module = module();
+
+    // Maps with non-string keys can't be serialized to JSON
+    let result = module
+        .invoke("call_double")
+        .arg(HashMap::from([((1, 2), 3)]))
+        .kwarg("key", "value")
+        .call::<Value>();
+
+    assert!(matches!(result, Err(ModuleError::FunctionError(_))));
+}
+
+#[test]
+fn invokes_async_with_mixed_arguments() {
+    let mut module = async_module();
+
+    let input: Value = block_on(
+        module
+            .invoke("call_double")
+            .arg(1)
+            .kwarg("key", "value")
+            .call()
+    ).unwrap();
+
+    assert_eq!(input, json!({ "args": [1], "kwargs": { "key": "value" } }));
+}
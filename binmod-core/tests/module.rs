@@ -0,0 +1,453 @@
+mod common;
+
+use std::{sync::{Arc, atomic::{AtomicUsize, Ordering}}, time::{Duration, Instant}};
+use binmod_core::{config::ModuleConfig, digest, error::ModuleError, input::FnInput, module::Module, result::FnResult};
+use common::{module, finalizing_guest, FINALIZE, SPIN};
+use futures::{executor::block_on, future};
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+
+#[test]
+fn calls_guest_function() {
+    let mut module = module();
+
+    assert_eq!(module.typed_call::<i64>("constant", ()).unwrap(), 42);
+    assert!(matches!(module.call("missing", FnInput::new()), Err(ModuleError::FunctionNotFound(_))));
+}
+
+#[test]
+fn calls_host_function() {
+    let mut module = module();
+
+    assert_eq!(module.typed_call::<i64>("call_double", (21,)).unwrap(), 42);
+}
+
+#[test]
+fn reports_wat_errors_with_line_and_column() {
+    let error = Module::builder()
+        .from_wat("(module\n  (func\n    i32.const))")
+        .err()
+        .unwrap();
+
+    assert!(matches!(&error, ModuleError::InvalidModuleConfig(message) if message.contains("<anon>:3:")), "{}", error);
+}
+
+#[test]
+fn exchanges_raw_bytes() {
+    let mut module = module();
+
+    assert_eq!(module.call_raw("echo", b"hello").unwrap(), b"hello");
+    assert_eq!(module.call_raw("echo", &[0, 159, 255]).unwrap(), [0, 159, 255]);
+}
+
+#[test]
+fn passes_raw_bytes_to_host_function() {
+    let mut module = module();
+
+    assert_eq!(module.call_raw("call_upper", b"hello").unwrap(), b"HELLO");
+}
+
+#[test]
+fn reuses_exchange_buffer() {
+    let mut module = module();
+
+    module.call_raw("echo", b"hello").unwrap();
+    module.call_raw("echo", b"world").unwrap();
+    assert_eq!(module.call_raw("exchange_grows", b"").unwrap(), b"1");
+
+    // Inputs exceeding the buffer grow it once, to at least their size
+    let large = vec![7; 10_000];
+    assert_eq!(module.call_raw("echo", &large).unwrap(), large);
+    assert_eq!(module.call_raw("exchange_grows", b"").unwrap(), b"2");
+    assert_eq!(module.call_raw("echo", b"hello").unwrap(), b"hello");
+    assert_eq!(module.call_raw("exchange_grows", b"").unwrap(), b"2");
+}
+
+#[test]
+fn writes_host_results_to_result_buffer() {
+    let mut module = module();
+
+    // The input of each call stays in the exchange buffer while the host result is written
+    assert_eq!(module.typed_call::<i64>("call_double", (21,)).unwrap(), 42);
+    assert_eq!(module.typed_call::<i64>("call_double", (30,)).unwrap(), 60);
+    assert_eq!(module.call_raw("result_grows", b"").unwrap(), b"1");
+
+    // Results exceeding the buffer grow it, while inputs of the same size keep fitting the exchange buffer
+    assert_eq!(module.typed_call::<i64>("call_double", (50,)).unwrap(), 100);
+    assert_eq!(module.typed_call::<i64>("call_double", (99,)).unwrap(), 198);
+    assert_eq!(module.call_raw("result_grows", b"").unwrap(), b"2");
+    assert_eq!(module.call_raw("exchange_grows", b"").unwrap(), b"1");
+}
+
+#[test]
+fn calls_cached_function() {
+    let mut module = module();
+    let double = module.func::<(i64,), i64>("call_double").unwrap();
+
+    assert_eq!(double.name(), "call_double");
+    assert_eq!(double.call(&mut module, (21,)).unwrap(), 42);
+    assert_eq!(double.call(&mut module, (50,)).unwrap(), 100);
+    assert!(matches!(module.func::<(i64,), i64>("missing"), Err(ModuleError::FunctionNotFound(_))));
+}
+
+#[test]
+fn rejects_cached_function_of_other_instance() {
+    let mut module = module();
+    let double = module.func::<(i64,), i64>("call_double").unwrap();
+
+    let mut other = module.clone().instantiate().unwrap();
+    assert!(matches!(double.call(&mut other, (21,)), Err(ModuleError::RuntimeError(_))));
+
+    module.shutdown().unwrap();
+    let mut module = module.instantiate().unwrap();
+    assert!(matches!(double.call(&mut module, (21,)), Err(ModuleError::RuntimeError(_))));
+}
+
+#[test]
+fn calls_cached_async_function() {
+    block_on(async {
+        let mut module = common::guest().build_async().unwrap().instantiate().await.unwrap();
+        let double = module.func::<(i64,), i64>("call_double").unwrap();
+
+        assert_eq!(double.call(&mut module, (21,)).await.unwrap(), 42);
+        assert!(!module.needs_reset());
+
+        let mut other = module.clone().instantiate().await.unwrap();
+        assert!(matches!(double.call(&mut other, (21,)).await, Err(ModuleError::RuntimeError(_))));
+    });
+}
+
+#[test]
+fn calls_back_into_guest() {
+    let mut module = module();
+
+    assert_eq!(module.typed_call::<i64>("call_add_one", (5,)).unwrap(), 5);
+    assert_eq!(module.call_raw("exchange_grows", b"").unwrap(), b"1");
+}
+
+#[test]
+fn limits_callback_depth() {
+    let mut module = common::guest()
+        .with_config(ModuleConfig::default().with_max_callback_depth(2))
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert_eq!(module.typed_call::<i64>("call_add_one", (2,)).unwrap(), 2);
+
+    let error = module.typed_call::<i64>("call_add_one", (3,)).unwrap_err();
+    assert!(error.to_string().contains(&ModuleError::CallbackDepthExceeded(2).to_string()), "{error}");
+
+    // The depth is restored once the callbacks return
+    assert_eq!(module.typed_call::<i64>("call_add_one", (2,)).unwrap(), 2);
+}
+
+#[test]
+fn records_content_hash_in_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    let hash = metrics::with_local_recorder(&recorder, || module().content_hash().to_string());
+
+    let counters: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let labels: Vec<_> = key.key().labels().map(|label| (label.key().to_string(), label.value().to_string())).collect();
+            (key.key().name().to_string(), labels, value)
+        })
+        .collect();
+
+    let labels = vec![("module".to_string(), "guest".to_string()), ("content_hash".to_string(), hash)];
+    for name in [digest::MODULES_LOADED_METRIC, digest::INSTANCES_CREATED_METRIC] {
+        assert!(counters.contains(&(name.to_string(), labels.clone(), DebugValue::Counter(1))), "{name}: {counters:?}");
+    }
+}
+
+#[test]
+fn pins_content_hash() {
+    let hash = common::guest().build().unwrap().content_hash().to_string();
+
+    assert!(common::guest().expect_sha256(hash.to_uppercase()).build().is_ok());
+    assert!(common::guest().expect_sha256(format!("  {}\n", hash)).build().is_ok());
+    assert!(matches!(
+        common::guest().expect_sha256("0".repeat(64)).build(),
+        Err(ModuleError::ContentHashMismatch { expected, actual }) if expected == "0".repeat(64) && actual == hash
+    ));
+}
+
+#[test]
+fn profiles_call() {
+    let mut module = common::guest()
+        .with_config(ModuleConfig::default().with_epoch_interruption(true))
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+    module.set_epoch_deadline(u64::MAX).unwrap();
+
+    let mut profile = Vec::new();
+    let result = module
+        .profile_call("constant", FnInput::new(), Duration::from_millis(1), &mut profile)
+        .unwrap();
+
+    assert_eq!(result.into_result::<i64>().unwrap(), 42);
+    assert!(!module.is_profiling());
+    assert!(serde_json::from_slice::<serde_json::Value>(&profile).is_ok());
+}
+
+#[test]
+fn rejects_zero_profiling_interval() {
+    let mut module = common::guest()
+        .with_config(ModuleConfig::default().with_epoch_interruption(true))
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert!(matches!(module.start_profiling(Duration::ZERO), Err(ModuleError::ProfilingError(_))));
+    assert!(!module.is_profiling());
+}
+
+#[test]
+fn frees_input_of_failed_call() {
+    let mut module = Module::builder()
+        .from_wat(r#"
+            (module
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 1024))
+                (global $deallocs (mut i32) (i32.const 0))
+                (func (export "binmod_abi_version") (result i32) (i32.const 2))
+                (func (export "guest_alloc") (param $size i32) (result i32)
+                    (global.get $heap)
+                    (global.set $heap (i32.add (global.get $heap) (local.get $size))))
+                (func (export "guest_dealloc") (param i32) (param i32)
+                    (global.set $deallocs (i32.add (global.get $deallocs) (i32.const 1))))
+                (func (export "fail") (param i32) (param i32) (result i64) unreachable)
+                (func (export "deallocs") (param i32) (param i32) (result i64)
+                    (i32.store8 (i32.const 16) (i32.add (i32.const 48) (global.get $deallocs)))
+                    (i64.const 0x10_0000_0001))
+            )
+        "#)
+        .unwrap()
+        .with_name("failing")
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert!(module.call_raw("fail", b"input").is_err());
+    assert_eq!(module.call_raw("deallocs", b"input").unwrap(), b"1");
+}
+
+#[test]
+fn rejects_guest_without_guest_alloc() {
+    // Binaries are checked when the module is built, so `host_alloc` never runs without `guest_alloc`
+    let result = Module::builder()
+        .from_wat(r#"
+            (module
+                (import "binmod" "host_alloc" (func $host_alloc (param i32) (result i32)))
+                (memory (export "memory") 1)
+                (func (export "_initialize")
+                    (drop (call $host_alloc (i32.const 16))))
+            )
+        "#)
+        .unwrap()
+        .with_name("allocless")
+        .build();
+
+    assert!(matches!(result, Err(ModuleError::AbiError(message)) if message.contains("missing export `guest_alloc`")));
+}
+
+#[test]
+fn traps_when_guest_alloc_fails() {
+    let mut module = Module::builder()
+        .from_wat(r#"
+            (module
+                (import "binmod" "host_alloc" (func $host_alloc (param i32) (result i32)))
+                (import "env" "large" (func $large (param i64) (result i64)))
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 1024))
+                ;; Fails allocations of more than 1000 bytes
+                (func (export "guest_alloc") (param $size i32) (result i32)
+                    (if (i32.gt_u (local.get $size) (i32.const 1000)) (then unreachable))
+                    (global.get $heap)
+                    (global.set $heap (i32.add (global.get $heap) (local.get $size))))
+                (func (export "guest_dealloc") (param i32) (param i32))
+                (func (export "alloc_large") (param i32) (param i32) (result i64)
+                    (drop (call $host_alloc (i32.const 2000)))
+                    (i64.const 0))
+                (func (export "call_large") (param $ptr i32) (param $len i32) (result i64)
+                    (call $large (i64.const 0)))
+            )
+        "#)
+        .unwrap()
+        .with_name("failing_alloc")
+        .host_fn_raw("large", |_: &[u8]| -> Result<Vec<u8>, String> {
+            Ok(vec![0; 2000])
+        })
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert!(matches!(
+        module.call_raw("alloc_large", b""),
+        Err(ModuleError::MemoryError(message)) if message.contains("Guest alloc failed")
+    ));
+    assert!(matches!(
+        module.call_raw("call_large", b""),
+        Err(ModuleError::MemoryError(message)) if message.contains("Guest alloc failed")
+    ));
+}
+
+#[test]
+fn runs_finalizer_on_shutdown() {
+    let finalized = Arc::new(AtomicUsize::new(0));
+    let mut module = finalizing_guest(FINALIZE, finalized.clone())
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert_eq!(module.abi_report().unwrap().finalizer(), Some("finalize"));
+    module.shutdown().unwrap();
+    assert_eq!(finalized.load(Ordering::SeqCst), 1);
+    assert!(!module.is_instantiated());
+
+    // Shutting down a module that is not instantiated has no effect
+    module.shutdown().unwrap();
+    assert_eq!(finalized.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn shuts_down_module_without_finalizer() {
+    let mut module = module();
+
+    assert_eq!(module.abi_report().unwrap().finalizer(), None);
+    module.shutdown().unwrap();
+    assert!(!module.is_instantiated());
+}
+
+#[test]
+fn skips_shutdown_export_without_binmod_signature() {
+    let mut module = Module::builder()
+        .from_wat(r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "guest_alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "guest_dealloc") (param i32) (param i32))
+                (func (export "shutdown") unreachable)
+            )
+        "#)
+        .unwrap()
+        .with_name("plain_shutdown")
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert_eq!(module.abi_report().unwrap().finalizer(), None);
+    module.shutdown().unwrap();
+}
+
+#[test]
+fn runs_bounded_finalizer_on_drop() {
+    let finalized = Arc::new(AtomicUsize::new(0));
+
+    drop(
+        finalizing_guest(FINALIZE, finalized.clone())
+            .with_config(ModuleConfig::default().with_epoch_interruption(true))
+            .build()
+            .unwrap()
+            .instantiate()
+            .unwrap()
+    );
+    assert_eq!(finalized.load(Ordering::SeqCst), 1);
+
+    // Without a bound, the finalizer could hang the dropping thread
+    drop(finalizing_guest(FINALIZE, finalized.clone()).build().unwrap().instantiate().unwrap());
+    assert_eq!(finalized.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn times_out_slow_finalizer() {
+    let mut module = finalizing_guest(SPIN, Arc::default())
+        .with_config(ModuleConfig::default().with_epoch_interruption(true))
+        .with_shutdown_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    let start = Instant::now();
+    assert!(module.shutdown().is_err());
+    assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+    assert!(!module.is_instantiated());
+}
+
+#[test]
+fn rejects_shutdown_timeout_it_can_not_enforce() {
+    let unenforced = finalizing_guest(FINALIZE, Arc::default())
+        .with_shutdown_timeout(Duration::from_secs(1))
+        .build();
+    let zero = finalizing_guest(FINALIZE, Arc::default())
+        .with_config(ModuleConfig::default().with_epoch_interruption(true))
+        .with_shutdown_timeout(Duration::ZERO)
+        .build();
+
+    assert!(matches!(unenforced, Err(ModuleError::InvalidModuleConfig(_))));
+    assert!(matches!(zero, Err(ModuleError::InvalidModuleConfig(_))));
+}
+
+#[test]
+fn runs_async_finalizer_on_shutdown() {
+    let finalized = Arc::new(AtomicUsize::new(0));
+
+    block_on(async {
+        let mut module = finalizing_guest(FINALIZE, finalized.clone())
+            .build_async()
+            .unwrap()
+            .instantiate()
+            .await
+            .unwrap();
+
+        module.shutdown().await.unwrap();
+        assert_eq!(finalized.load(Ordering::SeqCst), 1);
+        assert!(!module.is_instantiated());
+    });
+}
+
+#[test]
+fn times_out_slow_async_finalizer() {
+    block_on(async {
+        let mut spinning = finalizing_guest(SPIN, Arc::default())
+            .with_fuel_yield_interval(1000)
+            .with_shutdown_timeout(Duration::from_millis(50))
+            .build_async()
+            .unwrap()
+            .instantiate()
+            .await
+            .unwrap();
+
+        assert!(matches!(spinning.shutdown().await, Err(ModuleError::Cancelled)));
+        assert!(!spinning.is_instantiated());
+
+        // A finalizer awaiting a host function never yields on fuel,
+        // so only the timer can wake the executor to cancel it
+        let mut awaiting = finalizing_guest(FINALIZE, Arc::default())
+            .host_fn_with_guest_async("finalized", |_, _| Box::pin(future::pending::<FnResult>()))
+            .with_shutdown_timeout(Duration::from_millis(50))
+            .build_async()
+            .unwrap()
+            .instantiate()
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        assert!(matches!(awaiting.shutdown().await, Err(ModuleError::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+    });
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+use binmod_core::{
+    config::ModuleEnv,
+    module::Module,
+    package::{Package, PackageEnv, PackageManifest},
+};
+use tempfile::TempDir;
+
+
+/// Save the guest as a package passing an argument, with a single asset.
+fn save_package(dir: &TempDir) -> PathBuf {
+    let path = dir.path().join("guest.binmod");
+
+    Package::new(
+        PackageManifest::new("packaged").with_environment(PackageEnv {
+            args: vec!["manifest".into()],
+            inherit_env: true,
+            ..Default::default()
+        }),
+        wat::parse_str(common::GUEST).unwrap(),
+    )
+        .with_asset("constants.json", b"{\"pi\":3.14}".to_vec())
+        .unwrap()
+        .save(&path)
+        .unwrap();
+
+    path
+}
+
+/// Read the asset mounted at the default mount path of a module
+fn read_asset(environment: &ModuleEnv) -> Vec<u8> {
+    let mounts = environment.readonly_mount.as_ref().unwrap();
+    fs::read(mounts["/assets"].join("constants.json")).unwrap()
+}
+
+#[test]
+fn mounts_package_assets() {
+    let dir = TempDir::new().unwrap();
+    let mut module = common::guest()
+        .from_package(save_package(&dir))
+        .unwrap()
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert_eq!(module.name(), "guest");
+    assert_eq!(read_asset(module.environment()), b"{\"pi\":3.14}");
+    assert_eq!(module.typed_call::<i64>("call_double", (21,)).unwrap(), 42);
+}
+
+#[test]
+fn ignores_untrusted_manifest_environment() {
+    let dir = TempDir::new().unwrap();
+    let module = Module::builder()
+        .from_package(save_package(&dir))
+        .unwrap()
+        .build()
+        .unwrap();
+
+    assert_eq!(module.name(), "packaged");
+    assert!(module.environment().args.is_none());
+    assert!(module.environment().env.is_none());
+}
+
+#[test]
+fn applies_trusted_manifest_environment() {
+    let dir = TempDir::new().unwrap();
+    let module = Module::builder()
+        .from_package(save_package(&dir))
+        .unwrap()
+        .trust_package_manifest()
+        .build()
+        .unwrap();
+
+    assert_eq!(module.environment().args, Some(vec!["manifest".to_string()]));
+    assert!(module.environment().env.is_some());
+    assert_eq!(read_asset(module.environment()), b"{\"pi\":3.14}");
+}
+
+#[test]
+fn prefers_builder_environment_over_trusted_manifest() {
+    let dir = TempDir::new().unwrap();
+    let module = Module::builder()
+        .with_environment(ModuleEnv::new().arg("builder"))
+        .from_package(save_package(&dir))
+        .unwrap()
+        .trust_package_manifest()
+        .build()
+        .unwrap();
+
+    assert_eq!(module.environment().args, Some(vec!["builder".to_string()]));
+    assert!(module.environment().env.is_none());
+    assert_eq!(read_asset(module.environment()), b"{\"pi\":3.14}");
+}
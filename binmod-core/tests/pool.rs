@@ -0,0 +1,179 @@
+mod common;
+
+use std::{sync::{Arc, atomic::{AtomicUsize, Ordering}}, thread, time::Duration};
+use futures::{FutureExt, executor::block_on};
+use binmod_core::{cancel::CancellationToken, error::ModuleError, input::FnInput, pool::{AsyncModulePool, ModulePool}};
+use common::{finalizing_guest, FINALIZE};
+
+
+fn async_pool(count: usize) -> AsyncModulePool {
+    block_on(
+        AsyncModulePool::builder()
+            .with_builder(common::guest().with_fuel_yield_interval(1000))
+            .with_count(count)
+            .build()
+    ).unwrap()
+}
+
+#[test]
+fn resets_module_of_lease_dropped_during_call() {
+    let pool = async_pool(1);
+
+    block_on(async {
+        let mut lease = pool.lease().await;
+        assert!(lease.typed_call::<()>("spin", ()).now_or_never().is_none());
+        assert!(lease.needs_reset());
+        drop(lease);
+
+        assert_eq!(pool.size(), 1);
+
+        let mut lease = pool.lease().await;
+        assert!(!lease.needs_reset());
+        assert_eq!(lease.typed_call::<i64>("constant", ()).await.unwrap(), 42);
+    });
+}
+
+#[test]
+fn resets_released_module() {
+    let pool = async_pool(1);
+
+    block_on(async {
+        let mut lease = pool.lease().await;
+        assert!(lease.typed_call::<()>("spin", ()).now_or_never().is_none());
+        lease.release().await;
+
+        let result = pool.scoped(|module| {
+            let needs_reset = module.needs_reset();
+            async move { needs_reset }
+        }).await;
+        assert!(!result);
+    });
+}
+
+#[test]
+fn cancels_call_and_resets_module() {
+    let pool = async_pool(1);
+
+    block_on(async {
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            handle.cancel();
+        });
+
+        let mut lease = pool.lease().await;
+        let result = lease.call_cancellable("spin", FnInput::new(), &token).await;
+        canceller.join().unwrap();
+
+        assert!(matches!(result, Err(ModuleError::Cancelled)));
+        assert!(lease.needs_reset());
+        lease.release().await;
+
+        let mut lease = pool.lease().await;
+        assert!(!lease.needs_reset());
+        assert_eq!(lease.typed_call::<i64>("constant", ()).await.unwrap(), 42);
+    });
+}
+
+#[test]
+fn skips_call_with_cancelled_token() {
+    let pool = async_pool(1);
+
+    block_on(async {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let mut lease = pool.lease().await;
+        let result = lease.typed_call_cancellable::<i64>("constant", (), &token).await;
+
+        assert!(matches!(result, Err(ModuleError::Cancelled)));
+        assert!(!lease.needs_reset());
+    });
+}
+
+#[test]
+fn resets_returned_module_with_interrupted_call() {
+    let pool = async_pool(2);
+
+    block_on(async {
+        let token = CancellationToken::new();
+        let mut modules = vec![];
+
+        for _ in 0..2 {
+            let mut module = pool.lease().await.take();
+            let call = module.call_cancellable("spin", FnInput::new(), &token);
+            assert!(call.now_or_never().is_none());
+            assert!(module.needs_reset());
+            modules.push(module);
+        }
+
+        pool.return_module(modules.pop().unwrap()).await;
+        pool.try_return_module(modules.pop().unwrap()).await.unwrap();
+        assert_eq!(pool.size(), 2);
+
+        for _ in 0..2 {
+            let lease = pool.lease().await;
+            assert!(!lease.needs_reset());
+            lease.take();
+        }
+    });
+}
+
+#[test]
+fn shrinks_idle_modules() {
+    let pool = async_pool(3);
+
+    block_on(async {
+        let lease = pool.lease().await;
+
+        assert_eq!(pool.shrink(1).await.unwrap(), 1);
+        assert_eq!(pool.drain().await.unwrap(), 1);
+        assert_eq!(pool.size(), 1);
+
+        drop(lease);
+        assert_eq!(pool.drain().await.unwrap(), 1);
+        assert_eq!(pool.size(), 0);
+    });
+}
+
+#[test]
+fn finalizes_removed_modules() {
+    let finalized = Arc::new(AtomicUsize::new(0));
+    let pool = ModulePool::builder()
+        .with_builder(finalizing_guest(FINALIZE, finalized.clone()))
+        .with_count(3)
+        .build()
+        .unwrap();
+
+    let lease = pool.lease();
+    assert_eq!(pool.shrink(1).unwrap(), 1);
+    assert_eq!(finalized.load(Ordering::SeqCst), 1);
+    assert_eq!(pool.drain().unwrap(), 1);
+    assert_eq!(finalized.load(Ordering::SeqCst), 2);
+
+    // Leased modules are only finalized once they are back in the pool
+    drop(lease);
+    assert_eq!(finalized.load(Ordering::SeqCst), 2);
+    assert_eq!(pool.drain().unwrap(), 1);
+    assert_eq!(finalized.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn finalizes_removed_async_modules() {
+    let finalized = Arc::new(AtomicUsize::new(0));
+
+    block_on(async {
+        let pool = AsyncModulePool::builder()
+            .with_builder(finalizing_guest(FINALIZE, finalized.clone()))
+            .with_count(2)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(pool.shrink(1).await.unwrap(), 1);
+        assert_eq!(finalized.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.drain().await.unwrap(), 1);
+        assert_eq!(finalized.load(Ordering::SeqCst), 2);
+    });
+}
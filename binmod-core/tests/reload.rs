@@ -0,0 +1,107 @@
+mod common;
+
+use std::{fs, path::PathBuf, sync::mpsc, time::Duration};
+use binmod_core::{
+    module::Module,
+    reload::{ReloadEvent, ReloadablePool, ReloadablePoolBuilder},
+};
+use tempfile::TempDir;
+
+
+/// The guest with a larger memory, so its binary and content hash differ
+fn updated_guest() -> String {
+    common::GUEST.replace(r#"(memory (export "memory") 4)"#, r#"(memory (export "memory") 16)"#)
+}
+
+/// Write the guest to a file in the directory
+fn write_guest(dir: &TempDir) -> PathBuf {
+    let path = dir.path().join("guest.wat");
+    fs::write(&path, common::GUEST).unwrap();
+    path
+}
+
+/// Create a pool builder for the guest at the path
+fn pool_builder(path: &PathBuf) -> ReloadablePoolBuilder {
+    ReloadablePool::builder().with_builder(common::guest().from_file(path).unwrap())
+}
+
+#[test]
+fn reloads_on_change() {
+    let dir = TempDir::new().unwrap();
+    let path = write_guest(&dir);
+    let (sender, receiver) = mpsc::channel();
+
+    let pool = pool_builder(&path)
+        .with_poll_interval(Duration::from_millis(10))
+        .on_reload(move |event| {
+            let _ = sender.send(matches!(event, ReloadEvent::Reloaded { .. }));
+        })
+        .build()
+        .unwrap();
+    let previous = pool.content_hash();
+
+    fs::write(&path, updated_guest()).unwrap();
+
+    assert!(receiver.recv_timeout(Duration::from_secs(10)).unwrap());
+    assert_ne!(pool.content_hash(), previous);
+    assert_eq!(pool.scoped(|module| module.typed_call::<i64>("call_double", (21,))).unwrap(), 42);
+}
+
+#[test]
+fn keeps_previous_version_on_failed_reload() {
+    let dir = TempDir::new().unwrap();
+    let path = write_guest(&dir);
+    let (sender, receiver) = mpsc::channel();
+
+    let pool = pool_builder(&path)
+        .with_watch(false)
+        .on_reload(move |event| {
+            let _ = sender.send(matches!(event, ReloadEvent::Failed { .. }));
+        })
+        .build()
+        .unwrap();
+    let previous = pool.content_hash();
+
+    fs::write(&path, "(module").unwrap();
+
+    assert!(pool.reload().is_err());
+    assert!(receiver.try_recv().unwrap());
+    assert_eq!(pool.content_hash(), previous);
+    assert_eq!(pool.scoped(|module| module.typed_call::<i64>("call_double", (21,))).unwrap(), 42);
+}
+
+#[test]
+fn swaps_once_leases_are_returned() {
+    let dir = TempDir::new().unwrap();
+    let path = write_guest(&dir);
+
+    let pool = pool_builder(&path)
+        .with_watch(false)
+        .with_count(2)
+        .build()
+        .unwrap();
+    let previous = pool.content_hash();
+    let expected = Module::builder()
+        .from_wat(updated_guest())
+        .unwrap()
+        .with_name("updated")
+        .build()
+        .unwrap()
+        .content_hash()
+        .to_string();
+
+    let lease = pool.lease();
+    fs::write(&path, updated_guest()).unwrap();
+    pool.reload().unwrap();
+
+    // The new version waits for the lease, and no further leases are handed out meanwhile
+    assert_eq!(pool.content_hash(), previous);
+    assert_eq!(lease.content_hash(), previous);
+    assert!(pool.try_lease().is_none());
+
+    drop(lease);
+
+    assert_eq!(pool.content_hash(), expected);
+    let lease = pool.try_lease().unwrap();
+    assert_eq!(lease.content_hash(), expected);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+use binmod_core::{
+    error::ModuleError,
+    signature::{self, SigningKey, VerifyingKey},
+};
+use tempfile::TempDir;
+
+
+fn key() -> SigningKey {
+    SigningKey::from_bytes(&[7; 32])
+}
+
+fn other_key() -> VerifyingKey {
+    SigningKey::from_bytes(&[8; 32]).verifying_key()
+}
+
+fn binary() -> Vec<u8> {
+    wat::parse_str(common::GUEST).unwrap()
+}
+
+/// Save the guest binary with a detached signature next to it.
+fn save_signed(dir: &TempDir) -> PathBuf {
+    let path = dir.path().join("guest.wasm");
+    let binary = binary();
+
+    fs::write(&path, &binary).unwrap();
+    fs::write(dir.path().join("guest.wasm.sig"), signature::sign(&binary, &key()).unwrap().to_bytes()).unwrap();
+
+    path
+}
+
+#[test]
+fn builds_module_with_embedded_signature() {
+    let signed = signature::sign_embedded(&binary(), &key()).unwrap();
+
+    assert!(common::guest().with_binary(signed.clone()).verify_signature([key().verifying_key()]).build().is_ok());
+    assert!(matches!(
+        common::guest().with_binary(signed).verify_signature([other_key()]).build(),
+        Err(ModuleError::SignatureInvalid(_))
+    ));
+}
+
+#[test]
+fn rejects_bad_detached_signature() {
+    let binary = binary();
+    let signature = signature::sign(&binary, &key()).unwrap().to_bytes().to_vec();
+
+    let tampered = common::guest()
+        .with_binary(binary.clone())
+        .with_signature(signature.iter().map(|byte| byte ^ 1).collect())
+        .verify_signature([key().verifying_key()])
+        .build();
+    let truncated = common::guest()
+        .with_binary(binary)
+        .with_signature(signature[..10].to_vec())
+        .verify_signature([key().verifying_key()])
+        .build();
+
+    assert!(matches!(tampered, Err(ModuleError::SignatureInvalid(_))));
+    assert!(matches!(truncated, Err(ModuleError::SignatureInvalid(_))));
+}
+
+#[test]
+fn rejects_unsigned_binary_once_key_is_configured() {
+    assert!(common::guest().build().is_ok());
+    assert!(matches!(
+        common::guest().verify_signature([key().verifying_key()]).build(),
+        Err(ModuleError::SignatureInvalid(_))
+    ));
+}
+
+#[test]
+fn picks_up_signature_next_to_file() {
+    let dir = TempDir::new().unwrap();
+    let path = save_signed(&dir);
+
+    let signed = common::guest()
+        .from_file(&path)
+        .unwrap()
+        .verify_signature([key().verifying_key()])
+        .build();
+    let other = common::guest()
+        .from_file(&path)
+        .unwrap()
+        .verify_signature([other_key()])
+        .build();
+
+    assert!(signed.is_ok());
+    assert!(matches!(other, Err(ModuleError::SignatureInvalid(_))));
+
+    // Replacing the binary drops the signature of the file
+    let replaced = common::guest()
+        .from_file(&path)
+        .unwrap()
+        .with_binary(binary())
+        .verify_signature([key().verifying_key()])
+        .build();
+
+    assert!(matches!(replaced, Err(ModuleError::SignatureInvalid(_))));
+
+    // Without its signature file, the binary is unsigned
+    fs::remove_file(dir.path().join("guest.wasm.sig")).unwrap();
+    let unsigned = common::guest()
+        .from_file(&path)
+        .unwrap()
+        .verify_signature([key().verifying_key()])
+        .build();
+
+    assert!(matches!(unsigned, Err(ModuleError::SignatureInvalid(_))));
+}
@@ -0,0 +1,172 @@
+use std::io::Cursor;
+use binmod_core::{error::{ModuleError, ModuleResult}, module::{AsyncModule, Module, ModuleBuilder}, stream::StreamHandle};
+use futures::{executor::block_on, io::Cursor as AsyncCursor};
+
+
+/// A guest using the stream functions of the `binmod` namespace.
+///
+/// Its exports take the raw ids of two streams as little endian `u32`s,
+/// a reader followed by a writer, and return their input unchanged.
+const STREAMING_GUEST: &str = r#"
+(module
+    (import "binmod" "stream_read" (func $read (param i32 i32 i32) (result i32)))
+    (import "binmod" "stream_write" (func $write (param i32 i32 i32) (result i32)))
+    (import "binmod" "stream_close" (func $close (param i32)))
+
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+
+    (func $pack (param $ptr i32) (param $len i32) (result i64)
+        (i64.or
+            (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+            (i64.extend_i32_u (local.get $len))))
+
+    (func (export "guest_alloc") (param $size i32) (result i32)
+        (global.get $heap)
+        (global.set $heap (i32.add (global.get $heap) (local.get $size))))
+
+    (func (export "guest_dealloc") (param i32) (param i32))
+
+    ;; Copies the reader to the writer in 16 byte chunks, then closes the writer
+    (func (export "copy") (param $ptr i32) (param $len i32) (result i64)
+        (local $read i32)
+        (block $done
+            (loop $chunk
+                (local.set $read (call $read (i32.load (local.get $ptr)) (i32.const 512) (i32.const 16)))
+                (br_if $done (i32.eqz (local.get $read)))
+                (drop (call $write (i32.load offset=4 (local.get $ptr)) (i32.const 512) (local.get $read)))
+                (br $chunk)))
+        (call $close (i32.load offset=4 (local.get $ptr)))
+        (call $pack (local.get $ptr) (local.get $len)))
+
+    ;; Reads into a buffer running past the end of memory
+    (func (export "read_out_of_bounds") (param $ptr i32) (param $len i32) (result i64)
+        (drop (call $read (i32.load (local.get $ptr)) (i32.const 65530) (i32.const 16)))
+        (call $pack (local.get $ptr) (local.get $len)))
+
+    ;; Closes the reader, then reads from it
+    (func (export "read_closed") (param $ptr i32) (param $len i32) (result i64)
+        (call $close (i32.load (local.get $ptr)))
+        (drop (call $read (i32.load (local.get $ptr)) (i32.const 512) (i32.const 16)))
+        (call $pack (local.get $ptr) (local.get $len)))
+
+    ;; Writes to the reader
+    (func (export "write_to_reader") (param $ptr i32) (param $len i32) (result i64)
+        (drop (call $write (i32.load (local.get $ptr)) (i32.const 512) (i32.const 16)))
+        (call $pack (local.get $ptr) (local.get $len)))
+)
+"#;
+
+const PAYLOAD: &[u8] = b"a payload spanning several chunks of the guest buffer";
+
+fn guest() -> ModuleBuilder {
+    Module::builder()
+        .from_wat(STREAMING_GUEST)
+        .unwrap()
+        .with_name("streaming")
+}
+
+fn module() -> Module {
+    guest()
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap()
+}
+
+async fn async_module() -> AsyncModule {
+    guest()
+        .build_async()
+        .unwrap()
+        .instantiate()
+        .await
+        .unwrap()
+}
+
+/// Encode the ids of the streams passed to the guest.
+fn handles(reader: StreamHandle, writer: StreamHandle) -> Vec<u8> {
+    [reader.id().to_le_bytes(), writer.id().to_le_bytes()].concat()
+}
+
+fn is_stream_error(result: ModuleResult<Vec<u8>>, expected: &str) -> bool {
+    matches!(result, Err(ModuleError::StreamError(message)) if message.contains(expected))
+}
+
+#[test]
+fn copies_stream_through_guest() {
+    let mut module = module();
+    let reader = module.open_reader(Cursor::new(PAYLOAD)).unwrap();
+    let writer = module.open_writer(Vec::<u8>::new()).unwrap();
+
+    module.call_raw("copy", &handles(reader, writer)).unwrap();
+
+    // Writers closed by the guest can still be taken back
+    assert_eq!(module.take_writer::<Vec<u8>>(writer).unwrap(), PAYLOAD);
+}
+
+#[test]
+fn rejects_out_of_bounds_stream_buffer() {
+    let mut module = module();
+    let reader = module.open_reader(Cursor::new(PAYLOAD)).unwrap();
+    let writer = module.open_writer(Vec::<u8>::new()).unwrap();
+
+    assert!(matches!(
+        module.call_raw("read_out_of_bounds", &handles(reader, writer)),
+        Err(ModuleError::MemoryError(message)) if message.contains("out of bounds")
+    ));
+}
+
+#[test]
+fn rejects_closed_and_unknown_streams() {
+    let mut module = module();
+    let reader = module.open_reader(Cursor::new(PAYLOAD)).unwrap();
+    let writer = module.open_writer(Vec::<u8>::new()).unwrap();
+
+    assert!(is_stream_error(module.call_raw("write_to_reader", &handles(reader, writer)), "is not writable"));
+    assert!(is_stream_error(module.call_raw("read_closed", &handles(reader, writer)), "is closed"));
+
+    // Streams closed by the host are gone for the guest
+    let reader = module.open_reader(Cursor::new(PAYLOAD)).unwrap();
+
+    assert!(module.close_stream(writer).unwrap());
+    assert!(is_stream_error(module.call_raw("copy", &handles(reader, writer)), &format!("unknown stream {}", writer.id())));
+    assert!(is_stream_error(module.call_raw("copy", &[0; 8]), "unknown stream 0"));
+}
+
+#[test]
+fn copies_async_stream_through_guest() {
+    block_on(async {
+        let mut module = async_module().await;
+        let reader = module.open_async_reader(AsyncCursor::new(PAYLOAD)).unwrap();
+        let writer = module.open_async_writer(AsyncCursor::new(Vec::<u8>::new())).unwrap();
+
+        module.call_raw("copy", &handles(reader, writer)).await.unwrap();
+
+        let copied: AsyncCursor<Vec<u8>> = module.take_async_writer(writer).await.unwrap();
+        assert_eq!(copied.into_inner(), PAYLOAD);
+
+        // Blocking streams are readable by async modules too
+        let reader = module.open_reader(Cursor::new(PAYLOAD)).unwrap();
+        let writer = module.open_writer(Vec::<u8>::new()).unwrap();
+
+        module.call_raw("copy", &handles(reader, writer)).await.unwrap();
+        assert_eq!(module.take_writer::<Vec<u8>>(writer).unwrap(), PAYLOAD);
+    });
+}
+
+#[test]
+fn rejects_invalid_async_stream_access() {
+    block_on(async {
+        let mut module = async_module().await;
+        let reader = module.open_async_reader(AsyncCursor::new(PAYLOAD)).unwrap();
+        let writer = module.open_async_writer(AsyncCursor::new(Vec::<u8>::new())).unwrap();
+
+        assert!(matches!(
+            module.call_raw("read_out_of_bounds", &handles(reader, writer)).await,
+            Err(ModuleError::MemoryError(message)) if message.contains("out of bounds")
+        ));
+        assert!(is_stream_error(module.call_raw("write_to_reader", &handles(reader, writer)).await, "is not writable"));
+        assert!(is_stream_error(module.call_raw("read_closed", &handles(reader, writer)).await, "is closed"));
+        assert!(is_stream_error(module.call_raw("copy", &[0; 8]).await, "unknown stream 0"));
+    });
+}
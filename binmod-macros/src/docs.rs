@@ -0,0 +1,20 @@
+use syn::{Attribute, Expr, ExprLit, Lit, Meta};
+
+
+/// Join the doc comments of an item into a single description
+pub fn description(attrs: &[Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let description = lines.join("\n").trim().to_string();
+
+    (!description.is_empty()).then_some(description)
+}
@@ -0,0 +1,187 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, parse_quote, Data, DeriveInput, Error, Fields, Generics, Index, LitStr, Member, Path, Type};
+
+
+/// Where a field is read from and written to in the function input
+enum FieldSource {
+    Positional(usize),
+    Keyword(String),
+}
+
+/// How a missing field is filled in
+enum FieldDefault {
+    Required,
+    Default,
+    Path(Path),
+}
+
+/// A field of a struct deriving `FromFnInput` or `IntoFnInput`
+struct InputField {
+    member: Member,
+    ty: Type,
+    source: FieldSource,
+    default: FieldDefault,
+}
+
+/// Parse the fields of a struct along with their `#[binmod(...)]` attributes
+fn parse_fields(input: &DeriveInput) -> syn::Result<Vec<InputField>> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new(input.span(), "function inputs can only be derived for structs")),
+    };
+
+    let mut parsed = vec![];
+    let mut position = 0;
+
+    for (index, field) in fields.iter().enumerate() {
+        let mut rename = None;
+        let mut positional = false;
+        let mut keyword = false;
+        let mut default = FieldDefault::Required;
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("binmod")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    default = match meta.input.peek(syn::Token![=]) {
+                        true => FieldDefault::Path(meta.value()?.parse::<LitStr>()?.parse()?),
+                        false => FieldDefault::Default,
+                    };
+                } else if meta.path.is_ident("positional") {
+                    positional = true;
+                } else if meta.path.is_ident("keyword") {
+                    keyword = true;
+                } else {
+                    return Err(meta.error("unsupported field attribute, expected `rename`, `default`, `positional` or `keyword`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        if positional && keyword {
+            return Err(Error::new(field.span(), "a field can't be both `positional` and `keyword`"));
+        }
+
+        if positional && rename.is_some() {
+            return Err(Error::new(field.span(), "positional fields can't be renamed"));
+        }
+
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        };
+
+        let is_keyword = match fields {
+            Fields::Named(_) => !positional,
+            _ => keyword || rename.is_some(),
+        };
+
+        let source = if is_keyword {
+            match (rename, &field.ident) {
+                (Some(name), _) => FieldSource::Keyword(name),
+                (None, Some(ident)) => FieldSource::Keyword(ident.to_string()),
+                (None, None) => return Err(Error::new(field.span(), "keyword fields of tuple structs must be renamed")),
+            }
+        } else {
+            position += 1;
+            FieldSource::Positional(position - 1)
+        };
+
+        parsed.push(InputField { member, ty: field.ty.clone(), source, default });
+    }
+
+    Ok(parsed)
+}
+
+/// Bound the field types of a generic struct, so the derived impl only applies
+/// when every field can be read from or written to the function input
+fn bound_fields(generics: &Generics, fields: &[InputField], bound: impl Fn(&InputField) -> TokenStream) -> Generics {
+    let mut generics = generics.clone();
+
+    if generics.type_params().next().is_none() {
+        return generics;
+    }
+
+    let where_clause = generics.make_where_clause();
+
+    for field in fields {
+        let ty = &field.ty;
+        let bound = bound(field);
+        where_clause.predicates.push(parse_quote!(#ty: #bound));
+    }
+
+    generics
+}
+
+pub fn expand_from(input: TokenStream) -> syn::Result<TokenStream> {
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let fields = parse_fields(&input)?;
+
+    let ident = &input.ident;
+    let generics = bound_fields(&input.generics, &fields, |field| match field.default {
+        FieldDefault::Default => quote!(::binmod::serde::de::DeserializeOwned + ::std::default::Default),
+        _ => quote!(::binmod::serde::de::DeserializeOwned),
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let names = (0..fields.len())
+        .map(|index| format_ident!("field{}", index))
+        .collect::<Vec<_>>();
+    let values = fields.iter().map(|field| {
+        let (present, value) = match &field.source {
+            FieldSource::Positional(index) => (quote!(input.has_arg(#index)), quote!(input.get_arg(#index)?)),
+            FieldSource::Keyword(name) => (quote!(input.has_kwarg(#name)), quote!(input.get_kwarg(#name)?)),
+        };
+
+        match &field.default {
+            FieldDefault::Required => value,
+            FieldDefault::Default => quote!(if #present { #value } else { ::std::default::Default::default() }),
+            FieldDefault::Path(path) => quote!(if #present { #value } else { #path() }),
+        }
+    });
+    let members = fields.iter().map(|field| &field.member);
+
+    Ok(quote! {
+        impl #impl_generics ::binmod::input::FromFnInput for #ident #ty_generics #where_clause {
+            fn from_fn_input(input: &::binmod::input::FnInput) -> ::std::result::Result<Self, ::binmod::error::FnError> {
+                #(
+                    let #names = #values;
+                )*
+
+                Ok(Self {
+                    #(#members: #names),*
+                })
+            }
+        }
+    })
+}
+
+pub fn expand_into(input: TokenStream) -> syn::Result<TokenStream> {
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let fields = parse_fields(&input)?;
+
+    let ident = &input.ident;
+    let generics = bound_fields(&input.generics, &fields, |_| quote!(::binmod::serde::Serialize));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let writes = fields.iter().map(|field| {
+        let member = &field.member;
+
+        match &field.source {
+            FieldSource::Positional(_) => quote!(.with_arg(self.#member)?),
+            FieldSource::Keyword(name) => quote!(.with_kwarg(#name, self.#member)?),
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::binmod::input::IntoFnInput for #ident #ty_generics #where_clause {
+            fn into_fn_input(self) -> ::binmod::error::ModuleResult<::binmod::input::FnInput> {
+                Ok(
+                    ::binmod::input::FnInput::default()
+                        #(#writes)*
+                )
+            }
+        }
+    })
+}
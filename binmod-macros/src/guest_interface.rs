@@ -0,0 +1,325 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse::Parser, spanned::Spanned, Attribute, Error, FnArg, Ident, ItemTrait, LitStr, Pat, ReturnType,
+    TraitItem, TraitItemFn, Type,
+};
+
+use crate::docs;
+
+
+/// A guest function declared by a `#[guest_interface]` trait
+struct GuestFn {
+    attrs: Vec<Attribute>,
+    ident: Ident,
+    export: String,
+    args: Vec<(Ident, Type)>,
+    output: Type,
+}
+
+impl GuestFn {
+    fn parse(mut item: TraitItemFn) -> syn::Result<Self> {
+        let sig = &item.sig;
+
+        if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+            return Err(Error::new(sig.generics.span(), "guest functions can't be generic"));
+        }
+
+        if sig.asyncness.is_some() {
+            return Err(Error::new(sig.asyncness.span(), "guest functions can't be async, an async client is generated"));
+        }
+
+        if item.default.is_some() {
+            return Err(Error::new(item.default.span(), "guest functions can't have a default implementation"));
+        }
+
+        let mut args = vec![];
+        let mut receiver = false;
+
+        for input in &sig.inputs {
+            match input {
+                FnArg::Receiver(r) if r.reference.is_some() && r.mutability.is_some() => receiver = true,
+                FnArg::Receiver(r) => return Err(Error::new(r.span(), "guest functions must take `&mut self`")),
+                FnArg::Typed(arg) => match &*arg.pat {
+                    Pat::Ident(pat) => args.push((pat.ident.clone(), (*arg.ty).clone())),
+                    pat => return Err(Error::new(pat.span(), "guest function arguments must be identifiers")),
+                },
+            }
+        }
+
+        if !receiver {
+            return Err(Error::new(sig.span(), "guest functions must take `&mut self`"));
+        }
+
+        let output = match &sig.output {
+            ReturnType::Default => syn::parse_quote!(()),
+            ReturnType::Type(_, ty) => (**ty).clone(),
+        };
+
+        let mut export = sig.ident.to_string();
+        let mut attrs = vec![];
+
+        for attr in item.attrs.drain(..) {
+            if attr.path().is_ident("binmod") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        export = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported guest function attribute, expected `name`"))
+                    }
+                })?;
+            } else {
+                attrs.push(attr);
+            }
+        }
+
+        Ok(Self {
+            attrs,
+            ident: item.sig.ident.clone(),
+            export,
+            args,
+            output,
+        })
+    }
+
+    /// The signature of the generated client method, without the leading `fn`
+    fn signature(&self) -> TokenStream {
+        let ident = &self.ident;
+        let output = &self.output;
+        let args = self.args.iter().map(|(ident, ty)| quote!(#ident: #ty));
+
+        quote!(#ident(&mut self, #(#args),*) -> ::binmod::error::ModuleResult<#output>)
+    }
+
+    /// The expression building the schema of the function
+    fn schema(&self) -> TokenStream {
+        let output = &self.output;
+        let description = docs::description(&self.attrs)
+            .map(|description| quote!(.with_description(#description)));
+        let args = self.args.iter().map(|(ident, ty)| {
+            let name = ident.to_string();
+            quote!(.with_arg::<#ty>(#name))
+        });
+
+        quote! {
+            ::binmod::schema::FnSchema::new()
+                #description
+                #(#args)*
+                .with_returns::<#output>()
+        }
+    }
+
+    /// The expression building the function input from the method arguments
+    fn input(&self) -> TokenStream {
+        let args = self.args.iter().map(|(ident, _)| ident);
+
+        quote! {
+            ::binmod::input::FnInput::default()
+                #(.with_arg(#args)?)*
+        }
+    }
+}
+
+/// Arguments of the `#[guest_interface]` attribute
+#[derive(Default)]
+struct InterfaceArgs {
+    client: Option<Ident>,
+    async_client: Option<Ident>,
+    schema: bool,
+}
+
+impl InterfaceArgs {
+    fn parse(args: TokenStream) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("client") {
+                parsed.client = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("async_client") {
+                parsed.async_client = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("schema") {
+                parsed.schema = true;
+            } else {
+                return Err(meta.error("unsupported argument, expected `client`, `async_client` or `schema`"));
+            }
+
+            Ok(())
+        })
+        .parse2(args)?;
+
+        Ok(parsed)
+    }
+}
+
+pub fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args = InterfaceArgs::parse(args)?;
+    let item = syn::parse2::<ItemTrait>(item)?;
+
+    if !item.generics.params.is_empty() {
+        return Err(Error::new(item.generics.span(), "guest interfaces can't be generic"));
+    }
+
+    let functions = item.items
+        .iter()
+        .map(|item| match item {
+            TraitItem::Fn(func) => GuestFn::parse(func.clone()),
+            item => Err(Error::new(item.span(), "guest interfaces may only contain functions")),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let vis = &item.vis;
+    let attrs = &item.attrs;
+    let ident = &item.ident;
+    let client = args.client.unwrap_or_else(|| format_ident!("{}Client", ident));
+    let async_client = args.async_client.unwrap_or_else(|| format_ident!("Async{}Client", ident));
+    let exports = functions.iter().map(|func| &func.export).collect::<Vec<_>>();
+
+    let trait_fns = functions.iter().map(|func| {
+        let attrs = &func.attrs;
+        let signature = func.signature();
+
+        quote! {
+            #(#attrs)*
+            fn #signature;
+        }
+    });
+
+    let client_fns = functions.iter().map(|func| {
+        let output = &func.output;
+        let export = &func.export;
+        let signature = func.signature();
+        let input = func.input();
+
+        quote! {
+            fn #signature {
+                Ok(
+                    ::std::borrow::BorrowMut::<::binmod::module::Module>::borrow_mut(&mut self.module)
+                        .call(#export, #input)?
+                        .into_result::<#output>()?
+                )
+            }
+        }
+    });
+
+    let async_client_fns = functions.iter().map(|func| {
+        let attrs = &func.attrs;
+        let output = &func.output;
+        let export = &func.export;
+        let signature = func.signature();
+        let input = func.input();
+
+        quote! {
+            #(#attrs)*
+            pub async fn #signature {
+                Ok(
+                    ::std::borrow::BorrowMut::<::binmod::module::AsyncModule>::borrow_mut(&mut self.module)
+                        .call(#export, #input)
+                        .await?
+                        .into_result::<#output>()?
+                )
+            }
+        }
+    });
+
+    let client_docs = format!("Typed client calling the [`{}`] interface of a [`Module`](::binmod::module::Module).", ident);
+    let async_client_docs = format!("Typed client calling the [`{}`] interface of an [`AsyncModule`](::binmod::module::AsyncModule).", ident);
+
+    let interface = ident.to_string();
+    let schemas = functions.iter().map(GuestFn::schema);
+    let describe = args.schema.then(|| quote! {
+        /// Describe the functions of the interface.
+        pub fn describe() -> ::binmod::schema::InterfaceSchema {
+            ::binmod::schema::InterfaceSchema {
+                name: Some(#interface.to_string()),
+                exports: [#((#exports.to_string(), #schemas)),*].into_iter().collect(),
+                ..::std::default::Default::default()
+            }
+        }
+    });
+
+    let common = |module: TokenStream| quote! {
+        /// Names of the guest functions called by this client.
+        pub const EXPORTS: &'static [&'static str] = &[#(#exports),*];
+
+        /// Create a new client for the module.
+        pub fn new(module: M) -> Self {
+            Self { module }
+        }
+
+        /// Create a new client for the module, checking that
+        /// the module exports every function of the interface.
+        ///
+        /// The ABI report of the module is reused if the binary has
+        /// already been checked, which is the case for built modules.
+        pub fn new_checked(module: M) -> ::binmod::error::ModuleResult<Self> {
+            let inner = ::std::borrow::Borrow::<#module>::borrow(&module);
+
+            match inner.abi_report() {
+                Some(report) => report.expect_functions(Self::EXPORTS)?,
+                None => ::binmod::abi::check(inner.binary()).expect_functions(Self::EXPORTS)?,
+            }
+
+            Ok(Self { module })
+        }
+
+        /// Get the underlying module.
+        pub fn module(&self) -> &#module {
+            ::std::borrow::Borrow::<#module>::borrow(&self.module)
+        }
+
+        /// Get the underlying module mutably.
+        pub fn module_mut(&mut self) -> &mut #module {
+            ::std::borrow::BorrowMut::<#module>::borrow_mut(&mut self.module)
+        }
+
+        /// Consume the client, returning the underlying module.
+        pub fn into_inner(self) -> M {
+            self.module
+        }
+
+        #describe
+    };
+    let client_common = common(quote!(::binmod::module::Module));
+    let async_client_common = common(quote!(::binmod::module::AsyncModule));
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis trait #ident {
+            #(#trait_fns)*
+        }
+
+        #[doc = #client_docs]
+        #vis struct #client<M> {
+            module: M,
+        }
+
+        impl<M> #client<M>
+        where
+            M: ::std::borrow::BorrowMut<::binmod::module::Module>,
+        {
+            #client_common
+        }
+
+        impl<M> #ident for #client<M>
+        where
+            M: ::std::borrow::BorrowMut<::binmod::module::Module>,
+        {
+            #(#client_fns)*
+        }
+
+        #[doc = #async_client_docs]
+        #vis struct #async_client<M> {
+            module: M,
+        }
+
+        impl<M> #async_client<M>
+        where
+            M: ::std::borrow::BorrowMut<::binmod::module::AsyncModule>,
+        {
+            #async_client_common
+
+            #(#async_client_fns)*
+        }
+    })
+}
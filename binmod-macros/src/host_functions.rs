@@ -0,0 +1,264 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse::Parser, spanned::Spanned, Attribute, Error, FnArg, ImplItem, ImplItemFn, ItemImpl, LitStr, Pat,
+    ReturnType, Type,
+};
+
+use crate::docs;
+
+
+/// A method of a `#[host_functions]` impl block exposed as a host function
+struct HostMethod {
+    ident: syn::Ident,
+    export: String,
+    description: Option<String>,
+    args: Vec<HostArg>,
+    output: Type,
+}
+
+/// An argument of a host method
+enum HostArg {
+    /// Deserialized from the positional argument at the given index
+    Positional(usize, String, Type),
+    /// Deserialized from all keyword arguments
+    Kwargs(Type),
+}
+
+/// Options set on a method or argument with `#[binmod(...)]`
+#[derive(Default)]
+struct HostAttrs {
+    name: Option<String>,
+    skip: bool,
+    kwargs: bool,
+}
+
+impl HostAttrs {
+    /// Parse and remove the `#[binmod(...)]` attributes from a list of attributes
+    fn take(attrs: &mut Vec<Attribute>) -> syn::Result<Self> {
+        let mut options = Self::default();
+        let mut result = Ok(());
+
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("binmod") {
+                return true;
+            }
+
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    options.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else if meta.path.is_ident("kwargs") {
+                    options.kwargs = true;
+                } else {
+                    return Err(meta.error("unsupported host function attribute, expected `name`, `skip` or `kwargs`"));
+                }
+
+                Ok(())
+            });
+
+            if let Err(e) = parsed {
+                result = Err(e);
+            }
+
+            false
+        });
+
+        result.map(|_| options)
+    }
+}
+
+impl HostMethod {
+    /// Parse a method, returning `None` if it is not exposed as a host function
+    fn parse(item: &mut ImplItemFn) -> syn::Result<Option<Self>> {
+        let attrs = HostAttrs::take(&mut item.attrs)?;
+        let sig = &mut item.sig;
+
+        if attrs.kwargs {
+            return Err(Error::new(sig.span(), "`kwargs` can only be set on arguments"));
+        }
+
+        let mut args = vec![];
+        let mut receiver = None;
+        let mut position = 0;
+
+        for input in sig.inputs.iter_mut() {
+            match input {
+                FnArg::Receiver(r) => receiver = Some(r.reference.is_some() && r.mutability.is_none()),
+                FnArg::Typed(arg) => {
+                    let arg_attrs = HostAttrs::take(&mut arg.attrs)?;
+
+                    if arg_attrs.name.is_some() || arg_attrs.skip {
+                        return Err(Error::new(arg.span(), "only `kwargs` can be set on arguments"));
+                    }
+
+                    if arg_attrs.kwargs {
+                        args.push(HostArg::Kwargs((*arg.ty).clone()));
+                    } else {
+                        let name = match &*arg.pat {
+                            Pat::Ident(pat) => pat.ident.to_string(),
+                            _ => format!("arg{}", position),
+                        };
+
+                        args.push(HostArg::Positional(position, name, (*arg.ty).clone()));
+                        position += 1;
+                    }
+                },
+            }
+        }
+
+        if attrs.skip || receiver.is_none() {
+            return Ok(None);
+        }
+
+        if receiver == Some(false) {
+            return Err(Error::new(
+                sig.inputs.span(),
+                "host functions must take `&self`, use interior mutability for shared state",
+            ));
+        }
+
+        if sig.asyncness.is_some() {
+            return Err(Error::new(sig.asyncness.span(), "host functions can't be async"));
+        }
+
+        if !sig.generics.params.is_empty() {
+            return Err(Error::new(sig.generics.span(), "host functions can't be generic"));
+        }
+
+        let output = match &sig.output {
+            ReturnType::Default => syn::parse_quote!(()),
+            ReturnType::Type(_, ty) => (**ty).clone(),
+        };
+
+        Ok(Some(Self {
+            ident: sig.ident.clone(),
+            export: attrs.name.unwrap_or_else(|| sig.ident.to_string()),
+            description: docs::description(&item.attrs),
+            args,
+            output,
+        }))
+    }
+
+    /// The expression building the schema of the method
+    fn schema(&self) -> TokenStream {
+        let output = &self.output;
+        let description = self.description
+            .as_ref()
+            .map(|description| quote!(.with_description(#description)));
+        let args = self.args.iter().map(|arg| match arg {
+            HostArg::Positional(_, name, ty) => quote!(.with_arg::<#ty>(#name)),
+            HostArg::Kwargs(ty) => quote!(.with_kwargs::<#ty>()),
+        });
+
+        quote! {
+            ::binmod::schema::FnSchema::new()
+                #description
+                #(#args)*
+                .with_result::<#output>()
+        }
+    }
+
+    /// The expression registering the method in the host function set
+    fn register(&self, schema: bool) -> TokenStream {
+        let ident = &self.ident;
+        let export = &self.export;
+        let names = (0..self.args.len())
+            .map(|index| format_ident!("arg{}", index))
+            .collect::<Vec<_>>();
+        let values = self.args.iter().map(|arg| match arg {
+            HostArg::Positional(index, _, ty) => quote!(input.get_arg::<#ty>(#index)),
+            HostArg::Kwargs(ty) => quote!(input.clone().into_struct::<#ty>()),
+        });
+        let schema = schema.then(|| {
+            let schema = self.schema();
+            quote!(.with_schema(#schema))
+        });
+
+        quote! {
+            {
+                let instance = ::std::sync::Arc::clone(&self);
+
+                set.insert(#export, ::binmod::host_fns::HostFn::from_fn(move |input: &::binmod::input::FnInput| {
+                    #(
+                        let #names = match #values {
+                            Ok(value) => value,
+                            Err(e) => return ::binmod::result::FnResult::err(&e),
+                        };
+                    )*
+
+                    ::binmod::result::IntoFnResult::into_fn_result(instance.#ident(#(#names),*))
+                })#schema);
+            }
+        }
+    }
+}
+
+/// Arguments of the `#[host_functions]` attribute
+#[derive(Default)]
+struct HostFunctionsArgs {
+    namespace: Option<LitStr>,
+    schema: bool,
+}
+
+impl HostFunctionsArgs {
+    fn parse(args: TokenStream) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("namespace") {
+                parsed.namespace = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("schema") {
+                parsed.schema = true;
+            } else {
+                return Err(meta.error("unsupported argument, expected `namespace` or `schema`"));
+            }
+
+            Ok(())
+        })
+        .parse2(args)?;
+
+        Ok(parsed)
+    }
+}
+
+pub fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args = HostFunctionsArgs::parse(args)?;
+    let mut item = syn::parse2::<ItemImpl>(item)?;
+
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(Error::new(path.span(), "host functions must be defined in an inherent impl block"));
+    }
+
+    let mut methods = vec![];
+
+    for impl_item in item.items.iter_mut() {
+        if let ImplItem::Fn(func) = impl_item
+            && let Some(method) = HostMethod::parse(func)?
+        {
+            methods.push(method);
+        }
+    }
+
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let namespace = args.namespace.map(|namespace| quote!(.with_namespace(#namespace)));
+    let registrations = methods.iter().map(|method| method.register(args.schema));
+
+    Ok(quote! {
+        #item
+
+        impl #impl_generics ::binmod::host_fns::HostFunctions for #self_ty #where_clause {
+            fn host_fn_set(self: ::std::sync::Arc<Self>) -> ::binmod::host_fns::HostFnSet {
+                #[allow(unused_mut)]
+                let mut set = ::binmod::host_fns::HostFnSet::new()
+                    #namespace;
+
+                #(#registrations)*
+
+                set
+            }
+        }
+    })
+}
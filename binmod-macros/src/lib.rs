@@ -2,5 +2,145 @@
 extern crate self as binmod_macros;
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+
+mod docs;
+mod fn_input;
+mod guest_interface;
+mod host_functions;
+
+
+/// Generate typed clients for a guest interface.
+///
+/// Every function of the trait must take `&mut self` and maps to the guest
+/// export of the same name, or the name given with `#[binmod(name = "...")]`.
+/// Return types are wrapped in a `ModuleResult`.
+///
+/// Two clients are generated, `<Trait>Client` implementing the trait over any
+/// `BorrowMut<Module>` (a `Module`, a `&mut Module` or a pool lease), and
+/// `Async<Trait>Client` with the same functions as async methods over any
+/// `BorrowMut<AsyncModule>`. Their names can be overridden with the `client`
+/// and `async_client` arguments.
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::module::Module;
+///
+/// #[binmod::guest_interface]
+/// trait Calculator {
+///     fn circle_area(&mut self, r: f64) -> f64;
+///
+///     #[binmod(name = "add_numbers")]
+///     fn add(&mut self, a: i32, b: i32) -> i32;
+/// }
+///
+/// # fn example(module: Module) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut calculator = CalculatorClient::new_checked(module)?;
+/// let area = calculator.circle_area(5.0)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn guest_interface(args: TokenStream, item: TokenStream) -> TokenStream {
+    guest_interface::expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expose the methods of an impl block as a set of host functions.
+///
+/// Every method taking `&self` is registered under its own name, or the name given
+/// with `#[binmod(name = "...")]`, and bound to a shared instance of the type through
+/// a generated `HostFunctions` implementation.
+/// Methods can be left out with `#[binmod(skip)]`, and associated functions without
+/// a receiver are never registered.
+///
+/// Arguments are taken from the positional arguments of the call in order, except for
+/// an argument marked `#[binmod(kwargs)]`, which is deserialized from all keyword arguments.
+/// Methods must return a `Result` or `()`.
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::{collections::HashMap, sync::{Arc, Mutex}};
+/// use binmod::prelude::*;
+///
+/// #[derive(Default)]
+/// struct KvService {
+///     store: Mutex<HashMap<String, String>>,
+/// }
+///
+/// #[derive(serde::Deserialize)]
+/// struct SetOptions {
+///     value: String,
+/// }
+///
+/// #[binmod::host_functions(namespace = "kv")]
+/// impl KvService {
+///     fn get(&self, key: String) -> Result<Option<String>, String> {
+///         Ok(self.store.lock().unwrap().get(&key).cloned())
+///     }
+///
+///     #[binmod(name = "put")]
+///     fn set(&self, key: String, #[binmod(kwargs)] options: SetOptions) -> Result<(), String> {
+///         self.store.lock().unwrap().insert(key, options.value);
+///         Ok(())
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let module = Module::builder()
+///     .from_file("my_module.wasm")?
+///     .with_name("my_module")
+///     .with_host_fns(Arc::new(KvService::default()))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn host_functions(args: TokenStream, item: TokenStream) -> TokenStream {
+    host_functions::expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `FromFnInput` for a struct.
+///
+/// Fields of a struct with named fields are read from the keyword arguments, and fields
+/// of a tuple struct from the positional arguments, in order. Fields accept the following
+/// `#[binmod(...)]` attributes:
+///
+/// * `rename = "..."` - Read the field from the keyword argument with the given name
+/// * `positional` - Read the field from the next positional argument
+/// * `keyword` - Read the field from a keyword argument, tuple struct fields must also be renamed
+/// * `default` - Use `Default::default()` if the argument is missing
+/// * `default = "path"` - Call the given function if the argument is missing
+///
+/// # Examples
+/// ```rust,no_run
+/// use binmod::prelude::*;
+///
+/// #[derive(FromFnInput, IntoFnInput)]
+/// struct Resize {
+///     #[binmod(positional)]
+///     path: String,
+///     width: u32,
+///     #[binmod(rename = "keepAspect", default)]
+///     keep_aspect: bool,
+/// }
+/// ```
+#[proc_macro_derive(FromFnInput, attributes(binmod))]
+pub fn derive_from_fn_input(input: TokenStream) -> TokenStream {
+    fn_input::expand_from(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `IntoFnInput` for a struct.
+///
+/// Fields are written to the positional and keyword arguments they are read
+/// from by `FromFnInput`, accepting the same `#[binmod(...)]` attributes.
+#[proc_macro_derive(IntoFnInput, attributes(binmod))]
+pub fn derive_into_fn_input(input: TokenStream) -> TokenStream {
+    fn_input::expand_into(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
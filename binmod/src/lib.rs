@@ -4,3 +4,5 @@ extern crate self as binmod;
 pub mod prelude;
 
 pub use binmod_core::*;
+
+pub use binmod_macros::{guest_interface, host_functions, FromFnInput, IntoFnInput};
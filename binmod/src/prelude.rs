@@ -1,9 +1,20 @@
 pub use binmod_core::{
-    config::{ModuleEnv, ModuleConfig, ModuleLimits, ModuleCompiler, ModuleNetwork, ModuleSocketAddrAction},
+    config::{ModuleEnv, ModuleConfig, ModuleLimits, ModuleCompiler, ModuleProfiler, ModuleNetwork, ModuleSocketAddrAction},
     input::{FnInput, FromFnInput, IntoFnInput},
+    invoke::{Invocation, AsyncInvocation},
     result::{FnResult, IntoFnResult},
+    schema::{FnSchema, InterfaceSchema},
     error::{ModuleError, ModuleResult, FnError},
-    host_fns::{HostFn, HostFnCallable, HostFnWrapper},
-    module::{Module, AsyncModule, ModuleBuilder},
+    host_fns::{HostFn, HostFnCallable, HostFnWrapper, HostFnSet, HostFunctions},
+    module::{Module, AsyncModule, ModuleBuilder, ModuleFn, AsyncModuleFn},
+    component::{ComponentModule, AsyncComponentModule},
+    abi::{AbiReport, AbiVersion, ExportStatus},
+    callback::{GuestCaller, AsyncGuestCaller},
+    cancel::CancellationToken,
+    codec::Codec,
+    package::{Package, PackageManifest, PackageEnv},
     pool::{ModulePool, AsyncModulePool, ModulePoolBuilder},
-};
\ No newline at end of file
+    stream::StreamHandle,
+    reload::{ReloadablePool, ReloadableModule, ReloadablePoolBuilder, ReloadEvent},
+};
+pub use binmod_macros::{FromFnInput, IntoFnInput};
@@ -0,0 +1,59 @@
+#![allow(dead_code)]
+
+use binmod::prelude::*;
+
+
+/// A guest implementing the binmod ABI with a bump allocator.
+pub const GUEST: &str = r#"
+(module
+    (import "env" "double" (func $double (param i64) (result i64)))
+
+    (memory (export "memory") 1)
+
+    (global $heap (mut i32) (i32.const 1024))
+
+    (data (i32.const 64) "{\"object\":\"data\",\"value\":42}")
+
+    (func $pack (param $ptr i32) (param $len i32) (result i64)
+        (i64.or
+            (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+            (i64.extend_i32_u (local.get $len))))
+
+    (func $alloc (export "guest_alloc") (param $size i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap
+            (i32.and
+                (i32.add (i32.add (local.get $ptr) (local.get $size)) (i32.const 7))
+                (i32.const -8)))
+        (local.get $ptr))
+
+    (func (export "guest_dealloc") (param $ptr i32) (param $len i32))
+
+    ;; Returns 42, copied out of the data segment since the host frees results
+    (func (export "answer") (param $ptr i32) (param $len i32) (result i64)
+        (local $result i32)
+        (local.set $result (call $alloc (i32.const 28)))
+        (memory.copy (local.get $result) (i32.const 64) (i32.const 28))
+        (call $pack (local.get $result) (i32.const 28)))
+
+    ;; Forwards its input to the `double` host function
+    (func (export "call_double") (param $ptr i32) (param $len i32) (result i64)
+        (call $double (call $pack (local.get $ptr) (local.get $len))))
+)
+"#;
+
+/// Create a builder for the guest, without the host functions it imports.
+pub fn guest() -> ModuleBuilder {
+    Module::builder()
+        .from_wat(GUEST)
+        .unwrap()
+        .with_name("guest")
+}
+
+/// Create a builder for the guest, with the host functions it imports.
+pub fn linked_guest() -> ModuleBuilder {
+    guest().host_fn("double", |value: i64| -> Result<i64, String> {
+        Ok(value * 2)
+    })
+}
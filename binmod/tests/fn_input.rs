@@ -0,0 +1,27 @@
+use binmod::input::{FnInput, FromFnInput, IntoFnInput};
+use binmod::{FromFnInput, IntoFnInput};
+
+
+#[derive(FromFnInput, IntoFnInput, Debug, PartialEq)]
+struct Labeled<T> {
+    #[binmod(positional)]
+    value: T,
+    #[binmod(default)]
+    labels: Vec<String>,
+}
+
+#[test]
+fn derives_for_generic_structs() {
+    let input = Labeled { value: 4.5, labels: vec!["size".into()] }
+        .into_fn_input()
+        .unwrap();
+
+    assert_eq!(Labeled::<f64>::from_fn_input(&input).unwrap(), Labeled { value: 4.5, labels: vec!["size".into()] });
+}
+
+#[test]
+fn reads_defaults_for_generic_structs() {
+    let input = FnInput::new().with_arg("small").unwrap();
+
+    assert_eq!(Labeled::<String>::from_fn_input(&input).unwrap(), Labeled { value: "small".into(), labels: vec![] });
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use binmod::{guest_interface, prelude::*};
+use futures::executor::block_on;
+
+
+#[guest_interface(schema)]
+trait Calculator {
+    /// Double a value through the host.
+    #[binmod(name = "call_double")]
+    fn double(&mut self, value: i64) -> i64;
+
+    fn answer(&mut self) -> i64;
+}
+
+#[guest_interface]
+trait Missing {
+    fn subtract(&mut self, a: i64, b: i64) -> i64;
+}
+
+#[test]
+fn calls_guest_through_client() {
+    let mut module = common::linked_guest().build().unwrap().instantiate().unwrap();
+    let mut client = CalculatorClient::new_checked(&mut module).unwrap();
+
+    assert_eq!(client.double(21).unwrap(), 42);
+    assert_eq!(client.answer().unwrap(), 42);
+}
+
+#[test]
+fn calls_guest_through_async_client() {
+    let module = block_on(common::linked_guest().build_async().unwrap().instantiate()).unwrap();
+    let mut client = AsyncCalculatorClient::new_checked(module).unwrap();
+
+    assert_eq!(block_on(client.double(21)).unwrap(), 42);
+    assert_eq!(block_on(client.answer()).unwrap(), 42);
+}
+
+#[test]
+fn rejects_module_missing_exports() {
+    let module = common::linked_guest().build().unwrap();
+
+    assert!(matches!(MissingClient::new_checked(module.clone()), Err(ModuleError::AbiError(_))));
+    assert!(MissingClient::new(module).module().abi_report().is_some());
+}
+
+#[test]
+fn checks_modules_created_without_builder() {
+    let module = Module::new(
+        common::guest().build().unwrap().binary().to_vec(),
+        "guest",
+        "env",
+        ModuleEnv::default(),
+        ModuleConfig::default(),
+        ModuleLimits::default(),
+        Default::default(),
+    );
+
+    assert!(module.abi_report().is_none());
+    assert!(CalculatorClient::new_checked(module.clone()).is_ok());
+    assert!(MissingClient::new_checked(module).is_err());
+}
+
+#[test]
+fn describes_interface() {
+    let schema = CalculatorClient::<Module>::describe();
+
+    assert_eq!(CalculatorClient::<Module>::EXPORTS, &["call_double", "answer"]);
+    assert_eq!(schema.name.as_deref(), Some("Calculator"));
+    assert_eq!(schema.exports.len(), 2);
+
+    let double = &schema.exports["call_double"];
+    assert_eq!(double.description.as_deref(), Some("Double a value through the host."));
+    assert_eq!(double.args[0].name.as_deref(), Some("value"));
+    assert!(double.returns.is_some());
+    assert!(schema.exports["answer"].args.is_empty());
+}
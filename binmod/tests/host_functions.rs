@@ -0,0 +1,113 @@
+mod common;
+
+use std::sync::{Arc, atomic::{AtomicI64, Ordering}};
+use binmod::{host_functions, prelude::*};
+use serde::Deserialize;
+
+
+#[derive(Default)]
+struct Doubler {
+    calls: AtomicI64,
+}
+
+#[host_functions(schema)]
+impl Doubler {
+    /// Double a value.
+    fn double(&self, value: i64) -> Result<i64, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(value * 2)
+    }
+
+    #[binmod(name = "calls")]
+    fn call_count(&self) -> Result<i64, String> {
+        Ok(self.calls.load(Ordering::SeqCst))
+    }
+
+    #[binmod(skip)]
+    fn reset(&self) {
+        self.calls.store(0, Ordering::SeqCst);
+    }
+
+    fn helper() -> i64 {
+        0
+    }
+}
+
+#[derive(Deserialize)]
+struct ScaleOptions {
+    factor: i64,
+}
+
+struct Scaler;
+
+#[host_functions(namespace = "math")]
+impl Scaler {
+    #[binmod(name = "double")]
+    fn scale(&self, value: i64, #[binmod(kwargs)] options: ScaleOptions) -> Result<i64, String> {
+        Ok(value * options.factor)
+    }
+}
+
+#[test]
+fn generates_host_fn_set() {
+    let doubler = Arc::new(Doubler::default());
+    doubler.reset();
+    assert_eq!(Doubler::helper(), 0);
+
+    let set = doubler.host_fn_set();
+    let mut names: Vec<_> = set.names().collect();
+    names.sort();
+
+    assert_eq!(names, ["calls", "double"]);
+    assert_eq!(set.namespace(), None);
+    assert_eq!(Arc::new(Scaler).host_fn_set().namespace(), Some("math"));
+}
+
+#[test]
+fn calls_host_functions_of_shared_instance() {
+    let doubler = Arc::new(Doubler::default());
+    let mut module = common::guest()
+        .with_host_fns(doubler.clone())
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    assert_eq!(module.typed_call::<i64>("call_double", (21,)).unwrap(), 42);
+    assert_eq!(doubler.calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn passes_kwargs_to_host_function() {
+    let mut module = Module::builder()
+        .from_wat(common::GUEST.replace(r#"(import "env""#, r#"(import "math""#))
+        .unwrap()
+        .with_name("guest")
+        .with_host_fns(Arc::new(Scaler))
+        .build()
+        .unwrap()
+        .instantiate()
+        .unwrap();
+
+    let input = FnInput::new().with_arg(21).unwrap().with_kwarg("factor", 3).unwrap();
+    assert_eq!(module.call("call_double", input).unwrap().into_result::<i64>().unwrap(), 63);
+
+    let input = FnInput::new().with_arg(21).unwrap();
+    assert!(module.call("call_double", input).unwrap().is_error());
+}
+
+#[test]
+fn describes_host_functions() {
+    let schema = common::guest()
+        .with_host_fns(Arc::new(Doubler::default()))
+        .with_host_fns(Arc::new(Scaler))
+        .describe_host_fns();
+
+    let double = &schema.imports["env"]["double"];
+    assert_eq!(double.description.as_deref(), Some("Double a value."));
+    assert_eq!(double.args[0].name.as_deref(), Some("value"));
+    assert!(schema.imports["env"]["calls"].args.is_empty());
+
+    // Sets generated without `schema` are described with an empty schema
+    assert_eq!(schema.imports["math"]["double"], FnSchema::default());
+}